@@ -1,3 +1,4 @@
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 #[derive(Debug)]
 pub enum PowerSupplyErrorCodes {
     FailedToOpenFile,
@@ -34,3 +35,29 @@ impl PowerSupplyError {
         PowerSupplyError { code, message }
     }
 }
+
+impl ErrorCode for PowerSupplyErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_battery_ctrl"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            PowerSupplyErrorCodes::FailedToOpenFile | PowerSupplyErrorCodes::FailedToReadFile => {
+                ErrorKind::Unavailable
+            }
+            PowerSupplyErrorCodes::InvalidDataFormat => ErrorKind::Internal,
+            PowerSupplyErrorCodes::UnknownError => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl DomainError for PowerSupplyError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{PowerSupplyError, PowerSupplyErrorCodes};
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use std::fs::{self, File};
 use std::io::Read;
 use tracing::{error as trace_error, info, trace, warn};
@@ -25,7 +25,7 @@ pub struct PowerSupply {
 pub trait PowerSupplyInfo {
     fn info(&self) -> Result<PowerSupply>;
     fn set_device(&mut self, device: &str) -> Result<()>;
-    fn get_device(&self) -> Result<&str>;
+    fn get_device(&self) -> Result<String>;
     fn get_current(&self) -> Result<i64>;
 }
 
@@ -37,11 +37,24 @@ pub struct Battery {
 
 impl PowerSupplyInfo for Battery {
     fn info(&self) -> Result<PowerSupply> {
-        trace!(task = "battery_info", "init");
-        info!("Battery info");
-        let mut file = File::open(&self.path).unwrap();
+        trace!(task = "info", "init");
+        info!(task = "info", "battery info");
+        // the io error stays in the chain, e.g. to report permission denied
+        let mut file = File::open(&self.path).with_context(|| {
+            trace_error!(task = "info", "unable to open {}", self.path);
+            PowerSupplyError::new(
+                PowerSupplyErrorCodes::FailedToOpenFile,
+                format!("unable to open {}", self.path),
+            )
+        })?;
         let mut contents = String::new();
-        file.read_to_string(&mut contents).unwrap();
+        file.read_to_string(&mut contents).with_context(|| {
+            trace_error!(task = "info", "unable to read {}", self.path);
+            PowerSupplyError::new(
+                PowerSupplyErrorCodes::FailedToReadFile,
+                format!("unable to read {}", self.path),
+            )
+        })?;
 
         let mut power_supply = PowerSupply {
             name: String::new(),
@@ -92,9 +105,9 @@ impl PowerSupplyInfo for Battery {
     fn set_device(&mut self, device: &str) -> Result<()> {
         //try to set path or return error
         trace!(task = "set_device", "init");
-        info!(task = "set_devide", "set power device path");
+        info!(task = "set_device", "set power device path");
         if device.is_empty() {
-            trace_error!("Device path is empty");
+            trace_error!(task = "set_device", "device path is empty");
             bail!(PowerSupplyError::new(
                 PowerSupplyErrorCodes::FailedToOpenFile,
                 "failed to set device".to_string(),
@@ -104,22 +117,32 @@ impl PowerSupplyInfo for Battery {
         Ok(())
     }
 
-    fn get_device(&self) -> Result<&str> {
+    fn get_device(&self) -> Result<String> {
         if self.path.is_empty() {
-            trace_error!(task = "get_device_path", "Device path is empty");
+            trace_error!(task = "get_device", "Device path is empty");
             bail!(PowerSupplyError::new(
                 PowerSupplyErrorCodes::FailedToOpenFile,
                 "Device path is empty".to_string(),
             ));
         }
-        Ok(&self.path)
+        Ok(self.path.clone())
     }
 
     //to get current_now value read file from current_now path
     fn get_current(&self) -> Result<i64> {
-        let mut file = fs::File::open(&self.currnet_now)?;
+        let mut file = fs::File::open(&self.currnet_now).with_context(|| {
+            PowerSupplyError::new(
+                PowerSupplyErrorCodes::FailedToOpenFile,
+                format!("unable to open {}", self.currnet_now),
+            )
+        })?;
         let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
+        file.read_to_string(&mut contents).with_context(|| {
+            PowerSupplyError::new(
+                PowerSupplyErrorCodes::FailedToReadFile,
+                format!("unable to read {}", self.currnet_now),
+            )
+        })?;
         let current_now = match contents.trim().parse::<i64>() {
             Ok(value) => value,
             Err(_) => {
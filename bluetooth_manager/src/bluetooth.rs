@@ -1,21 +1,32 @@
 use crate::errors::{BluetoothError, BluetoothErrorCodes};
 use anyhow::{bail, Result};
+use async_trait::async_trait;
 use bluer::Session;
 use tracing::{error as trace_error, info, trace};
+
 //allow dead_code
 #[allow(dead_code)]
-
 pub struct BluetoothController {
     session: Session,
 }
 
+#[async_trait]
+pub trait BluetoothControl {
+    async fn bluetooth_status(&self) -> Result<bool>;
+    async fn enable_bluetooth(&self) -> Result<()>;
+    async fn disable_bluetooth(&self) -> Result<()>;
+}
+
 impl BluetoothController {
     pub async fn new() -> Result<Self> {
         let session = Session::new().await?;
         Ok(Self { session })
     }
+}
 
-    pub async fn bluetooth_status(&self) -> Result<bool> {
+#[async_trait]
+impl BluetoothControl for BluetoothController {
+    async fn bluetooth_status(&self) -> Result<bool> {
         trace!(task = "bluetooth_status", "init");
         let adapter = self.session.default_adapter().await?;
         let powered = match adapter.is_powered().await {
@@ -35,7 +46,7 @@ impl BluetoothController {
         Ok(powered)
     }
 
-    pub async fn enable_bluetooth(&self) -> Result<()> {
+    async fn enable_bluetooth(&self) -> Result<()> {
         trace!(task = "enable_bluetooth", "init");
         let adapter = self.session.default_adapter().await?;
         match adapter.set_powered(true).await {
@@ -57,7 +68,7 @@ impl BluetoothController {
         }
     }
 
-    pub async fn disable_bluetooth(&self) -> Result<()> {
+    async fn disable_bluetooth(&self) -> Result<()> {
         trace!(task = "disable_bluetooth", "init");
         let adapter = self.session.default_adapter().await?;
         match adapter.set_powered(false).await {
@@ -1,3 +1,4 @@
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 #[derive(Debug, Default, Clone, Copy)]
 pub enum BluetoothErrorCodes {
     #[default]
@@ -47,3 +48,31 @@ impl BluetoothError {
         BluetoothError { code, message }
     }
 }
+
+impl ErrorCode for BluetoothErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_bluetooth_manager"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            BluetoothErrorCodes::NoBluetoothDeviceFound => ErrorKind::NotFound,
+            BluetoothErrorCodes::UnableToTurnOnBluetooth
+            | BluetoothErrorCodes::UnableToTurnOffBluetooth
+            | BluetoothErrorCodes::UnableToConnectToBluetoothDevice
+            | BluetoothErrorCodes::UnableToDisconnectFromBluetoothDevice
+            | BluetoothErrorCodes::UnableToGetBluetoothDeviceStatus => ErrorKind::Unavailable,
+            BluetoothErrorCodes::Unknown => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl DomainError for BluetoothError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
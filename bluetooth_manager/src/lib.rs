@@ -1,6 +1,6 @@
 #![deny(clippy::all)]
 mod bluetooth;
-pub use bluetooth::BluetoothController;
+pub use bluetooth::{BluetoothControl, BluetoothController};
 
 mod errors;
-pub use errors::{BluetoothError, BluetoothErrorCodes};
\ No newline at end of file
+pub use errors::{BluetoothError, BluetoothErrorCodes};
@@ -1,4 +1,4 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use std::fs::{read_to_string, File};
 use std::io::Write;
 use tracing::{error as trace_error, info, trace, warn};
@@ -17,39 +17,90 @@ pub struct CpuCtrl {
     pub cpu_frequency_path: String,
 }
 
+pub trait CpuControl {
+    // `governor` has to be one of `scaling_available_governors`, e.g. userspace
+    fn set_cpu_governor(&self, governor: &str) -> Result<()>;
+    fn get_cpu_governor(&self) -> Result<String>;
+    fn get_cpu_frequency(&self) -> Result<String>;
+    fn set_cpu_frequency(&self, frequency: CpuFrequency) -> Result<()>;
+}
+
+impl Default for CpuCtrl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CpuCtrl {
     pub fn new() -> Self {
-        trace!(tack = "cpuctrl instace", "init");
+        Self::with_path("/sys/devices/system/cpu/cpu0/cpufreq")
+    }
+
+    // cpufreq directory of the policy to control, e.g. for a simulated sysfs tree
+    pub fn with_path(path: &str) -> Self {
+        trace!(task = "with_path", "init");
         CpuCtrl {
-            cpu_frequency_path: String::from("/sys/devices/system/cpu/cpu0/cpufreq"),
+            cpu_frequency_path: String::from(path),
         }
     }
+}
 
-    pub fn set_cpu_governor(&self) -> Result<()> {
+impl CpuControl for CpuCtrl {
+    fn set_cpu_governor(&self, governor: &str) -> Result<()> {
         trace!(task = "set_cpu_governor", "init");
+        let available = match read_to_string(format!(
+            "{}/scaling_available_governors",
+            self.cpu_frequency_path
+        )) {
+            Ok(available) => available,
+            Err(e) => {
+                let message = format!("failed to read available CPU governors: {}", e);
+                return Err(e).context(CpuCtrlError::new(
+                    CpuCtrlErrorCodes::FailedToSetCpuGovernorPath,
+                    message,
+                ));
+            }
+        };
+        if !available.split_whitespace().any(|name| name == governor) {
+            bail!(CpuCtrlError::new(
+                CpuCtrlErrorCodes::InvalidCpuGovernor,
+                format!(
+                    "invalid CPU governor {}, available: {}",
+                    governor,
+                    available.trim()
+                ),
+            ));
+        }
+
         let mut file = match File::create(format!("{}/scaling_governor", self.cpu_frequency_path)) {
-            Ok(file) => {
-                info!(task = "set_cpu_governor", "set cpu governor to userspace");
-                file
+            Ok(file) => file,
+            Err(e) => {
+                let message = format!("failed to set CPU governor: {}", e);
+                return Err(e).context(CpuCtrlError::new(
+                    CpuCtrlErrorCodes::FailedToSetCpuGovernorPath,
+                    message,
+                ));
             }
-            Err(e) => bail!(CpuCtrlError::new(
-                CpuCtrlErrorCodes::FailedToSetCpuGovernorPath,
-                format!("failed to set CPU governor: {}", e)
-            )),
         };
-        match file.write_all(b"userspace") {
+        match file.write_all(governor.as_bytes()) {
             Ok(_) => {
-                info!(task = "set_cpu_governor", "set cpu governor to userspace");
+                info!(
+                    task = "set_cpu_governor",
+                    "set cpu governor to {}", governor
+                );
                 Ok(())
             }
-            Err(e) => bail!(CpuCtrlError::new(
-                CpuCtrlErrorCodes::FailedToSetCpuGovernor,
-                format!("failed to set CPU governor: {}", e)
-            )),
+            Err(e) => {
+                let message = format!("failed to set CPU governor: {}", e);
+                Err(e).context(CpuCtrlError::new(
+                    CpuCtrlErrorCodes::FailedToSetCpuGovernor,
+                    message,
+                ))
+            }
         }
     }
 
-    pub fn get_cpu_governor(&self) -> Result<String> {
+    fn get_cpu_governor(&self) -> Result<String> {
         match read_to_string(format!("{}/scaling_governor", self.cpu_frequency_path)) {
             Ok(content) => {
                 info!(task = "get_cpu_governor", "get cpu governor: {}", content);
@@ -61,15 +112,16 @@ impl CpuCtrl {
                     "failed to get CPU governor: {}",
                     e
                 );
-                bail!(CpuCtrlError::new(
+                let message = format!("failed to get CPU governor: {}", e);
+                Err(e).context(CpuCtrlError::new(
                     CpuCtrlErrorCodes::FailedToGetCpuGovernor,
-                    format!("failed to get CPU governor: {}", e)
+                    message,
                 ))
             }
         }
     }
 
-    pub fn get_cpu_frequency(&self) -> Result<String> {
+    fn get_cpu_frequency(&self) -> Result<String> {
         match read_to_string(format!("{}/scaling_cur_freq", self.cpu_frequency_path)) {
             Ok(content) => Ok(content),
             Err(e) => {
@@ -78,15 +130,16 @@ impl CpuCtrl {
                     "failed to get CPU frequency: {}",
                     e
                 );
-                bail!(CpuCtrlError::new(
+                let message = format!("failed to get CPU frequency: {}", e);
+                Err(e).context(CpuCtrlError::new(
                     CpuCtrlErrorCodes::FailedToGetCpuFrequency,
-                    format!("failed to get CPU frequency: {}", e)
+                    message,
                 ))
             }
         }
     }
 
-    pub fn set_cpu_frequency(&self, frequency: CpuFrequency) -> Result<()> {
+    fn set_cpu_frequency(&self, frequency: CpuFrequency) -> Result<()> {
         let freq_str = match frequency {
             CpuFrequency::Freq1200000 => "1200000",
             CpuFrequency::Freq1600000 => "1600000",
@@ -107,10 +160,11 @@ impl CpuCtrl {
                     "failed to set CPU frequency: {}",
                     e
                 );
-                bail!(CpuCtrlError::new(
+                let message = format!("failed to set CPU frequency: {}", e);
+                return Err(e).context(CpuCtrlError::new(
                     CpuCtrlErrorCodes::FailedToSetCpuFrequencyPath,
-                    format!("failed to set CPU frequency: {}", e)
-                ))
+                    message,
+                ));
             }
         };
         match file.write_all(freq_str.as_bytes()) {
@@ -126,9 +180,10 @@ impl CpuCtrl {
                     task = "set_cpu_frequency",
                     "failed to set CPU frequency: {}", e
                 );
-                bail!(CpuCtrlError::new(
+                let message = format!("failed to set CPU frequency: {}", e);
+                Err(e).context(CpuCtrlError::new(
                     CpuCtrlErrorCodes::FailedToSetCpuFrequency,
-                    format!("failed to set CPU frequency: {}", e)
+                    message,
                 ))
             }
         }
@@ -1,7 +1,9 @@
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 #[derive(Debug)]
 pub enum CpuCtrlErrorCodes {
     FailedToSetCpuGovernor,
     FailedToSetCpuGovernorPath,
+    InvalidCpuGovernor,
     FailedToGetCpuGovernor,
     FailedToGetCpuFrequency,
     FailedToSetCpuFrequency,
@@ -20,6 +22,7 @@ impl std::fmt::Display for CpuCtrlErrorCodes {
             CpuCtrlErrorCodes::FailedToSetCpuGovernorPath => {
                 write!(f, "FailedToSetCpuGovernorPath")
             }
+            CpuCtrlErrorCodes::InvalidCpuGovernor => write!(f, "InvalidCpuGovernor"),
             CpuCtrlErrorCodes::FailedToGetCpuGovernor => write!(f, "FailedToGetCpuGovernor"),
             CpuCtrlErrorCodes::FailedToGetCpuFrequency => write!(f, "FailedToGetCpuFrequency"),
             CpuCtrlErrorCodes::FailedToSetCpuFrequency => write!(f, "FailedToSetCpuFrequency"),
@@ -52,3 +55,35 @@ impl CpuCtrlError {
         CpuCtrlError { code, message }
     }
 }
+
+impl ErrorCode for CpuCtrlErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_cpu_governor_ctrl"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            CpuCtrlErrorCodes::FailedToSetCpuGovernorPath
+            | CpuCtrlErrorCodes::FailedToSetCpuFrequencyPath
+            | CpuCtrlErrorCodes::FailedToOpenFile => ErrorKind::Unavailable,
+            CpuCtrlErrorCodes::InvalidCpuGovernor => ErrorKind::InvalidArgument,
+            CpuCtrlErrorCodes::FailedToSetCpuGovernor
+            | CpuCtrlErrorCodes::FailedToGetCpuGovernor
+            | CpuCtrlErrorCodes::FailedToGetCpuFrequency
+            | CpuCtrlErrorCodes::FailedToSetCpuFrequency
+            | CpuCtrlErrorCodes::FailedToWriteToFile
+            | CpuCtrlErrorCodes::FailedToReadFile => ErrorKind::Internal,
+            CpuCtrlErrorCodes::UnknownError => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl DomainError for CpuCtrlError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
@@ -1,7 +1,7 @@
 #![deny(clippy::all)]
 
 mod cpu_ctrl;
-pub use cpu_ctrl::{CpuCtrl,CpuFrequency};
+pub use cpu_ctrl::{CpuControl, CpuCtrl, CpuFrequency};
 
 mod errors;
 pub use errors::{CpuCtrlError, CpuCtrlErrorCodes};
\ No newline at end of file
@@ -129,7 +129,7 @@ impl DeviceInfo {
         }
 
         if disks.is_empty() {
-            warn!("failed to get disk info");
+            warn!(task = "get_disk_info", "failed to get disk info");
             bail!(DeviceInfoError::new(
                 DeviceInfoErrorCodes::FailedToGetDiskUsage,
                 "failed to get disk info".to_string(),
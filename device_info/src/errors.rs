@@ -1,3 +1,4 @@
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 
 #[derive(Debug)]
 pub enum DeviceInfoErrorCodes {
@@ -37,3 +38,28 @@ impl DeviceInfoError {
         DeviceInfoError { code, message }
     }
 }
+
+impl ErrorCode for DeviceInfoErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_device_info"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            DeviceInfoErrorCodes::FailedToGetCpuUsage
+            | DeviceInfoErrorCodes::FailedToGetMemoryUsage
+            | DeviceInfoErrorCodes::FailedToGetDiskUsage => ErrorKind::Internal,
+            DeviceInfoErrorCodes::UnknownError => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl DomainError for DeviceInfoError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
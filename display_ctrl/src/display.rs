@@ -10,15 +10,22 @@ pub struct DisplayCtrl {
     pub path: String,
 }
 
+pub trait DisplayControl {
+    fn set_display_brightness(&self, brightness: u8) -> Result<()>;
+    fn get_display_brightness(&self) -> Result<u8>;
+}
+
 impl DisplayCtrl {
     pub fn new(path: &str) -> Self {
-        trace!(task = "display_ctrl instance", "init");
+        trace!(task = "new", "init");
         DisplayCtrl {
             path: String::from(path),
         }
     }
+}
 
-    pub fn set_display_brightness(&self, brightness: u8) -> Result<()> {
+impl DisplayControl for DisplayCtrl {
+    fn set_display_brightness(&self, brightness: u8) -> Result<()> {
         trace!(task = "set_display_brightness", "init");
         // Check if the brightness value is valid
         if brightness > 244 {
@@ -61,7 +68,7 @@ impl DisplayCtrl {
         Ok(())
     }
 
-    pub fn get_display_brightness(&self) -> Result<u8> {
+    fn get_display_brightness(&self) -> Result<u8> {
         trace!(task = "get_display_brightness", "init");
         let file = File::open(&self.path).with_context(|| {
             trace_error!(
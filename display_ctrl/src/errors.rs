@@ -1,3 +1,4 @@
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 use std::fmt;
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -39,3 +40,26 @@ impl DisplayError {
         DisplayError { code, message }
     }
 }
+
+impl ErrorCode for DisplayErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_display_ctrl"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            DisplayErrorCodes::InvalidBrightnessValueError => ErrorKind::InvalidArgument,
+            DisplayErrorCodes::InvalidBrightnessPathError => ErrorKind::Unavailable,
+        }
+    }
+}
+
+impl DomainError for DisplayError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
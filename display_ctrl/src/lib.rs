@@ -4,4 +4,4 @@ mod errors;
 pub use errors::{DisplayError, DisplayErrorCodes};
 
 mod display;
-pub use display::DisplayCtrl;
+pub use display::{DisplayControl, DisplayCtrl};
@@ -0,0 +1,48 @@
+use std::fmt;
+
+// how a failure should be reported to a caller, independent of the crate it
+// comes from. each maps to one grpc status code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    // the request carries a value the device can not accept
+    InvalidArgument,
+    // the device, file or network asked for does not exist
+    NotFound,
+    // the hardware or the daemon behind it can not be reached right now
+    Unavailable,
+    // the server process is not allowed to touch the device
+    PermissionDenied,
+    // the device is not in a state that allows the operation
+    FailedPrecondition,
+    // the device answered with something unexpected
+    Internal,
+    Unknown,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::InvalidArgument => write!(f, "InvalidArgument"),
+            ErrorKind::NotFound => write!(f, "NotFound"),
+            ErrorKind::Unavailable => write!(f, "Unavailable"),
+            ErrorKind::PermissionDenied => write!(f, "PermissionDenied"),
+            ErrorKind::FailedPrecondition => write!(f, "FailedPrecondition"),
+            ErrorKind::Internal => write!(f, "Internal"),
+            ErrorKind::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+// implemented by the `XErrorCodes` enum of every device crate. the display
+// name of a code is its machine readable reason, e.g. InvalidBrightnessValueError
+pub trait ErrorCode: fmt::Display {
+    // the crate the code belongs to, e.g. mecha_display_ctrl
+    fn domain(&self) -> &'static str;
+    fn kind(&self) -> ErrorKind;
+}
+
+// implemented by the `XError { code, message }` struct of every device crate
+pub trait DomainError: fmt::Debug + fmt::Display + Send + Sync + 'static {
+    fn code(&self) -> &dyn ErrorCode;
+    fn message(&self) -> &str;
+}
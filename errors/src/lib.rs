@@ -0,0 +1,9 @@
+#![deny(clippy::all)]
+
+mod kind;
+pub use kind::{DomainError, ErrorCode, ErrorKind};
+
+#[cfg(feature = "grpc")]
+mod status;
+#[cfg(feature = "grpc")]
+pub use status::{error_info, status, ErrorInfo, ERROR_INFO_TYPE_URL};
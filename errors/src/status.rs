@@ -0,0 +1,78 @@
+use prost::Message;
+use prost_types::Any;
+use std::collections::HashMap;
+use tonic::{Code, Status};
+
+use crate::{DomainError, ErrorKind};
+
+pub const ERROR_INFO_TYPE_URL: &str = "type.googleapis.com/google.rpc.ErrorInfo";
+
+// google.rpc.ErrorInfo
+#[derive(Clone, PartialEq, Message)]
+pub struct ErrorInfo {
+    #[prost(string, tag = "1")]
+    pub reason: String,
+    #[prost(string, tag = "2")]
+    pub domain: String,
+    #[prost(map = "string, string", tag = "3")]
+    pub metadata: HashMap<String, String>,
+}
+
+// google.rpc.Status, carried in the grpc-status-details-bin trailer
+#[derive(Clone, PartialEq, Message)]
+struct RpcStatus {
+    #[prost(int32, tag = "1")]
+    code: i32,
+    #[prost(string, tag = "2")]
+    message: String,
+    #[prost(message, repeated, tag = "3")]
+    details: Vec<Any>,
+}
+
+impl From<ErrorKind> for Code {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::InvalidArgument => Code::InvalidArgument,
+            ErrorKind::NotFound => Code::NotFound,
+            ErrorKind::Unavailable => Code::Unavailable,
+            ErrorKind::PermissionDenied => Code::PermissionDenied,
+            ErrorKind::FailedPrecondition => Code::FailedPrecondition,
+            ErrorKind::Internal => Code::Internal,
+            ErrorKind::Unknown => Code::Unknown,
+        }
+    }
+}
+
+// a status for `error` with an ErrorInfo naming its domain and code. `kind`
+// is usually `error.code().kind()`, callers may override it when they know
+// more about the failure
+pub fn status(kind: ErrorKind, error: &dyn DomainError) -> Status {
+    let code = Code::from(kind);
+    let info = ErrorInfo {
+        reason: error.code().to_string(),
+        domain: error.code().domain().to_string(),
+        metadata: HashMap::new(),
+    };
+    let details = RpcStatus {
+        code: code as i32,
+        message: error.message().to_string(),
+        details: vec![Any {
+            type_url: ERROR_INFO_TYPE_URL.to_string(),
+            value: info.encode_to_vec(),
+        }],
+    };
+    Status::with_details(code, error.message(), details.encode_to_vec().into())
+}
+
+// the ErrorInfo attached to a status, if any
+pub fn error_info(status: &Status) -> Option<ErrorInfo> {
+    if status.details().is_empty() {
+        return None;
+    }
+    let details = RpcStatus::decode(status.details()).ok()?;
+    details
+        .details
+        .iter()
+        .find(|any| any.type_url == ERROR_INFO_TYPE_URL)
+        .and_then(|any| ErrorInfo::decode(any.value.as_slice()).ok())
+}
@@ -0,0 +1,70 @@
+use mecha_errors::{error_info, status, DomainError, ErrorCode, ErrorKind};
+use std::fmt;
+use tonic::Code;
+
+#[derive(Debug)]
+enum FakeErrorCodes {
+    OutOfRange,
+}
+
+impl fmt::Display for FakeErrorCodes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FakeErrorCodes::OutOfRange => write!(f, "OutOfRange"),
+        }
+    }
+}
+
+impl ErrorCode for FakeErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_fake"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            FakeErrorCodes::OutOfRange => ErrorKind::InvalidArgument,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FakeError {
+    code: FakeErrorCodes,
+    message: String,
+}
+
+impl fmt::Display for FakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(code: {:?}, message: {})", self.code, self.message)
+    }
+}
+
+impl DomainError for FakeError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+#[test]
+fn status_carries_the_code_and_error_info() {
+    let error = FakeError {
+        code: FakeErrorCodes::OutOfRange,
+        message: "value out of range".to_string(),
+    };
+    let status = status(error.code().kind(), &error);
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "value out of range");
+
+    let info = error_info(&status).unwrap();
+    assert_eq!(info.domain, "mecha_fake");
+    assert_eq!(info.reason, "OutOfRange");
+}
+
+#[test]
+fn plain_statuses_have_no_error_info() {
+    assert!(error_info(&tonic::Status::unknown("boom")).is_none());
+}
@@ -1,3 +1,4 @@
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 #[derive(Debug)]
 pub enum LedCtrlErrorCodes {
     InvalidLedColorError,
@@ -34,3 +35,26 @@ impl LedCtrlError {
         LedCtrlError { code, message }
     }
 }
+
+impl ErrorCode for LedCtrlErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_led_ctrl"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            LedCtrlErrorCodes::InvalidLedColorError => ErrorKind::InvalidArgument,
+            LedCtrlErrorCodes::InvalidLedPathValueError => ErrorKind::Unavailable,
+        }
+    }
+}
+
+impl DomainError for LedCtrlError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
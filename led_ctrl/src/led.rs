@@ -1,5 +1,5 @@
 use crate::errors::{LedCtrlError, LedCtrlErrorCodes};
-use anyhow::{bail, Result};
+use anyhow::{Context, Result};
 use std::fs::File;
 use std::io::{self, Write};
 use tracing::{error as trace_error, info, trace};
@@ -18,10 +18,15 @@ pub struct LedCtrl {
     blue_led_path: String,
 }
 
+pub trait LedControl {
+    fn set_led(&self, color: LedColor) -> Result<()>;
+    fn clear_led(&self, color: LedColor) -> Result<()>;
+}
+
 impl LedCtrl {
     // Constructor for LedCtrl
     pub fn new(red_led_path: &str, green_led_path: &str, blue_led_path: &str) -> Self {
-        trace!(task = "led_ctrl instance", "init");
+        trace!(task = "new", "init");
         LedCtrl {
             red_led_path: String::from(red_led_path),
             green_led_path: String::from(green_led_path),
@@ -29,8 +34,18 @@ impl LedCtrl {
         }
     }
 
+    // Private function to write to the brightness file with error handling
+    fn write_brightness(&self, path: &str, value: &str) -> Result<(), io::Error> {
+        trace!(task = "write_brightness", "init");
+        let mut file = File::create(path)?;
+        file.write_all(value.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl LedControl for LedCtrl {
     // Function to set the LED based on the specified color
-    pub fn set_led(&self, color: LedColor) -> Result<()> {
+    fn set_led(&self, color: LedColor) -> Result<()> {
         trace!(task = "set_led", "init");
         //check if color is valid or not if not return error
         let path = match color {
@@ -42,9 +57,11 @@ impl LedCtrl {
         //try to write the brightness value to the file or return an error
         if let Err(e) = self.write_brightness(path, "1") {
             trace_error!(task = "set_led", "unable to write brightness value: {}", e);
-            bail!(LedCtrlError::new(
+            // the io error stays in the chain, e.g. to report permission denied
+            let message = format!("unable to write brightness value: {}", e);
+            return Err(e).context(LedCtrlError::new(
                 LedCtrlErrorCodes::InvalidLedPathValueError,
-                format!("unable to write brightness value: {}", e),
+                message,
             ));
         }
         info!(task = "set_led", "set led to {:?}", color);
@@ -53,7 +70,7 @@ impl LedCtrl {
     }
 
     // Function to clear the LED (set brightness to 0) based on the specified color
-    pub fn clear_led(&self, color: LedColor) -> Result<()> {
+    fn clear_led(&self, color: LedColor) -> Result<()> {
         trace!(task = "clear_led", "init");
         let path = match color {
             LedColor::Red => &self.red_led_path,
@@ -68,20 +85,13 @@ impl LedCtrl {
                 "unable to write brightness value: {}",
                 e
             );
-            bail!(LedCtrlError::new(
+            let message = format!("unable to read brightness value: {}", e);
+            return Err(e).context(LedCtrlError::new(
                 LedCtrlErrorCodes::InvalidLedPathValueError,
-                format!("unable to read brightness value: {}", e),
+                message,
             ));
         }
         info!(task = "clear_led", "clear led {:?}", color);
         Ok(())
     }
-
-    // Private function to write to the brightness file with error handling
-    fn write_brightness(&self, path: &str, value: &str) -> Result<(), io::Error> {
-        trace!(task = "write_brightness", "init");
-        let mut file = File::create(path)?;
-        file.write_all(value.as_bytes())?;
-        Ok(())
-    }
 }
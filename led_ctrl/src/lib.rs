@@ -1,6 +1,6 @@
 #![deny(clippy::all)]
 mod led;
-pub use led::{LedCtrl, LedColor};
+pub use led::{LedColor, LedControl, LedCtrl};
 
 mod errors;
-pub use errors::{LedCtrlError, LedCtrlErrorCodes};
\ No newline at end of file
+pub use errors::{LedCtrlError, LedCtrlErrorCodes};
@@ -0,0 +1,353 @@
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use mecha_sdk_client::proto::trustzonectrl::{KeySize, KeyType};
+use mecha_sdk_client::{ClientConfig, RetryPolicy};
+
+// every option can also be set through its MECHACTL_* environment variable
+#[derive(Debug, Parser)]
+#[command(
+    name = "mechactl",
+    version,
+    about = "Command line client for the Mecha edge gRPC server"
+)]
+pub struct Cli {
+    #[command(flatten)]
+    pub target: TargetArgs,
+
+    /// Print the response as JSON instead of a table
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Args)]
+pub struct TargetArgs {
+    /// Server address: http://host:port, https://host:port, host:port or unix:/path
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "MECHACTL_ENDPOINT",
+        default_value = "http://127.0.0.1:50052"
+    )]
+    pub endpoint: String,
+
+    /// Bearer token sent with every call
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "MECHACTL_TOKEN",
+        hide_env_values = true
+    )]
+    pub token: Option<String>,
+
+    /// CA certificate the server certificate is verified against, for https endpoints
+    #[arg(long, global = true, env = "MECHACTL_CA_CERT")]
+    pub ca_cert: Option<PathBuf>,
+
+    /// Seconds to wait for the connection and for each call
+    #[arg(long, global = true, env = "MECHACTL_TIMEOUT", default_value = "10")]
+    pub timeout: u64,
+}
+
+impl TargetArgs {
+    // a single attempt per command, failures are reported right away
+    pub fn config(&self) -> Result<ClientConfig> {
+        let timeout = Duration::from_secs(self.timeout);
+        let mut config = ClientConfig::new(self.endpoint.as_str())
+            .connect_timeout(timeout)
+            .timeout(timeout)
+            .retry(RetryPolicy::none());
+        if let Some(token) = &self.token {
+            config = config.token(token.as_str());
+        }
+        if let Some(ca_cert) = &self.ca_cert {
+            let pem = std::fs::read(ca_cert)
+                .with_context(|| format!("unable to read {}", ca_cert.display()))?;
+            config = config.ca_certificate(pem);
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Display backlight
+    #[command(subcommand)]
+    Display(DisplayCommand),
+    /// RGB leds
+    #[command(subcommand)]
+    Led(LedCommand),
+    /// Motion sensor
+    #[command(subcommand)]
+    Motion(MotionCommand),
+    /// Battery fuel gauge
+    #[command(subcommand)]
+    Battery(BatteryCommand),
+    /// CPU governor and frequency
+    #[command(subcommand)]
+    Cpu(CpuCommand),
+    /// Memory, disk and CPU information
+    #[command(subcommand)]
+    Device(DeviceCommand),
+    /// CPU, memory and disk usage
+    #[command(subcommand)]
+    Metrics(MetricsCommand),
+    /// Wi-Fi networks
+    #[command(subcommand)]
+    Wifi(WifiCommand),
+    /// Bluetooth adapter
+    #[command(subcommand)]
+    Bluetooth(BluetoothCommand),
+    /// OPTIGA TrustZone certificates, keys and signatures
+    #[command(subcommand)]
+    Trustzone(TrustzoneCommand),
+    /// Device events as they happen
+    #[command(subcommand)]
+    Events(EventsCommand),
+    /// Board profile, registered services and supported hardware
+    Capabilities,
+    /// Settings restored when the server starts
+    #[command(subcommand)]
+    Settings(SettingsCommand),
+    /// Rules applied on device state changes
+    #[command(subcommand)]
+    Automation(AutomationCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DisplayCommand {
+    /// Current brightness
+    Get,
+    /// Set the brightness
+    Set { brightness: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum LedCommand {
+    /// Turn a led on
+    Set { color: Color },
+    /// Turn a led off
+    Clear { color: Color },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MotionCommand {
+    /// Current x, y and z values
+    Read,
+    /// Whether the sensor detects motion
+    Detect,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BatteryCommand {
+    /// Power supply properties
+    Info,
+    /// Power supply device in use
+    Device,
+    /// Switch to another power supply device
+    SetDevice { path: String },
+    /// Current draw
+    Current,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CpuCommand {
+    /// Current scaling governor
+    Governor,
+    /// Set the scaling governor
+    SetGovernor { governor: String },
+    /// Current frequency
+    Frequency,
+    /// Set the frequency
+    SetFrequency { frequency: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DeviceCommand {
+    /// Total, available and free memory
+    Memory,
+    /// Mounted disks
+    Disk,
+    /// CPU model, frequency and cores
+    Cpu,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MetricsCommand {
+    /// CPU usage
+    Cpu,
+    /// Memory usage
+    Memory,
+    /// Disk usage
+    Disk,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WifiCommand {
+    /// Networks in range
+    Scan,
+    /// Networks saved in wpa_supplicant
+    Known,
+    /// Connect to a network
+    Connect {
+        ssid: String,
+        /// Pre-shared key, prefer the environment variable over the command line
+        #[arg(long, env = "MECHACTL_PSK", hide_env_values = true)]
+        psk: String,
+    },
+    /// Remove a saved network
+    Disconnect { network_id: i32 },
+    /// Whether wifi is on
+    Status,
+    /// Network currently connected to
+    Current,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BluetoothCommand {
+    /// Whether the adapter is powered
+    Status,
+    /// Power the adapter on
+    Enable,
+    /// Power the adapter off
+    Disable,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EventsCommand {
+    /// Print events until interrupted
+    Watch {
+        /// Only these topics: battery, wifi, motion, display, bluetooth
+        #[arg(long = "topic")]
+        topics: Vec<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SettingsCommand {
+    /// Saved settings
+    Get {
+        /// Only keys starting with it, e.g. led.
+        #[arg(long, default_value = "")]
+        prefix: String,
+    },
+    /// Forget saved settings, every one when no key is given
+    Reset { keys: Vec<String> },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AutomationCommand {
+    /// Configured rules and their state
+    List,
+    /// Enable a rule until the server restarts
+    Enable { name: String },
+    /// Disable a rule until the server restarts
+    Disable { name: String },
+    /// Check a rule against the latest readings without applying it
+    DryRun { name: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrustzoneCommand {
+    /// Read a certificate from the secure element
+    ReadCert {
+        #[arg(long)]
+        region: String,
+        #[arg(long)]
+        output_file: String,
+    },
+    /// Write a certificate to a data object
+    WriteCert {
+        cert_file: String,
+        #[arg(long)]
+        oid: String,
+    },
+    /// Remove the certificate from a data object
+    RemoveCert { oid: String },
+    /// Generate a key pair in a key slot
+    GenerateKey {
+        #[arg(long)]
+        oid: String,
+        /// auth, enc, hfwu, devm, sign or agmt
+        #[arg(long, value_parser = key_type)]
+        key_type: KeyType,
+        /// ecc256, ecc384, ecc521, brainpool256, brainpool384 or brainpool512
+        #[arg(long, value_parser = key_size)]
+        key_size: KeySize,
+        #[arg(long)]
+        output_file: String,
+    },
+    /// Sign a file with a key slot
+    Sign {
+        #[arg(long)]
+        key_oid: String,
+        #[arg(long)]
+        input_file: String,
+        #[arg(long)]
+        output_file: String,
+        /// Hash the input before signing
+        #[arg(long)]
+        hash: bool,
+    },
+    /// Verify a signature with a public key
+    Verify {
+        #[arg(long)]
+        pubkey_file: String,
+        #[arg(long)]
+        input_file: String,
+        #[arg(long)]
+        signature_file: String,
+        /// Hash the input before verifying
+        #[arg(long)]
+        hash: bool,
+    },
+    /// Derive a key from a shared secret
+    DeriveKey {
+        #[arg(long)]
+        secret_oid: String,
+        #[arg(long)]
+        hkdf_type: u32,
+        #[arg(long)]
+        info_file: String,
+        #[arg(long)]
+        salt_file: String,
+        #[arg(long)]
+        output_file: String,
+    },
+    /// HMAC of the input data with a shared secret
+    Hmac {
+        #[arg(long)]
+        secret_oid: String,
+        #[arg(long)]
+        hmac_type: u32,
+        #[arg(long)]
+        input_data: String,
+        #[arg(long)]
+        output_file: String,
+    },
+}
+
+// the proto enums spell their values in upper case
+fn key_type(value: &str) -> Result<KeyType, String> {
+    KeyType::from_str_name(&value.to_uppercase())
+        .ok_or_else(|| format!("unknown key type {}", value))
+}
+
+fn key_size(value: &str) -> Result<KeySize, String> {
+    KeySize::from_str_name(&value.to_uppercase())
+        .ok_or_else(|| format!("unknown key size {}", value))
+}
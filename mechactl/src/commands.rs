@@ -0,0 +1,355 @@
+use anyhow::{anyhow, Result};
+use mecha_sdk_client::Connection;
+use serde::Serialize;
+use serde_json::Value;
+use tonic::{Response, Status};
+
+use crate::cli::{
+    AutomationCommand, BatteryCommand, BluetoothCommand, Color, Command, CpuCommand, DeviceCommand,
+    DisplayCommand, EventsCommand, LedCommand, MetricsCommand, MotionCommand, SettingsCommand,
+    TrustzoneCommand, WifiCommand,
+};
+use mecha_sdk_client::proto::automation::{
+    automation_service_client::AutomationServiceClient, ListRulesRequest, RuleRequest,
+};
+use mecha_sdk_client::proto::battery::{
+    self, power_supply_service_client::PowerSupplyServiceClient,
+};
+use mecha_sdk_client::proto::bluetooth::{self, bluetooth_service_client::BluetoothServiceClient};
+use mecha_sdk_client::proto::capabilities::{
+    device_capabilities_service_client::DeviceCapabilitiesServiceClient, GetCapabilitiesRequest,
+};
+use mecha_sdk_client::proto::cpugovernorctrl::{
+    self, cpu_governor_ctrl_service_client::CpuGovernorCtrlServiceClient,
+};
+use mecha_sdk_client::proto::deviceinfo::{
+    self, device_info_service_client::DeviceInfoServiceClient,
+};
+use mecha_sdk_client::proto::displaymanager::{
+    display_ctrl_service_client::DisplayCtrlServiceClient, GetBrightnessRequest,
+    SetBrightnessRequest,
+};
+use mecha_sdk_client::proto::events::{event_service_client::EventServiceClient, SubscribeRequest};
+use mecha_sdk_client::proto::led_ctrl::{self, led_ctrl_service_client::LedCtrlServiceClient};
+use mecha_sdk_client::proto::metrics::{self, metrics_service_client::MetricsServiceClient};
+use mecha_sdk_client::proto::motionsensor::{
+    self, motion_sensor_service_client::MotionSensorServiceClient,
+};
+use mecha_sdk_client::proto::networkmanager::{
+    self, network_manager_service_client::NetworkManagerServiceClient,
+};
+use mecha_sdk_client::proto::settings::{
+    settings_service_client::SettingsServiceClient, GetSettingsRequest, ResetSettingsRequest,
+};
+use mecha_sdk_client::proto::trustzonectrl::{
+    self, trust_zone_ctrl_service_client::TrustZoneCtrlServiceClient,
+};
+
+// call the rpc a command maps to, the response as json
+pub async fn call(command: Command, connection: Connection) -> Result<Value> {
+    match command {
+        Command::Display(command) => display(command, connection).await,
+        Command::Led(command) => led(command, connection).await,
+        Command::Motion(command) => motion(command, connection).await,
+        Command::Battery(command) => battery(command, connection).await,
+        Command::Cpu(command) => cpu(command, connection).await,
+        Command::Device(command) => device(command, connection).await,
+        Command::Metrics(command) => metrics(command, connection).await,
+        Command::Wifi(command) => wifi(command, connection).await,
+        Command::Bluetooth(command) => bluetooth(command, connection).await,
+        Command::Trustzone(command) => trustzone(command, connection).await,
+        Command::Events(_) => Err(anyhow!("events are streamed, use watch")),
+        Command::Capabilities => json(
+            DeviceCapabilitiesServiceClient::new(connection)
+                .get_capabilities(GetCapabilitiesRequest {})
+                .await,
+        ),
+        Command::Settings(command) => settings(command, connection).await,
+        Command::Automation(command) => automation(command, connection).await,
+    }
+}
+
+// subscribe and hand every event to `print` until the server ends the stream
+pub async fn watch(
+    command: EventsCommand,
+    connection: Connection,
+    mut print: impl FnMut(&Value),
+) -> Result<()> {
+    let EventsCommand::Watch { topics } = command;
+    let mut client = EventServiceClient::new(connection);
+    let mut events = match client.subscribe(SubscribeRequest { topics }).await {
+        Ok(response) => response.into_inner(),
+        Err(status) => return Err(failed(status)),
+    };
+    loop {
+        match events.message().await {
+            Ok(Some(event)) => print(&serde_json::to_value(event)?),
+            Ok(None) => return Ok(()),
+            Err(status) => return Err(failed(status)),
+        }
+    }
+}
+
+fn json<T: Serialize>(response: Result<Response<T>, Status>) -> Result<Value> {
+    match response {
+        Ok(response) => Ok(serde_json::to_value(response.into_inner())?),
+        Err(status) => Err(failed(status)),
+    }
+}
+
+fn failed(status: Status) -> anyhow::Error {
+    if status.message().is_empty() {
+        anyhow!("{:?}: {}", status.code(), status.code().description())
+    } else {
+        anyhow!("{:?}: {}", status.code(), status.message())
+    }
+}
+
+async fn display(command: DisplayCommand, connection: Connection) -> Result<Value> {
+    let mut client = DisplayCtrlServiceClient::new(connection);
+    match command {
+        DisplayCommand::Get => json(client.get_brightness(GetBrightnessRequest {}).await),
+        DisplayCommand::Set { brightness } => json(
+            client
+                .set_brightness(SetBrightnessRequest { brightness })
+                .await,
+        ),
+    }
+}
+
+async fn led(command: LedCommand, connection: Connection) -> Result<Value> {
+    let mut client = LedCtrlServiceClient::new(connection);
+    let request = |color: Color| {
+        let color = match color {
+            Color::Red => led_ctrl::led_color::Color::Red,
+            Color::Green => led_ctrl::led_color::Color::Green,
+            Color::Blue => led_ctrl::led_color::Color::Blue,
+        };
+        led_ctrl::LedColor {
+            color: color as i32,
+        }
+    };
+    match command {
+        LedCommand::Set { color } => json(client.set_led(request(color)).await),
+        LedCommand::Clear { color } => json(client.clear_led(request(color)).await),
+    }
+}
+
+async fn motion(command: MotionCommand, connection: Connection) -> Result<Value> {
+    let mut client = MotionSensorServiceClient::new(connection);
+    match command {
+        MotionCommand::Read => json(client.read_value(motionsensor::Empty {}).await),
+        MotionCommand::Detect => json(client.detect_motion(motionsensor::Empty {}).await),
+    }
+}
+
+async fn battery(command: BatteryCommand, connection: Connection) -> Result<Value> {
+    let mut client = PowerSupplyServiceClient::new(connection);
+    match command {
+        BatteryCommand::Info => json(client.get_power_supply_info(battery::Empty {}).await),
+        BatteryCommand::Device => json(client.get_device(battery::Empty {}).await),
+        BatteryCommand::SetDevice { path } => json(
+            client
+                .set_device(battery::SetDeviceRequest { device_path: path })
+                .await,
+        ),
+        BatteryCommand::Current => json(client.get_current(battery::Empty {}).await),
+    }
+}
+
+async fn cpu(command: CpuCommand, connection: Connection) -> Result<Value> {
+    let mut client = CpuGovernorCtrlServiceClient::new(connection);
+    match command {
+        CpuCommand::Governor => json(client.get_governor(cpugovernorctrl::Empty {}).await),
+        CpuCommand::SetGovernor { governor } => json(
+            client
+                .set_governor(cpugovernorctrl::GovernorRequest { governor })
+                .await,
+        ),
+        CpuCommand::Frequency => json(client.get_cpu_frequency(cpugovernorctrl::Empty {}).await),
+        CpuCommand::SetFrequency { frequency } => json(
+            client
+                .set_cpu_frequency(cpugovernorctrl::CpuFrequencyRequest { frequency })
+                .await,
+        ),
+    }
+}
+
+async fn settings(command: SettingsCommand, connection: Connection) -> Result<Value> {
+    let mut client = SettingsServiceClient::new(connection);
+    match command {
+        SettingsCommand::Get { prefix } => {
+            json(client.get_settings(GetSettingsRequest { prefix }).await)
+        }
+        SettingsCommand::Reset { keys } => {
+            json(client.reset_settings(ResetSettingsRequest { keys }).await)
+        }
+    }
+}
+
+async fn automation(command: AutomationCommand, connection: Connection) -> Result<Value> {
+    let mut client = AutomationServiceClient::new(connection);
+    match command {
+        AutomationCommand::List => json(client.list_rules(ListRulesRequest {}).await),
+        AutomationCommand::Enable { name } => json(client.enable_rule(RuleRequest { name }).await),
+        AutomationCommand::Disable { name } => {
+            json(client.disable_rule(RuleRequest { name }).await)
+        }
+        AutomationCommand::DryRun { name } => json(client.dry_run_rule(RuleRequest { name }).await),
+    }
+}
+
+async fn device(command: DeviceCommand, connection: Connection) -> Result<Value> {
+    let mut client = DeviceInfoServiceClient::new(connection);
+    match command {
+        DeviceCommand::Memory => json(client.get_memory_info(deviceinfo::Empty {}).await),
+        DeviceCommand::Disk => json(client.get_disk_info(deviceinfo::Empty {}).await),
+        DeviceCommand::Cpu => json(client.get_cpu_info(deviceinfo::Empty {}).await),
+    }
+}
+
+async fn metrics(command: MetricsCommand, connection: Connection) -> Result<Value> {
+    let mut client = MetricsServiceClient::new(connection);
+    match command {
+        MetricsCommand::Cpu => json(client.get_cpu_usage(metrics::Empty {}).await),
+        MetricsCommand::Memory => json(client.get_memory_usage(metrics::Empty {}).await),
+        MetricsCommand::Disk => json(client.get_disk_usage(metrics::Empty {}).await),
+    }
+}
+
+async fn wifi(command: WifiCommand, connection: Connection) -> Result<Value> {
+    let mut client = NetworkManagerServiceClient::new(connection);
+    match command {
+        WifiCommand::Scan => json(client.scan_wireless_network(networkmanager::Empty {}).await),
+        WifiCommand::Known => json(
+            client
+                .scan_known_wireless_network(networkmanager::Empty {})
+                .await,
+        ),
+        WifiCommand::Connect { ssid, psk } => json(
+            client
+                .connect_wireless_network(networkmanager::WifiConnectRequest { ssid, psk })
+                .await,
+        ),
+        WifiCommand::Disconnect { network_id } => json(
+            client
+                .disconnect_wireless_network(networkmanager::RemoveNetworkRequest { network_id })
+                .await,
+        ),
+        WifiCommand::Status => json(client.get_wifi_status(networkmanager::Empty {}).await),
+        WifiCommand::Current => json(client.get_current_network(networkmanager::Empty {}).await),
+    }
+}
+
+async fn bluetooth(command: BluetoothCommand, connection: Connection) -> Result<Value> {
+    let mut client = BluetoothServiceClient::new(connection);
+    match command {
+        BluetoothCommand::Status => json(client.get_bluetooth_status(bluetooth::Empty {}).await),
+        BluetoothCommand::Enable => json(client.enable_bluetooth(bluetooth::Empty {}).await),
+        BluetoothCommand::Disable => json(client.disable_bluetooth(bluetooth::Empty {}).await),
+    }
+}
+
+async fn trustzone(command: TrustzoneCommand, connection: Connection) -> Result<Value> {
+    let mut client = TrustZoneCtrlServiceClient::new(connection);
+    match command {
+        TrustzoneCommand::ReadCert {
+            region,
+            output_file,
+        } => json(
+            client
+                .read_certification(trustzonectrl::ReadCertificationRequest {
+                    output_file,
+                    region,
+                })
+                .await,
+        ),
+        TrustzoneCommand::WriteCert { cert_file, oid } => json(
+            client
+                .write_certificate(trustzonectrl::WriteCertificateRequest { cert_file, oid })
+                .await,
+        ),
+        TrustzoneCommand::RemoveCert { oid } => json(
+            client
+                .remove_certificate(trustzonectrl::RemoveCertificateRequest { oid })
+                .await,
+        ),
+        TrustzoneCommand::GenerateKey {
+            oid,
+            key_type,
+            key_size,
+            output_file,
+        } => json(
+            client
+                .generate_key(trustzonectrl::GenerateKeyRequest {
+                    oid,
+                    key_type: key_type as i32,
+                    key_size: key_size as i32,
+                    output_file,
+                })
+                .await,
+        ),
+        TrustzoneCommand::Sign {
+            key_oid,
+            input_file,
+            output_file,
+            hash,
+        } => json(
+            client
+                .sign_data(trustzonectrl::SignDataRequest {
+                    key_oid,
+                    input_file,
+                    output_file,
+                    hash_before_sign: hash,
+                })
+                .await,
+        ),
+        TrustzoneCommand::Verify {
+            pubkey_file,
+            input_file,
+            signature_file,
+            hash,
+        } => json(
+            client
+                .verify_data(trustzonectrl::VerifyDataRequest {
+                    pubkey_file,
+                    input_file,
+                    signature_file,
+                    hash_before_verify: hash,
+                })
+                .await,
+        ),
+        TrustzoneCommand::DeriveKey {
+            secret_oid,
+            hkdf_type,
+            info_file,
+            salt_file,
+            output_file,
+        } => json(
+            client
+                .derive_key(trustzonectrl::DeriveKeyRequest {
+                    secret_oid,
+                    hkdf_type,
+                    info_file,
+                    salt_file,
+                    output_file,
+                })
+                .await,
+        ),
+        TrustzoneCommand::Hmac {
+            secret_oid,
+            hmac_type,
+            input_data,
+            output_file,
+        } => json(
+            client
+                .generate_hmac(trustzonectrl::GenerateHmacRequest {
+                    secret_oid,
+                    hmac_type,
+                    input_data,
+                    output_file,
+                })
+                .await,
+        ),
+    }
+}
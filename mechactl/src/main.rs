@@ -0,0 +1,36 @@
+use anyhow::Result;
+use clap::Parser;
+use mecha_sdk_client::MechaClient;
+
+use mechactl::cli::{Cli, Command};
+use mechactl::commands::{call, watch};
+use mechactl::output::{render, Format};
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let client = MechaClient::connect(cli.target.config()?).await?;
+    let format = if cli.json {
+        Format::Json
+    } else {
+        Format::Table
+    };
+    if let Command::Events(command) = cli.command {
+        // one json document per line, or a table per event
+        return watch(command, client.connection(), |event| match format {
+            Format::Json => println!("{}", serde_json::to_string(event).unwrap_or_default()),
+            Format::Table => println!("{}\n", render(event, format)),
+        })
+        .await;
+    }
+    let response = call(cli.command, client.connection()).await?;
+    println!("{}", render(&response, format));
+    Ok(())
+}
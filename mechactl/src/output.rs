@@ -0,0 +1,103 @@
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+}
+
+pub fn render(value: &Value, format: Format) -> String {
+    match format {
+        Format::Json => serde_json::to_string_pretty(value).unwrap_or_default(),
+        Format::Table => table(unwrap(value)),
+    }
+}
+
+// responses such as `{"disk_info": [...]}` hold a single message or list,
+// print that instead of the wrapper
+fn unwrap(value: &Value) -> &Value {
+    match value {
+        Value::Object(fields) if fields.len() == 1 => match fields.values().next() {
+            Some(inner @ (Value::Object(_) | Value::Array(_))) => inner,
+            _ => value,
+        },
+        _ => value,
+    }
+}
+
+fn table(value: &Value) -> String {
+    match value {
+        Value::Object(fields) if fields.is_empty() => "ok".to_string(),
+        Value::Object(fields) => {
+            let mut rows = Vec::new();
+            flatten("", fields, &mut rows);
+            let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+            rows.iter()
+                .map(|(key, value)| format!("{:width$}  {}", key, value, width = width))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        Value::Array(items) if items.is_empty() => "(none)".to_string(),
+        Value::Array(items) => columns(items),
+        value => scalar(value),
+    }
+}
+
+// nested messages become `parent.field` rows
+fn flatten(prefix: &str, fields: &Map<String, Value>, rows: &mut Vec<(String, String)>) {
+    for (name, value) in fields {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        match value {
+            Value::Object(fields) => flatten(&key, fields, rows),
+            value => rows.push((key, scalar(value))),
+        }
+    }
+}
+
+// one row per item, the fields of the first item are the columns
+fn columns(items: &[Value]) -> String {
+    let header = match items.first() {
+        Some(Value::Object(fields)) => fields.keys().cloned().collect::<Vec<_>>(),
+        _ => return items.iter().map(scalar).collect::<Vec<_>>().join("\n"),
+    };
+    let mut rows = vec![header
+        .iter()
+        .map(|name| name.to_uppercase())
+        .collect::<Vec<_>>()];
+    for item in items {
+        rows.push(
+            header
+                .iter()
+                .map(|name| item.get(name).map(scalar).unwrap_or_default())
+                .collect(),
+        );
+    }
+
+    let widths = (0..header.len())
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,68 @@
+use clap::Parser;
+use mecha_sdk_client::proto::trustzonectrl::{KeySize, KeyType};
+use mechactl::cli::{Cli, Color, Command, LedCommand, TrustzoneCommand};
+use mechactl::output::{render, Format};
+use serde_json::json;
+
+#[test]
+fn messages_render_as_key_value_rows() {
+    let value = json!({"memory_info": {"total_memory": 4096, "free_memory": 512}});
+    assert_eq!(
+        render(&value, Format::Table),
+        "total_memory  4096\nfree_memory   512"
+    );
+    assert_eq!(render(&json!({}), Format::Table), "ok");
+    assert_eq!(
+        render(&value, Format::Json),
+        serde_json::to_string_pretty(&value).unwrap()
+    );
+}
+
+#[test]
+fn lists_render_as_tables() {
+    let value = json!({"results": [
+        {"mac": "aa:bb", "signal": -40, "name": "office"},
+        {"mac": "cc:dd", "signal": -71, "name": "guest"},
+    ]});
+    assert_eq!(
+        render(&value, Format::Table),
+        "MAC    SIGNAL  NAME\naa:bb  -40     office\ncc:dd  -71     guest"
+    );
+    assert_eq!(render(&json!({"results": []}), Format::Table), "(none)");
+}
+
+#[test]
+fn subcommands_parse_into_requests() {
+    let cli = Cli::parse_from(["mechactl", "led", "set", "green", "--json"]);
+    assert!(cli.json);
+    assert!(matches!(
+        cli.command,
+        Command::Led(LedCommand::Set {
+            color: Color::Green
+        })
+    ));
+
+    let cli = Cli::parse_from([
+        "mechactl",
+        "trustzone",
+        "generate-key",
+        "--oid",
+        "0xE0F1",
+        "--key-type",
+        "sign",
+        "--key-size",
+        "ecc384",
+        "--output-file",
+        "/tmp/key.pem",
+    ]);
+    match cli.command {
+        Command::Trustzone(TrustzoneCommand::GenerateKey {
+            key_type, key_size, ..
+        }) => {
+            assert_eq!(key_type, KeyType::Sign);
+            assert_eq!(key_size, KeySize::Ecc384);
+        }
+        command => panic!("unexpected command {:?}", command),
+    }
+    assert!(Cli::try_parse_from(["mechactl", "led", "set", "purple"]).is_err());
+}
@@ -1,4 +1,4 @@
-
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 #[derive(Debug)]
 pub enum DeviceMetricsErrorCodes {
     UnknownError,
@@ -37,3 +37,28 @@ impl DeviceMetricsError {
         DeviceMetricsError { code, message }
     }
 }
+
+impl ErrorCode for DeviceMetricsErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_metrics"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            DeviceMetricsErrorCodes::FailedToGetCpuUsage
+            | DeviceMetricsErrorCodes::FailedToGetMemoryUsage
+            | DeviceMetricsErrorCodes::FailedToGetDiskUsage => ErrorKind::Internal,
+            DeviceMetricsErrorCodes::UnknownError => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl DomainError for DeviceMetricsError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
@@ -1,6 +1,6 @@
 #![deny(clippy::all)]
 mod metrics;
-pub use metrics::DeviceMetrics;
+pub use metrics::{DeviceMetrics, DeviceMetricsInfo};
 
 mod errors;
-pub use errors::{DeviceMetricsError, DeviceMetricsErrorCodes};
\ No newline at end of file
+pub use errors::{DeviceMetricsError, DeviceMetricsErrorCodes};
@@ -8,15 +8,32 @@ pub struct DeviceMetrics {
     system: System,
 }
 
+pub trait DeviceMetricsInfo {
+    fn get_cpu_usage(&self) -> Result<f32>;
+    fn get_memory_usage(&self) -> Result<u64>;
+    fn get_disk_usage(&self) -> Result<u64>;
+    // take a new sample, readings are otherwise those of the last refresh
+    fn refresh(&mut self) {}
+}
+
 impl DeviceMetrics {
     pub fn new() -> Self {
-        trace!(task = "device_metrics instance", "init");
+        trace!(task = "new", "init");
         let mut system = System::new_all();
         system.refresh_all();
         DeviceMetrics { system }
     }
+}
+
+impl DeviceMetricsInfo for DeviceMetrics {
+    fn refresh(&mut self) {
+        trace!(task = "refresh", "init");
+        self.system.refresh_cpu();
+        self.system.refresh_memory();
+        self.system.refresh_disks();
+    }
 
-    pub fn get_cpu_usage(&self) -> Result<f32> {
+    fn get_cpu_usage(&self) -> Result<f32> {
         trace!(task = "get_cpu_usage", "init");
         match self.system.global_cpu_info().cpu_usage() {
             cpu_usage => {
@@ -34,7 +51,7 @@ impl DeviceMetrics {
         }
     }
 
-    pub fn get_memory_usage(&self) -> Result<u64> {
+    fn get_memory_usage(&self) -> Result<u64> {
         trace!(task = "get_memory_usage", "init");
         match self.system.used_memory() {
             memory_usage => {
@@ -51,7 +68,7 @@ impl DeviceMetrics {
         }
     }
 
-    pub fn get_disk_usage(&self) -> Result<u64> {
+    fn get_disk_usage(&self) -> Result<u64> {
         trace!(task = "get_disk_usage", "init");
         //take primary disk
         match self.system.disks().iter().take(1).next() {
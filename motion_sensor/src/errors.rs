@@ -1,3 +1,4 @@
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 #[derive(Debug)]
 pub enum MotionSensorErrorCodes {
     NoMotionDetected,
@@ -36,3 +37,29 @@ impl MotionSensorError {
         MotionSensorError { code, message }
     }
 }
+
+impl ErrorCode for MotionSensorErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_motion_sensor"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            MotionSensorErrorCodes::NoMotionDetected => ErrorKind::NotFound,
+            MotionSensorErrorCodes::UnableToReadMotionSensor
+            | MotionSensorErrorCodes::UnableToOpenFile => ErrorKind::Unavailable,
+            MotionSensorErrorCodes::UnableToParseValue => ErrorKind::Internal,
+            MotionSensorErrorCodes::Unknown => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl DomainError for MotionSensorError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
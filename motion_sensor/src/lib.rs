@@ -1,6 +1,6 @@
 #![deny(clippy::all)]
 mod motion_sensor;
-pub use motion_sensor::MotionSensor;
+pub use motion_sensor::{MotionSensor, MotionSensorControl};
 
 mod errors;
 pub use errors::{MotionSensorError, MotionSensorErrorCodes};
@@ -1,5 +1,5 @@
 use crate::errors::{MotionSensorError, MotionSensorErrorCodes};
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use std::default::Default;
 use std::fs::File;
 use tracing::{error as trace_error, info, trace};
@@ -14,9 +14,14 @@ pub struct MotionSensor {
     z_axis_path: String,
 }
 
+pub trait MotionSensorControl {
+    fn read_motion_sensor_value(&self) -> Result<(f64, f64, f64)>;
+    fn detect_motion_sensor_event(&self) -> Result<bool>;
+}
+
 impl MotionSensor {
     pub fn new(x_path: &str, y_path: &str, z_path: &str) -> Self {
-        trace!(task = "motion_sensor instance", "init");
+        trace!(task = "new", "init");
         MotionSensor {
             x_axis_path: String::from(x_path),
             y_axis_path: String::from(y_path),
@@ -24,59 +29,6 @@ impl MotionSensor {
         }
     }
 
-    pub fn read_motion_sensor_value(&self) -> Result<(f64, f64, f64)> {
-        trace!(task = "read_motion_sensor_value", "init");
-        //read x,y,z values from the motion sensor or error using match and anyhow error
-        let (x_value, y_value, z_value) = match (
-            self.read_value_from_file(&self.x_axis_path),
-            self.read_value_from_file(&self.y_axis_path),
-            self.read_value_from_file(&self.z_axis_path),
-        ) {
-            (Ok(x), Ok(y), Ok(z)) => {
-                info!(
-                    task = "read_motion_sensor_value",
-                    "x: {}, y: {}, z: {}", x, y, z
-                );
-                (x, y, z)
-            }
-            (Err(e), _, _) => {
-                trace_error!(
-                    task = "read_motion_sensor_value",
-                    "unable to read x axis value: {}",
-                    e
-                );
-                bail!(MotionSensorError::new(
-                    MotionSensorErrorCodes::UnableToReadMotionSensor,
-                    format!("unable to read x axis value: {}", e),
-                ))
-            }
-            (_, Err(e), _) => {
-                trace_error!(
-                    task = "read_motion_sensor_value",
-                    "unable to read y axis value: {}",
-                    e
-                );
-                bail!(MotionSensorError::new(
-                    MotionSensorErrorCodes::UnableToReadMotionSensor,
-                    format!("unable to read y axis value: {}", e),
-                ))
-            }
-            (_, _, Err(e)) => {
-                trace_error!(
-                    task = "read_motion_sensor_value",
-                    "unable to read z axis value: {}",
-                    e
-                );
-                bail!(MotionSensorError::new(
-                    MotionSensorErrorCodes::UnableToReadMotionSensor,
-                    format!("unable to read z axis value: {}", e),
-                ))
-            }
-        };
-
-        Ok((x_value, y_value, z_value))
-    }
-
     fn read_value_from_file(&self, path: &str) -> Result<f64> {
         trace!(task = "read_value_from_file", "init");
         let file = match File::open(path) {
@@ -93,10 +45,11 @@ impl MotionSensor {
                     "unable to open file value: {}",
                     e
                 );
-                bail!(MotionSensorError::new(
+                let message = format!("unable to open file value: {}", e);
+                return Err(e).context(MotionSensorError::new(
                     MotionSensorErrorCodes::UnableToOpenFile,
-                    format!("unable to open file value: {}", e),
-                ))
+                    message,
+                ));
             }
         };
 
@@ -135,10 +88,11 @@ impl MotionSensor {
                     "unable to parce sensor_value_string from buffer: {}",
                     e
                 );
-                bail!(MotionSensorError::new(
+                let message = format!("unable to parse value: {}", e);
+                return Err(e).context(MotionSensorError::new(
                     MotionSensorErrorCodes::UnableToParseValue,
-                    format!("unable to parse value: {}", e),
-                ))
+                    message,
+                ));
             }
         };
 
@@ -156,10 +110,11 @@ impl MotionSensor {
                     "unable to parse sansor_value : {}",
                     e
                 );
-                bail!(MotionSensorError::new(
+                let message = format!("unable to parse value: {}", e);
+                return Err(e).context(MotionSensorError::new(
                     MotionSensorErrorCodes::UnableToParseValue,
-                    format!("unable to parse value: {}", e),
-                ))
+                    message,
+                ));
             }
         };
 
@@ -182,10 +137,11 @@ impl MotionSensor {
                     "unable to open file value: {}",
                     e
                 );
-                bail!(MotionSensorError::new(
+                let message = format!("unable to open file value: {}", e);
+                return Err(e).context(MotionSensorError::new(
                     MotionSensorErrorCodes::UnableToOpenFile,
-                    format!("unable to open file value: {}", e),
-                ))
+                    message,
+                ));
             }
         };
 
@@ -193,10 +149,11 @@ impl MotionSensor {
         let _ = match write!(file, "{}", value) {
             Ok(file) => file,
             Err(e) => {
-                bail!(MotionSensorError::new(
+                let message = format!("unable to write data to sensor {}", e);
+                return Err(e).context(MotionSensorError::new(
                     MotionSensorErrorCodes::UnableToParseValue,
-                    format!("unable to write data to sensor {}", e),
-                ))
+                    message,
+                ));
             }
         };
 
@@ -207,8 +164,66 @@ impl MotionSensor {
 
         Ok(())
     }
+}
 
-    pub fn detect_motion_sensor_event(&self) -> Result<bool> {
+impl MotionSensorControl for MotionSensor {
+    fn read_motion_sensor_value(&self) -> Result<(f64, f64, f64)> {
+        trace!(task = "read_motion_sensor_value", "init");
+        //read x,y,z values from the motion sensor or error using match and anyhow error
+        let (x_value, y_value, z_value) = match (
+            self.read_value_from_file(&self.x_axis_path),
+            self.read_value_from_file(&self.y_axis_path),
+            self.read_value_from_file(&self.z_axis_path),
+        ) {
+            (Ok(x), Ok(y), Ok(z)) => {
+                info!(
+                    task = "read_motion_sensor_value",
+                    "x: {}, y: {}, z: {}", x, y, z
+                );
+                (x, y, z)
+            }
+            (Err(e), _, _) => {
+                trace_error!(
+                    task = "read_motion_sensor_value",
+                    "unable to read x axis value: {}",
+                    e
+                );
+                let message = format!("unable to read x axis value: {}", e);
+                return Err(e).context(MotionSensorError::new(
+                    MotionSensorErrorCodes::UnableToReadMotionSensor,
+                    message,
+                ));
+            }
+            (_, Err(e), _) => {
+                trace_error!(
+                    task = "read_motion_sensor_value",
+                    "unable to read y axis value: {}",
+                    e
+                );
+                let message = format!("unable to read y axis value: {}", e);
+                return Err(e).context(MotionSensorError::new(
+                    MotionSensorErrorCodes::UnableToReadMotionSensor,
+                    message,
+                ));
+            }
+            (_, _, Err(e)) => {
+                trace_error!(
+                    task = "read_motion_sensor_value",
+                    "unable to read z axis value: {}",
+                    e
+                );
+                let message = format!("unable to read z axis value: {}", e);
+                return Err(e).context(MotionSensorError::new(
+                    MotionSensorErrorCodes::UnableToReadMotionSensor,
+                    message,
+                ));
+            }
+        };
+
+        Ok((x_value, y_value, z_value))
+    }
+
+    fn detect_motion_sensor_event(&self) -> Result<bool> {
         trace!(task = "detect_motion_sensor_event", "init");
         let (x_value, y_value, z_value) = match self.read_motion_sensor_value() {
             Ok((x, y, z)) => {
@@ -224,10 +239,11 @@ impl MotionSensor {
                     "unable to read motion sensor value: {}",
                     e
                 );
-                bail!(MotionSensorError::new(
+                let message = format!("unable to read motion sensor value: {}", e);
+                return Err(e).context(MotionSensorError::new(
                     MotionSensorErrorCodes::UnableToReadMotionSensor,
-                    format!("unable to read motion sensor value: {}", e),
-                ))
+                    message,
+                ));
             }
         };
         let mut is_motion_detected = false;
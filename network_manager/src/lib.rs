@@ -1 +1 @@
-pub mod wifi;
\ No newline at end of file
+pub mod wifi;
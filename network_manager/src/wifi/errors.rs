@@ -1,3 +1,4 @@
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 //make a sturct for WifiError and implement the Error trait for it as we did for all the other errors try to includ all the possible error code that you can think of while working with linux and wifi
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -53,3 +54,32 @@ impl WifiError {
         WifiError { code, message }
     }
 }
+
+impl ErrorCode for WifiErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_network_manager"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            WifiErrorCodes::NoWifiDeviceFound => ErrorKind::NotFound,
+            WifiErrorCodes::UnableToTurnOnWifi
+            | WifiErrorCodes::UnableToTurnOffWifi
+            | WifiErrorCodes::UnableToConnectToWifiDevice
+            | WifiErrorCodes::UnableToDisconnectFromWifiDevice
+            | WifiErrorCodes::UnableToGetWifiDeviceStatus
+            | WifiErrorCodes::UnableToRemoveWifiDevice => ErrorKind::Unavailable,
+            WifiErrorCodes::Unknown => ErrorKind::Unknown,
+        }
+    }
+}
+
+impl DomainError for WifiError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
@@ -1,5 +1,7 @@
+#[allow(clippy::module_inception)]
 mod wifi;
-pub use wifi::WifiModule;
+pub use wifi::{WifiControl, WifiModule, WPA_SUPPLICANT_SOCKET};
+pub use wifi_ctrl::sta::{Broadcast, NetworkResult, ScanResult};
 
 mod errors;
 pub use errors::{WifiError, WifiErrorCodes};
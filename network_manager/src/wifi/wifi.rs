@@ -1,17 +1,42 @@
 use crate::wifi::errors::{WifiError, WifiErrorCodes};
 use anyhow::{bail, Result};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
 use std::process::Command;
+use tokio::sync::broadcast;
 use tracing::{error as trace_error, info, trace};
 use wifi_ctrl::sta::{self, NetworkResult, ScanResult};
 
+// control socket of the wpa_supplicant instance managing wlan0
+pub const WPA_SUPPLICANT_SOCKET: &str = "/var/run/wpa_supplicant/wlan0";
+
+// every wpa_supplicant broadcast received by listen_broadcasts, for callers
+// that follow connection changes
+static BROADCASTS: Lazy<broadcast::Sender<sta::Broadcast>> = Lazy::new(|| broadcast::channel(16).0);
+
+#[derive(Debug, Default)]
 pub struct WifiModule;
 
+#[async_trait]
+pub trait WifiControl {
+    fn wifi_status(&self) -> bool;
+    async fn scan_wireless_network(&self) -> Result<Vec<ScanResult>>;
+    async fn get_known_wifi_list(&self) -> Result<Vec<NetworkResult>>;
+    async fn current_wifi_network(&self) -> Result<ScanResult>;
+    async fn connect_wireless_network(&self, ssid: &str, psk: &str) -> Result<()>;
+    async fn remove_wireless_network(&self, network_id: usize) -> Result<()>;
+}
+
 impl WifiModule {
     pub fn new() -> Self {
-        trace!(task = "wifi instance", "init");
+        trace!(task = "new", "init");
         Self
     }
 
+    pub fn subscribe_broadcasts() -> broadcast::Receiver<sta::Broadcast> {
+        BROADCASTS.subscribe()
+    }
+
     pub fn wifi_status() -> bool {
         trace!(task = "wifi_status", "checking wifi status");
         let output = Command::new("ifconfig")
@@ -29,7 +54,7 @@ impl WifiModule {
         trace!(task = "scan_wireless_network", "init");
         let mut setup = match sta::WifiSetup::new() {
             Ok(setup) => {
-                info!(take = "wifi_setup", "wifi setup successful");
+                info!(task = "scan_wireless_network", "wifi setup successful");
                 setup
             }
             Err(e) => {
@@ -45,7 +70,7 @@ impl WifiModule {
             }
         };
 
-        let proposed_path = format!("/var/run/wpa_supplicant/wlan0");
+        let proposed_path = WPA_SUPPLICANT_SOCKET.to_string();
         setup.set_socket_path(proposed_path);
 
         let broadcast = setup.get_broadcast_receiver();
@@ -95,7 +120,7 @@ impl WifiModule {
         trace!(task = "get_known_wifi_list", "starting wifi connection");
         let mut setup = match sta::WifiSetup::new() {
             Ok(setup) => {
-                info!(task = "wifi_setup", "wifi setup successful");
+                info!(task = "get_known_wifi_list", "wifi setup successful");
                 setup
             }
             Err(e) => {
@@ -110,7 +135,7 @@ impl WifiModule {
                 ))
             }
         };
-        let proposed_path = format!("/var/run/wpa_supplicant/wlan0");
+        let proposed_path = WPA_SUPPLICANT_SOCKET.to_string();
         setup.set_socket_path(proposed_path);
 
         let broadcast = setup.get_broadcast_receiver();
@@ -175,7 +200,7 @@ impl WifiModule {
         match current_wifi {
             Some(current_wifi) => Ok(current_wifi.clone()),
             None => {
-                trace_error!(task = "currnet_wifi", "unable to get current wifi network");
+                trace_error!(task = "current_wifi_network", "unable to get current wifi network");
                 bail!(WifiError::new(
                     WifiErrorCodes::UnableToGetWifiDeviceStatus,
                     format!("unable to get current wifi network"),
@@ -192,7 +217,7 @@ impl WifiModule {
 
         let mut setup = match sta::WifiSetup::new() {
             Ok(setup) => {
-                info!(task = "wifi_setup", "wifi setup successful");
+                info!(task = "connect_wireless_network", "wifi setup successful");
                 setup
             }
             Err(e) => {
@@ -208,7 +233,7 @@ impl WifiModule {
             }
         };
 
-        let proposed_path = format!("/var/run/wpa_supplicant/wlan0");
+        let proposed_path = WPA_SUPPLICANT_SOCKET.to_string();
         setup.set_socket_path(proposed_path);
 
         let broadcast = setup.get_broadcast_receiver();
@@ -273,7 +298,7 @@ impl WifiModule {
         //if ssid is in known networks, use that network id to connect else create new network id
         for network in networks {
             if network.ssid == ssid {
-                info!("network id: {}", network.network_id);
+                info!(task = "connect_wifi", "network id: {}", network.network_id);
                 requester.select_network(network.network_id).await?;
                 requester.shutdown().await?;
                 return Ok(());
@@ -354,7 +379,7 @@ impl WifiModule {
             }
         };
 
-        let proposed_path = format!("/var/run/wpa_supplicant/wlan0");
+        let proposed_path = WPA_SUPPLICANT_SOCKET.to_string();
         setup.set_socket_path(proposed_path);
 
         let broadcast = setup.get_broadcast_receiver();
@@ -405,8 +430,77 @@ impl WifiModule {
     async fn broadcast_listener(mut broadcast_receiver: sta::BroadcastReceiver) -> Result<()> {
         trace!(task = "broadcast_listener", "listening for broadcasts");
         while let Ok(broadcast) = broadcast_receiver.recv().await {
-            info!("Broadcast: {:?}", broadcast);
+            info!(task = "broadcast_listener", "broadcast: {:?}", broadcast);
         }
         Ok(())
     }
+
+    // stays attached to the wpa_supplicant control socket and publishes its
+    // broadcasts to subscribe_broadcasts, returns once the connection is lost
+    pub async fn listen_broadcasts(socket: &str) -> Result<()> {
+        trace!(task = "listen_broadcasts", "init");
+        let mut setup = match sta::WifiSetup::new() {
+            Ok(setup) => setup,
+            Err(e) => {
+                trace_error!(
+                    task = "listen_broadcasts",
+                    "unable to get wifi device status: {}",
+                    e
+                );
+                bail!(WifiError::new(
+                    WifiErrorCodes::UnableToGetWifiDeviceStatus,
+                    format!("unable to get wifi device status: {}", e),
+                ))
+            }
+        };
+        setup.set_socket_path(socket.to_string());
+
+        let mut broadcast = setup.get_broadcast_receiver();
+        // the station stops once its request client is dropped
+        let _requester = setup.get_request_client();
+        let runtime = setup.complete();
+
+        let (runtime, _) = tokio::join!(runtime.run(), async move {
+            while let Ok(broadcast) = broadcast.recv().await {
+                info!(task = "listen_broadcasts", "broadcast: {:?}", broadcast);
+                // no subscriber is not an error
+                let _ = BROADCASTS.send(broadcast);
+            }
+        });
+        if let Err(e) = runtime {
+            trace_error!(task = "listen_broadcasts", "error: {}", e);
+            bail!(WifiError::new(
+                WifiErrorCodes::UnableToGetWifiDeviceStatus,
+                format!("unable to listen to wpa_supplicant: {}", e),
+            ))
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl WifiControl for WifiModule {
+    fn wifi_status(&self) -> bool {
+        WifiModule::wifi_status()
+    }
+
+    async fn scan_wireless_network(&self) -> Result<Vec<ScanResult>> {
+        WifiModule::scan_wireless_network(self).await
+    }
+
+    async fn get_known_wifi_list(&self) -> Result<Vec<NetworkResult>> {
+        WifiModule::get_known_wifi_list().await
+    }
+
+    async fn current_wifi_network(&self) -> Result<ScanResult> {
+        WifiModule::current_wifi_network(self).await
+    }
+
+    async fn connect_wireless_network(&self, ssid: &str, psk: &str) -> Result<()> {
+        WifiModule::connect_wireless_network(ssid, psk).await
+    }
+
+    async fn remove_wireless_network(&self, network_id: usize) -> Result<()> {
+        WifiModule::remove_wireless_network(network_id).await
+    }
 }
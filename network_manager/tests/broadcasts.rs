@@ -0,0 +1,49 @@
+use mecha_network_manager::wifi::{Broadcast, WifiModule};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::UnixDatagram;
+
+// a wpa_supplicant control socket that sends `event` to the first client
+// attaching to it
+async fn fake_wpa_supplicant(socket: UnixDatagram, event: &str) {
+    let mut buffer = [0; 64];
+    loop {
+        let (n, client) = socket.recv_from(&mut buffer).await.unwrap();
+        if &buffer[..n] == b"ATTACH" {
+            let client = client.as_pathname().unwrap();
+            socket.send_to(b"OK", client).await.unwrap();
+            socket.send_to(event.as_bytes(), client).await.unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn broadcasts_are_published_without_a_request() {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos();
+    let dir = std::env::temp_dir().join(format!("mecha-wifi-{}-{}", std::process::id(), nanos));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("wlan0");
+    let socket = UnixDatagram::bind(&path).unwrap();
+
+    let mut broadcasts = WifiModule::subscribe_broadcasts();
+    tokio::spawn(fake_wpa_supplicant(
+        socket,
+        "<3>CTRL-EVENT-CONNECTED - Connection to 00:11:22:33:44:55 completed",
+    ));
+    let path = path.to_string_lossy().to_string();
+    let listening = tokio::spawn(async move { WifiModule::listen_broadcasts(&path).await });
+
+    let connected = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Broadcast::Connected = broadcasts.recv().await.unwrap() {
+                break;
+            }
+        }
+    })
+    .await;
+    listening.abort();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(connected.is_ok(), "no connected broadcast");
+}
@@ -0,0 +1,28 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    //the server's proto files, clients only
+    let protos = [
+        "../sdk_server/proto/network_manager.proto",
+        "../sdk_server/proto/display_manager.proto",
+        "../sdk_server/proto/motion_sensor_manager.proto",
+        "../sdk_server/proto/led_manager.proto",
+        "../sdk_server/proto/device_info.proto",
+        "../sdk_server/proto/metrics_manager.proto",
+        "../sdk_server/proto/cpu_governor_ctrl.proto",
+        "../sdk_server/proto/trustzone_ctrl.proto",
+        "../sdk_server/proto/battery_ctrl.proto",
+        "../sdk_server/proto/bluetooth_manager.proto",
+        "../sdk_server/proto/events.proto",
+        "../sdk_server/proto/capabilities.proto",
+        "../sdk_server/proto/settings.proto",
+        "../sdk_server/proto/automation.proto",
+    ];
+
+    tonic_build::configure()
+        .build_server(false)
+        .type_attribute(
+            ".",
+            "#[cfg_attr(feature = \"serde\", derive(serde::Serialize))]",
+        )
+        .compile(&protos, &["../sdk_server/proto"])?;
+    Ok(())
+}
@@ -0,0 +1,293 @@
+use anyhow::{anyhow, bail, Result};
+use mecha_errors::error_info;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::net::UnixStream;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity, Uri};
+use tonic::{Request, Response, Status};
+use tower::service_fn;
+use tracing::{debug, trace};
+
+use crate::services::{
+    AutomationClient, BatteryClient, BluetoothClient, CapabilitiesClient, CpuClient,
+    DeviceInfoClient, DisplayClient, EventsClient, LedClient, MetricsClient, MotionSensorClient,
+    SettingsClient, TrustZoneClient, WifiClient,
+};
+use crate::{ClientConfig, ClientError, ClientErrorCodes, RetryPolicy};
+
+// a channel to the server adding the bearer token to every call, usable with
+// the generated clients in `proto`
+pub type Connection = InterceptedService<Channel, BearerToken>;
+
+#[derive(Debug, Clone, Default)]
+pub struct BearerToken(Option<MetadataValue<Ascii>>);
+
+impl BearerToken {
+    pub fn new(token: Option<&str>) -> Result<Self> {
+        let value = match token.map(|token| format!("Bearer {}", token).parse()) {
+            Some(Ok(value)) => Some(value),
+            Some(Err(_)) => bail!(ClientError::new(
+                ClientErrorCodes::InvalidToken,
+                "token is not a valid header value".to_string()
+            )),
+            None => None,
+        };
+        Ok(BearerToken(value))
+    }
+}
+
+impl Interceptor for BearerToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(value) = &self.0 {
+            request
+                .metadata_mut()
+                .insert("authorization", value.clone());
+        }
+        Ok(request)
+    }
+}
+
+// entry point of the sdk, cheap to clone. the underlying channel reconnects
+// on its own after the server restarts
+#[derive(Debug, Clone)]
+pub struct MechaClient {
+    connection: Connection,
+    timeout: Duration,
+    retry: RetryPolicy,
+}
+
+impl MechaClient {
+    // connect now, failing when the server can not be reached
+    pub async fn connect(config: ClientConfig) -> Result<Self> {
+        trace!(task = "mecha_client", "connect {}", config.endpoint);
+        let endpoint = endpoint(&config)?;
+        let channel = match unix_socket(&config) {
+            Some(path) => {
+                endpoint
+                    .connect_with_connector(service_fn(move |_: Uri| {
+                        UnixStream::connect(path.clone())
+                    }))
+                    .await
+            }
+            None => endpoint.connect().await,
+        };
+        match channel {
+            Ok(channel) => Self::new(channel, &config),
+            Err(e) => bail!(ClientError::new(
+                ClientErrorCodes::ConnectionFailed,
+                format!("unable to connect to {}: {}", config.endpoint, e)
+            )),
+        }
+    }
+
+    // connect on the first call
+    pub fn connect_lazy(config: ClientConfig) -> Result<Self> {
+        let endpoint = endpoint(&config)?;
+        let channel = match unix_socket(&config) {
+            Some(path) => endpoint.connect_with_connector_lazy(service_fn(move |_: Uri| {
+                UnixStream::connect(path.clone())
+            })),
+            None => endpoint.connect_lazy(),
+        };
+        Self::new(channel, &config)
+    }
+
+    fn new(channel: Channel, config: &ClientConfig) -> Result<Self> {
+        Ok(MechaClient {
+            connection: InterceptedService::new(
+                channel,
+                BearerToken::new(config.token.as_deref())?,
+            ),
+            timeout: config.timeout,
+            retry: config.retry,
+        })
+    }
+
+    // the same connection with another deadline
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        MechaClient {
+            timeout,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_retry(&self, retry: RetryPolicy) -> Self {
+        MechaClient {
+            retry,
+            ..self.clone()
+        }
+    }
+
+    pub fn connection(&self) -> Connection {
+        self.connection.clone()
+    }
+
+    pub fn automation(&self) -> AutomationClient {
+        AutomationClient::new(self.clone())
+    }
+
+    pub fn battery(&self) -> BatteryClient {
+        BatteryClient::new(self.clone())
+    }
+
+    pub fn bluetooth(&self) -> BluetoothClient {
+        BluetoothClient::new(self.clone())
+    }
+
+    pub fn capabilities(&self) -> CapabilitiesClient {
+        CapabilitiesClient::new(self.clone())
+    }
+
+    pub fn cpu(&self) -> CpuClient {
+        CpuClient::new(self.clone())
+    }
+
+    pub fn device_info(&self) -> DeviceInfoClient {
+        DeviceInfoClient::new(self.clone())
+    }
+
+    pub fn display(&self) -> DisplayClient {
+        DisplayClient::new(self.clone())
+    }
+
+    pub fn events(&self) -> EventsClient {
+        EventsClient::new(self.clone())
+    }
+
+    pub fn led(&self) -> LedClient {
+        LedClient::new(self.clone())
+    }
+
+    pub fn metrics(&self) -> MetricsClient {
+        MetricsClient::new(self.clone())
+    }
+
+    pub fn motion_sensor(&self) -> MotionSensorClient {
+        MotionSensorClient::new(self.clone())
+    }
+
+    pub fn settings(&self) -> SettingsClient {
+        SettingsClient::new(self.clone())
+    }
+
+    pub fn trustzone(&self) -> TrustZoneClient {
+        TrustZoneClient::new(self.clone())
+    }
+
+    pub fn wifi(&self) -> WifiClient {
+        WifiClient::new(self.clone())
+    }
+
+    pub(crate) fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.set_timeout(self.timeout);
+        request
+    }
+
+    // run `rpc` on a fresh request until it succeeds, fails with a status that
+    // is not retried or runs out of attempts
+    pub(crate) async fn call<T, F, Fut>(
+        &self,
+        name: &str,
+        idempotent: bool,
+        mut rpc: F,
+    ) -> Result<T>
+    where
+        F: FnMut(Connection) -> Fut,
+        Fut: Future<Output = Result<Response<T>, Status>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let status = match rpc(self.connection.clone()).await {
+                Ok(response) => return Ok(response.into_inner()),
+                Err(status) => status,
+            };
+            if attempt >= self.retry.max_attempts || !self.retry.retries(&status, idempotent) {
+                return Err(rpc_failed(name, &status));
+            }
+            let backoff = self.retry.backoff(attempt);
+            debug!(
+                task = "mecha_client",
+                "{} failed with {:?}, attempt {} of {} in {:?}",
+                name,
+                status.code(),
+                attempt + 1,
+                self.retry.max_attempts,
+                backoff
+            );
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+}
+
+pub(crate) fn rpc_failed(name: &str, status: &Status) -> anyhow::Error {
+    let mut error = ClientError::new(
+        ClientErrorCodes::RpcFailed(status.code()),
+        format!("{} failed: {}", name, status.message()),
+    );
+    error.error_info = error_info(status);
+    anyhow!(error)
+}
+
+fn unix_socket(config: &ClientConfig) -> Option<PathBuf> {
+    config.endpoint.strip_prefix("unix:").map(PathBuf::from)
+}
+
+fn endpoint(config: &ClientConfig) -> Result<Endpoint> {
+    // the uri of a unix socket is required by tonic but never dialed
+    let uri = match &config.endpoint {
+        endpoint if endpoint.starts_with("unix:") => "http://localhost".to_string(),
+        endpoint if endpoint.contains("://") => endpoint.clone(),
+        endpoint => format!("http://{}", endpoint),
+    };
+    let mut endpoint = match Endpoint::from_shared(uri) {
+        Ok(endpoint) => endpoint
+            .connect_timeout(config.connect_timeout)
+            .tcp_keepalive(Some(Duration::from_secs(30)))
+            .http2_keep_alive_interval(Duration::from_secs(30))
+            .keep_alive_while_idle(true),
+        Err(e) => bail!(ClientError::new(
+            ClientErrorCodes::InvalidEndpoint,
+            format!("invalid endpoint {}: {}", config.endpoint, e)
+        )),
+    };
+
+    // tls settings only apply to https endpoints
+    if config.endpoint.starts_with("https://") {
+        let mut tls = ClientTlsConfig::new();
+        if let Some(pem) = &config.ca_certificate {
+            tls = tls.ca_certificate(Certificate::from_pem(pem));
+        }
+        if let Some((cert, key)) = &config.identity {
+            tls = tls.identity(Identity::from_pem(cert, key));
+        }
+        if let Some(domain_name) = &config.domain_name {
+            tls = tls.domain_name(domain_name);
+        }
+        endpoint = match endpoint.tls_config(tls) {
+            Ok(endpoint) => endpoint,
+            Err(e) => bail!(ClientError::new(
+                ClientErrorCodes::InvalidTlsConfig,
+                format!("invalid tls configuration: {}", e)
+            )),
+        };
+    }
+    Ok(endpoint)
+}
+
+// one attempt of `$client::$method($message)`, the message is rebuilt for
+// every retry
+macro_rules! rpc {
+    ($mecha:expr, $client:ident :: $method:ident($message:expr), idempotent = $idempotent:expr) => {
+        $mecha.call(stringify!($method), $idempotent, |connection| {
+            let request = $mecha.request($message);
+            async move { $client::new(connection).$method(request).await }
+        })
+    };
+}
+pub(crate) use rpc;
@@ -0,0 +1,137 @@
+use std::error::Error;
+use std::time::Duration;
+use tonic::{Code, Status};
+
+// how and where to reach the server, e.g.
+// `ClientConfig::new("https://board.local:50052").ca_certificate(pem).token("...")`
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    // `http://host:port`, `https://host:port`, `host:port` or `unix:/path`
+    pub endpoint: String,
+    pub token: Option<String>,
+    // pem encoded ca the server certificate is checked against
+    pub ca_certificate: Option<Vec<u8>>,
+    // pem encoded certificate and key for servers requiring mutual tls
+    pub identity: Option<(Vec<u8>, Vec<u8>)>,
+    // name the server certificate is issued for, when it differs from the endpoint host
+    pub domain_name: Option<String>,
+    pub connect_timeout: Duration,
+    // deadline sent with every call
+    pub timeout: Duration,
+    pub retry: RetryPolicy,
+}
+
+impl ClientConfig {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        ClientConfig {
+            endpoint: endpoint.into(),
+            token: None,
+            ca_certificate: None,
+            identity: None,
+            domain_name: None,
+            connect_timeout: Duration::from_secs(5),
+            timeout: Duration::from_secs(10),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn ca_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_certificate = Some(pem.into());
+        self
+    }
+
+    pub fn identity(mut self, cert_pem: impl Into<Vec<u8>>, key_pem: impl Into<Vec<u8>>) -> Self {
+        self.identity = Some((cert_pem.into(), key_pem.into()));
+        self
+    }
+
+    pub fn domain_name(mut self, domain_name: impl Into<String>) -> Self {
+        self.domain_name = Some(domain_name.into());
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+}
+
+// exponential backoff between attempts, doubling up to `max_backoff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    // attempts including the first one, 1 disables retries
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        }
+    }
+
+    // a call that failed to connect never left the client and is always
+    // retried. anything else, including a connection lost while the handler
+    // ran or an unavailable device, is only retried for calls that are safe
+    // to repeat
+    pub fn retries(&self, status: &Status, idempotent: bool) -> bool {
+        match status.code() {
+            Code::Unavailable if not_connected(status) => true,
+            Code::Unavailable
+            | Code::DeadlineExceeded
+            | Code::ResourceExhausted
+            | Code::Aborted => idempotent,
+            _ => false,
+        }
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+// the channel could not connect to the server, so the request was never sent
+fn not_connected(status: &Status) -> bool {
+    let mut source = status.source();
+    while let Some(error) = source {
+        if error
+            .downcast_ref::<hyper::Error>()
+            .is_some_and(hyper::Error::is_connect)
+        {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
@@ -0,0 +1,65 @@
+use mecha_errors::ErrorInfo;
+use tonic::Code;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientErrorCodes {
+    InvalidEndpoint,
+    InvalidTlsConfig,
+    InvalidToken,
+    ConnectionFailed,
+    // the server answered the rpc with a non-ok status
+    RpcFailed(Code),
+    InvalidResponse,
+    OperationFailed,
+}
+
+impl std::fmt::Display for ClientErrorCodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            ClientErrorCodes::InvalidEndpoint => write!(f, "InvalidEndpoint"),
+            ClientErrorCodes::InvalidTlsConfig => write!(f, "InvalidTlsConfig"),
+            ClientErrorCodes::InvalidToken => write!(f, "InvalidToken"),
+            ClientErrorCodes::ConnectionFailed => write!(f, "ConnectionFailed"),
+            ClientErrorCodes::RpcFailed(code) => write!(f, "RpcFailed({:?})", code),
+            ClientErrorCodes::InvalidResponse => write!(f, "InvalidResponse"),
+            ClientErrorCodes::OperationFailed => write!(f, "OperationFailed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ClientError {
+    pub code: ClientErrorCodes,
+    pub message: String,
+    // domain and reason of a failed rpc, when the server sent them
+    pub error_info: Option<ErrorInfo>,
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "(code: {:?}, message: {})", self.code, self.message)
+    }
+}
+
+impl ClientError {
+    pub fn new(code: ClientErrorCodes, message: String) -> Self {
+        ClientError {
+            code,
+            message,
+            error_info: None,
+        }
+    }
+
+    // the grpc status code when the server rejected the call
+    pub fn grpc_code(&self) -> Option<Code> {
+        match self.code {
+            ClientErrorCodes::RpcFailed(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    // the reason the server gave for a failed rpc, e.g. InvalidBrightnessValueError
+    pub fn reason(&self) -> Option<&str> {
+        self.error_info.as_ref().map(|info| info.reason.as_str())
+    }
+}
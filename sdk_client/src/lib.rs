@@ -0,0 +1,13 @@
+mod errors;
+pub use errors::{ClientError, ClientErrorCodes};
+pub use mecha_errors::ErrorInfo;
+
+mod config;
+pub use config::{ClientConfig, RetryPolicy};
+
+mod client;
+pub use client::{BearerToken, Connection, MechaClient};
+
+pub mod proto;
+
+pub mod services;
@@ -0,0 +1,56 @@
+// clients generated from the server's proto files, one module per package
+pub mod automation {
+    tonic::include_proto!("automation");
+}
+
+pub mod battery {
+    tonic::include_proto!("battery");
+}
+
+pub mod bluetooth {
+    tonic::include_proto!("bluetooth");
+}
+
+pub mod capabilities {
+    tonic::include_proto!("capabilities");
+}
+
+pub mod cpugovernorctrl {
+    tonic::include_proto!("cpugovernorctrl");
+}
+
+pub mod deviceinfo {
+    tonic::include_proto!("deviceinfo");
+}
+
+pub mod displaymanager {
+    tonic::include_proto!("displaymanager");
+}
+
+pub mod events {
+    tonic::include_proto!("events");
+}
+
+pub mod led_ctrl {
+    tonic::include_proto!("led_ctrl");
+}
+
+pub mod metrics {
+    tonic::include_proto!("metrics");
+}
+
+pub mod motionsensor {
+    tonic::include_proto!("motionsensor");
+}
+
+pub mod networkmanager {
+    tonic::include_proto!("networkmanager");
+}
+
+pub mod settings {
+    tonic::include_proto!("settings");
+}
+
+pub mod trustzonectrl {
+    tonic::include_proto!("trustzonectrl");
+}
@@ -0,0 +1,64 @@
+use anyhow::Result;
+
+use crate::client::{rpc, MechaClient};
+use crate::proto::automation::{
+    automation_service_client::AutomationServiceClient, ListRulesRequest, RuleRequest,
+};
+pub use crate::proto::automation::{DryRunResponse as DryRun, Rule};
+
+#[derive(Debug, Clone)]
+pub struct AutomationClient(MechaClient);
+
+impl AutomationClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        AutomationClient(client)
+    }
+
+    // the configured rules, whether each is enabled and active and how
+    // often it fired
+    pub async fn rules(&self) -> Result<Vec<Rule>> {
+        let response = rpc!(
+            self.0,
+            AutomationServiceClient::list_rules(ListRulesRequest {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.rules)
+    }
+
+    // lasts until the server restarts
+    pub async fn enable(&self, name: &str) -> Result<Rule> {
+        rpc!(
+            self.0,
+            AutomationServiceClient::enable_rule(RuleRequest {
+                name: name.to_string(),
+            }),
+            idempotent = true
+        )
+        .await
+    }
+
+    pub async fn disable(&self, name: &str) -> Result<Rule> {
+        rpc!(
+            self.0,
+            AutomationServiceClient::disable_rule(RuleRequest {
+                name: name.to_string(),
+            }),
+            idempotent = true
+        )
+        .await
+    }
+
+    // each condition checked against the latest readings and the actions
+    // that would apply, nothing is changed
+    pub async fn dry_run(&self, name: &str) -> Result<DryRun> {
+        rpc!(
+            self.0,
+            AutomationServiceClient::dry_run_rule(RuleRequest {
+                name: name.to_string(),
+            }),
+            idempotent = true
+        )
+        .await
+    }
+}
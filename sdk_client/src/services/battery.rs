@@ -0,0 +1,130 @@
+use anyhow::Result;
+
+use super::parse;
+use crate::client::{rpc, MechaClient};
+use crate::proto::battery::{
+    power_supply_service_client::PowerSupplyServiceClient, Empty, GetPowerSupplyInfoResponse,
+    SetDeviceRequest,
+};
+
+// the power_supply `status` attribute
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatteryStatus {
+    Charging,
+    Discharging,
+    NotCharging,
+    Full,
+    Unknown(String),
+}
+
+impl From<&str> for BatteryStatus {
+    fn from(status: &str) -> Self {
+        match status.trim() {
+            "Charging" => BatteryStatus::Charging,
+            "Discharging" => BatteryStatus::Discharging,
+            "Not charging" => BatteryStatus::NotCharging,
+            "Full" => BatteryStatus::Full,
+            status => BatteryStatus::Unknown(status.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatteryInfo {
+    pub name: String,
+    // e.g. Battery, Mains, USB
+    pub kind: String,
+    pub status: BatteryStatus,
+    pub present: bool,
+    // microvolts
+    pub voltage_now: u32,
+    // microamperes, negative while discharging
+    pub current_now: i32,
+    // percent
+    pub capacity: u8,
+    pub capacity_level: String,
+    pub temperature_celsius: f32,
+    pub technology: String,
+    // microampere-hours
+    pub charge_full: u32,
+    pub charge_now: u32,
+    pub charge_full_design: u32,
+    pub manufacturer: String,
+}
+
+impl TryFrom<GetPowerSupplyInfoResponse> for BatteryInfo {
+    type Error = anyhow::Error;
+
+    fn try_from(info: GetPowerSupplyInfoResponse) -> Result<Self> {
+        Ok(BatteryInfo {
+            capacity: parse("capacity", &info.capacity)?,
+            status: BatteryStatus::from(info.status.as_str()),
+            // the fuel gauge reports tenths of a degree
+            temperature_celsius: info.temp as f32 / 10.0,
+            name: info.name,
+            kind: info.r#type,
+            present: info.present,
+            voltage_now: info.voltage_now,
+            current_now: info.current_now,
+            capacity_level: info.capacity_level,
+            technology: info.technology,
+            charge_full: info.charge_full,
+            charge_now: info.charge_now,
+            charge_full_design: info.charge_full_design,
+            manufacturer: info.manufacturer,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatteryClient(MechaClient);
+
+impl BatteryClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        BatteryClient(client)
+    }
+
+    pub async fn info(&self) -> Result<BatteryInfo> {
+        let response = rpc!(
+            self.0,
+            PowerSupplyServiceClient::get_power_supply_info(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        BatteryInfo::try_from(response)
+    }
+
+    // the power_supply device the server reads from
+    pub async fn device(&self) -> Result<String> {
+        let response = rpc!(
+            self.0,
+            PowerSupplyServiceClient::get_device(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.device_path)
+    }
+
+    pub async fn set_device(&self, device_path: &str) -> Result<()> {
+        rpc!(
+            self.0,
+            PowerSupplyServiceClient::set_device(SetDeviceRequest {
+                device_path: device_path.to_string()
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(())
+    }
+
+    // microamperes
+    pub async fn current(&self) -> Result<i64> {
+        let response = rpc!(
+            self.0,
+            PowerSupplyServiceClient::get_current(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.current_value)
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::Result;
+
+use crate::client::{rpc, MechaClient};
+use crate::proto::bluetooth::{bluetooth_service_client::BluetoothServiceClient, Empty};
+
+#[derive(Debug, Clone)]
+pub struct BluetoothClient(MechaClient);
+
+impl BluetoothClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        BluetoothClient(client)
+    }
+
+    pub async fn enabled(&self) -> Result<bool> {
+        let response = rpc!(
+            self.0,
+            BluetoothServiceClient::get_bluetooth_status(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.enabled)
+    }
+
+    pub async fn enable(&self) -> Result<()> {
+        rpc!(
+            self.0,
+            BluetoothServiceClient::enable_bluetooth(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn disable(&self) -> Result<()> {
+        rpc!(
+            self.0,
+            BluetoothServiceClient::disable_bluetooth(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+use anyhow::Result;
+
+use crate::client::{rpc, MechaClient};
+pub use crate::proto::capabilities::Capabilities;
+use crate::proto::capabilities::{
+    device_capabilities_service_client::DeviceCapabilitiesServiceClient, GetCapabilitiesRequest,
+};
+
+#[derive(Debug, Clone)]
+pub struct CapabilitiesClient(MechaClient);
+
+impl CapabilitiesClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        CapabilitiesClient(client)
+    }
+
+    // board profile, registered services and the hardware each subsystem offers
+    pub async fn get(&self) -> Result<Capabilities> {
+        rpc!(
+            self.0,
+            DeviceCapabilitiesServiceClient::get_capabilities(GetCapabilitiesRequest {}),
+            idempotent = true
+        )
+        .await
+    }
+}
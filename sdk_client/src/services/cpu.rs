@@ -0,0 +1,123 @@
+use anyhow::Result;
+use std::fmt;
+
+use super::parse;
+use crate::client::{rpc, MechaClient};
+use crate::proto::cpugovernorctrl::{
+    cpu_governor_ctrl_service_client::CpuGovernorCtrlServiceClient, CpuFrequencyRequest, Empty,
+    GovernorRequest,
+};
+
+// cpufreq scaling governors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Governor {
+    Performance,
+    Powersave,
+    Userspace,
+    Ondemand,
+    Conservative,
+    Schedutil,
+    Other(String),
+}
+
+impl From<&str> for Governor {
+    fn from(governor: &str) -> Self {
+        match governor.trim() {
+            "performance" => Governor::Performance,
+            "powersave" => Governor::Powersave,
+            "userspace" => Governor::Userspace,
+            "ondemand" => Governor::Ondemand,
+            "conservative" => Governor::Conservative,
+            "schedutil" => Governor::Schedutil,
+            governor => Governor::Other(governor.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Governor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Governor::Performance => "performance",
+            Governor::Powersave => "powersave",
+            Governor::Userspace => "userspace",
+            Governor::Ondemand => "ondemand",
+            Governor::Conservative => "conservative",
+            Governor::Schedutil => "schedutil",
+            Governor::Other(name) => name,
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// frequencies the server accepts, named after their value in kHz
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuFrequency {
+    Freq1200000,
+    Freq1600000,
+    Freq1800000,
+}
+
+impl CpuFrequency {
+    // the request carries MHz
+    fn megahertz(&self) -> &'static str {
+        match self {
+            CpuFrequency::Freq1200000 => "1200",
+            CpuFrequency::Freq1600000 => "1600",
+            CpuFrequency::Freq1800000 => "1800",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CpuClient(MechaClient);
+
+impl CpuClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        CpuClient(client)
+    }
+
+    pub async fn governor(&self) -> Result<Governor> {
+        let response = rpc!(
+            self.0,
+            CpuGovernorCtrlServiceClient::get_governor(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(Governor::from(response.result.as_str()))
+    }
+
+    pub async fn set_governor(&self, governor: Governor) -> Result<()> {
+        rpc!(
+            self.0,
+            CpuGovernorCtrlServiceClient::set_governor(GovernorRequest {
+                governor: governor.to_string()
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(())
+    }
+
+    // current frequency in kHz
+    pub async fn frequency(&self) -> Result<u32> {
+        let response = rpc!(
+            self.0,
+            CpuGovernorCtrlServiceClient::get_cpu_frequency(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        parse("frequency", &response.result)
+    }
+
+    pub async fn set_frequency(&self, frequency: CpuFrequency) -> Result<()> {
+        rpc!(
+            self.0,
+            CpuGovernorCtrlServiceClient::set_cpu_frequency(CpuFrequencyRequest {
+                frequency: frequency.megahertz().to_string()
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+
+use super::invalid_response;
+use crate::client::{rpc, MechaClient};
+use crate::proto::deviceinfo::{device_info_service_client::DeviceInfoServiceClient, Empty};
+pub use crate::proto::deviceinfo::{CpuInfo, DiskInfo, MemoryInfo};
+
+#[derive(Debug, Clone)]
+pub struct DeviceInfoClient(MechaClient);
+
+impl DeviceInfoClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        DeviceInfoClient(client)
+    }
+
+    pub async fn memory(&self) -> Result<MemoryInfo> {
+        let response = rpc!(
+            self.0,
+            DeviceInfoServiceClient::get_memory_info(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        response
+            .memory_info
+            .ok_or_else(|| invalid_response("memory_info is missing".to_string()))
+    }
+
+    pub async fn disks(&self) -> Result<Vec<DiskInfo>> {
+        let response = rpc!(
+            self.0,
+            DeviceInfoServiceClient::get_disk_info(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.disk_info)
+    }
+
+    pub async fn cpu(&self) -> Result<CpuInfo> {
+        let response = rpc!(
+            self.0,
+            DeviceInfoServiceClient::get_cpu_info(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        response
+            .cpu_info
+            .ok_or_else(|| invalid_response("cpu_info is missing".to_string()))
+    }
+}
@@ -0,0 +1,44 @@
+use anyhow::Result;
+
+use super::invalid_response;
+use crate::client::{rpc, MechaClient};
+use crate::proto::displaymanager::{
+    display_ctrl_service_client::DisplayCtrlServiceClient, GetBrightnessRequest,
+    SetBrightnessRequest,
+};
+
+#[derive(Debug, Clone)]
+pub struct DisplayClient(MechaClient);
+
+impl DisplayClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        DisplayClient(client)
+    }
+
+    pub async fn brightness(&self) -> Result<u8> {
+        let response = rpc!(
+            self.0,
+            DisplayCtrlServiceClient::get_brightness(GetBrightnessRequest {}),
+            idempotent = true
+        )
+        .await?;
+        u8::try_from(response.brightness).map_err(|_| {
+            invalid_response(format!(
+                "brightness {} is out of range",
+                response.brightness
+            ))
+        })
+    }
+
+    pub async fn set_brightness(&self, brightness: u8) -> Result<()> {
+        rpc!(
+            self.0,
+            DisplayCtrlServiceClient::set_brightness(SetBrightnessRequest {
+                brightness: brightness.into()
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::Result;
+use tonic::Streaming;
+
+use crate::client::{rpc_failed, MechaClient};
+use crate::proto::events::{event_service_client::EventServiceClient, Event, SubscribeRequest};
+
+#[derive(Debug, Clone)]
+pub struct EventsClient(MechaClient);
+
+impl EventsClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        EventsClient(client)
+    }
+
+    // every topic when `topics` is empty. the stream has no deadline, it runs
+    // until it is dropped or the server shuts down
+    pub async fn subscribe(&self, topics: &[&str]) -> Result<EventStream> {
+        let topics: Vec<String> = topics.iter().map(|topic| topic.to_string()).collect();
+        let stream = self
+            .0
+            .call("subscribe", true, |connection| {
+                let request = SubscribeRequest {
+                    topics: topics.clone(),
+                };
+                async move { EventServiceClient::new(connection).subscribe(request).await }
+            })
+            .await?;
+        Ok(EventStream(stream))
+    }
+}
+
+#[derive(Debug)]
+pub struct EventStream(Streaming<Event>);
+
+impl EventStream {
+    // the next event, None once the server ended the stream
+    pub async fn next(&mut self) -> Result<Option<Event>> {
+        self.0
+            .message()
+            .await
+            .map_err(|status| rpc_failed("subscribe", &status))
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+
+use crate::client::{rpc, MechaClient};
+use crate::proto::led_ctrl::{
+    led_color::Color, led_ctrl_service_client::LedCtrlServiceClient, LedColor as LedColorRequest,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedColor {
+    Red,
+    Green,
+    Blue,
+}
+
+impl From<LedColor> for LedColorRequest {
+    fn from(color: LedColor) -> Self {
+        let color = match color {
+            LedColor::Red => Color::Red,
+            LedColor::Green => Color::Green,
+            LedColor::Blue => Color::Blue,
+        };
+        LedColorRequest {
+            color: color as i32,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LedClient(MechaClient);
+
+impl LedClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        LedClient(client)
+    }
+
+    pub async fn set(&self, color: LedColor) -> Result<()> {
+        rpc!(
+            self.0,
+            LedCtrlServiceClient::set_led(LedColorRequest::from(color)),
+            idempotent = true
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn clear(&self, color: LedColor) -> Result<()> {
+        rpc!(
+            self.0,
+            LedCtrlServiceClient::clear_led(LedColorRequest::from(color)),
+            idempotent = true
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+
+use crate::client::{rpc, MechaClient};
+use crate::proto::metrics::{metrics_service_client::MetricsServiceClient, Empty};
+
+#[derive(Debug, Clone)]
+pub struct MetricsClient(MechaClient);
+
+impl MetricsClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        MetricsClient(client)
+    }
+
+    // percent of cpu time in use
+    pub async fn cpu_usage(&self) -> Result<f32> {
+        let response = rpc!(
+            self.0,
+            MetricsServiceClient::get_cpu_usage(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.cpu_usage)
+    }
+
+    // bytes of memory in use
+    pub async fn memory_usage(&self) -> Result<u64> {
+        let response = rpc!(
+            self.0,
+            MetricsServiceClient::get_memory_usage(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.memory_usage)
+    }
+
+    // bytes of disk space in use
+    pub async fn disk_usage(&self) -> Result<u64> {
+        let response = rpc!(
+            self.0,
+            MetricsServiceClient::get_disk_usage(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.disk_usage)
+    }
+}
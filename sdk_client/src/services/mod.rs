@@ -0,0 +1,62 @@
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+use crate::{ClientError, ClientErrorCodes};
+
+mod automation;
+pub use automation::{AutomationClient, DryRun, Rule};
+
+mod battery;
+pub use battery::{BatteryClient, BatteryInfo, BatteryStatus};
+
+mod bluetooth;
+pub use bluetooth::BluetoothClient;
+
+mod capabilities;
+pub use capabilities::{Capabilities, CapabilitiesClient};
+
+mod cpu;
+pub use cpu::{CpuClient, CpuFrequency, Governor};
+
+mod device_info;
+pub use device_info::{CpuInfo, DeviceInfoClient, DiskInfo, MemoryInfo};
+
+mod display;
+pub use display::DisplayClient;
+
+mod events;
+pub use events::{EventStream, EventsClient};
+
+mod led;
+pub use led::{LedClient, LedColor};
+
+mod metrics;
+pub use metrics::MetricsClient;
+
+mod motion_sensor;
+pub use motion_sensor::{Acceleration, MotionSensorClient};
+
+mod settings;
+pub use settings::SettingsClient;
+
+mod trustzone;
+pub use trustzone::{KeySize, KeyType, TrustZoneClient};
+
+mod wifi;
+pub use wifi::{KnownNetwork, WifiClient, WifiNetwork};
+
+fn invalid_response(message: String) -> anyhow::Error {
+    anyhow!(ClientError::new(ClientErrorCodes::InvalidResponse, message))
+}
+
+fn operation_failed(message: String) -> anyhow::Error {
+    anyhow!(ClientError::new(ClientErrorCodes::OperationFailed, message))
+}
+
+// several responses carry numbers as strings, read straight from sysfs
+fn parse<T: FromStr>(field: &str, value: &str) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| invalid_response(format!("{} is not a number: {:?}", field, value)))
+}
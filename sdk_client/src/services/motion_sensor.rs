@@ -0,0 +1,45 @@
+use anyhow::Result;
+
+use crate::client::{rpc, MechaClient};
+use crate::proto::motionsensor::{motion_sensor_service_client::MotionSensorServiceClient, Empty};
+
+// raw values of the x, y and z axes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Acceleration {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MotionSensorClient(MechaClient);
+
+impl MotionSensorClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        MotionSensorClient(client)
+    }
+
+    pub async fn read(&self) -> Result<Acceleration> {
+        let response = rpc!(
+            self.0,
+            MotionSensorServiceClient::read_value(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(Acceleration {
+            x: response.x_value,
+            y: response.y_value,
+            z: response.z_value,
+        })
+    }
+
+    pub async fn motion_detected(&self) -> Result<bool> {
+        let response = rpc!(
+            self.0,
+            MotionSensorServiceClient::detect_motion(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.is_motion_detected)
+    }
+}
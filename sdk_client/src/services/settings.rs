@@ -0,0 +1,48 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+use crate::client::{rpc, MechaClient};
+use crate::proto::settings::{
+    settings_service_client::SettingsServiceClient, GetSettingsRequest, ResetSettingsRequest,
+};
+
+#[derive(Debug, Clone)]
+pub struct SettingsClient(MechaClient);
+
+impl SettingsClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        SettingsClient(client)
+    }
+
+    // saved settings by key, e.g. display.brightness, only the keys starting
+    // with `prefix`
+    pub async fn get(&self, prefix: &str) -> Result<BTreeMap<String, String>> {
+        let response = rpc!(
+            self.0,
+            SettingsServiceClient::get_settings(GetSettingsRequest {
+                prefix: prefix.to_string(),
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(response
+            .settings
+            .into_iter()
+            .map(|setting| (setting.key, setting.value))
+            .collect())
+    }
+
+    // forget the given keys, every key when empty. returns the keys that
+    // were saved
+    pub async fn reset(&self, keys: &[&str]) -> Result<Vec<String>> {
+        let response = rpc!(
+            self.0,
+            SettingsServiceClient::reset_settings(ResetSettingsRequest {
+                keys: keys.iter().map(|key| key.to_string()).collect(),
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.keys)
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::Result;
+
+use super::operation_failed;
+use crate::client::{rpc, MechaClient};
+use crate::proto::trustzonectrl::{
+    trust_zone_ctrl_service_client::TrustZoneCtrlServiceClient, DeriveKeyRequest,
+    GenerateHmacRequest, GenerateKeyRequest, ReadCertificationRequest, RemoveCertificateRequest,
+    SignDataRequest, VerifyDataRequest, WriteCertificateRequest,
+};
+pub use crate::proto::trustzonectrl::{KeySize, KeyType};
+
+// files are paths on the board, the secure element is addressed by oid
+#[derive(Debug, Clone)]
+pub struct TrustZoneClient(MechaClient);
+
+impl TrustZoneClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        TrustZoneClient(client)
+    }
+
+    pub async fn read_certificate(&self, region: &str, output_file: &str) -> Result<String> {
+        let response = rpc!(
+            self.0,
+            TrustZoneCtrlServiceClient::read_certification(ReadCertificationRequest {
+                output_file: output_file.to_string(),
+                region: region.to_string(),
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.certificate)
+    }
+
+    pub async fn write_certificate(&self, cert_file: &str, oid: &str) -> Result<()> {
+        let response = rpc!(
+            self.0,
+            TrustZoneCtrlServiceClient::write_certificate(WriteCertificateRequest {
+                cert_file: cert_file.to_string(),
+                oid: oid.to_string(),
+            }),
+            idempotent = true
+        )
+        .await?;
+        if !response.success {
+            return Err(operation_failed(format!(
+                "unable to write {} to {}",
+                cert_file, oid
+            )));
+        }
+        Ok(())
+    }
+
+    pub async fn remove_certificate(&self, oid: &str) -> Result<()> {
+        let response = rpc!(
+            self.0,
+            TrustZoneCtrlServiceClient::remove_certificate(RemoveCertificateRequest {
+                oid: oid.to_string()
+            }),
+            idempotent = true
+        )
+        .await?;
+        if !response.success {
+            return Err(operation_failed(format!(
+                "unable to remove the certificate in {}",
+                oid
+            )));
+        }
+        Ok(())
+    }
+
+    // the public key of the new key pair
+    pub async fn generate_key(
+        &self,
+        oid: &str,
+        key_type: KeyType,
+        key_size: KeySize,
+        output_file: &str,
+    ) -> Result<String> {
+        let response = rpc!(
+            self.0,
+            TrustZoneCtrlServiceClient::generate_key(GenerateKeyRequest {
+                oid: oid.to_string(),
+                key_type: key_type as i32,
+                key_size: key_size as i32,
+                output_file: output_file.to_string(),
+            }),
+            idempotent = false
+        )
+        .await?;
+        Ok(response.public_key)
+    }
+
+    pub async fn sign(
+        &self,
+        key_oid: &str,
+        input_file: &str,
+        output_file: &str,
+        hash_before_sign: bool,
+    ) -> Result<String> {
+        let response = rpc!(
+            self.0,
+            TrustZoneCtrlServiceClient::sign_data(SignDataRequest {
+                key_oid: key_oid.to_string(),
+                input_file: input_file.to_string(),
+                output_file: output_file.to_string(),
+                hash_before_sign,
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.signed_data)
+    }
+
+    // fails when the signature does not match
+    pub async fn verify(
+        &self,
+        pubkey_file: &str,
+        input_file: &str,
+        signature_file: &str,
+        hash_before_verify: bool,
+    ) -> Result<()> {
+        rpc!(
+            self.0,
+            TrustZoneCtrlServiceClient::verify_data(VerifyDataRequest {
+                pubkey_file: pubkey_file.to_string(),
+                input_file: input_file.to_string(),
+                signature_file: signature_file.to_string(),
+                hash_before_verify,
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn derive_key(
+        &self,
+        secret_oid: &str,
+        hkdf_type: u32,
+        info_file: &str,
+        salt_file: &str,
+        output_file: &str,
+    ) -> Result<String> {
+        let response = rpc!(
+            self.0,
+            TrustZoneCtrlServiceClient::derive_key(DeriveKeyRequest {
+                secret_oid: secret_oid.to_string(),
+                hkdf_type,
+                info_file: info_file.to_string(),
+                salt_file: salt_file.to_string(),
+                output_file: output_file.to_string(),
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.derived_key)
+    }
+
+    pub async fn hmac(
+        &self,
+        secret_oid: &str,
+        hmac_type: u32,
+        input_data: &str,
+        output_file: &str,
+    ) -> Result<String> {
+        let response = rpc!(
+            self.0,
+            TrustZoneCtrlServiceClient::generate_hmac(GenerateHmacRequest {
+                secret_oid: secret_oid.to_string(),
+                hmac_type,
+                input_data: input_data.to_string(),
+                output_file: output_file.to_string(),
+            }),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.generated_hmac)
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::Result;
+
+use super::{operation_failed, parse};
+use crate::client::{rpc, MechaClient};
+use crate::proto::networkmanager::{
+    network_manager_service_client::NetworkManagerServiceClient, Empty, NetworkResult,
+    RemoveNetworkRequest, ScanResult, WifiConnectRequest,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiNetwork {
+    pub mac: String,
+    pub ssid: String,
+    pub frequency_mhz: u32,
+    pub signal_dbm: i32,
+    // e.g. ["WPA2-PSK-CCMP", "ESS"]
+    pub flags: Vec<String>,
+}
+
+impl TryFrom<ScanResult> for WifiNetwork {
+    type Error = anyhow::Error;
+
+    fn try_from(result: ScanResult) -> Result<Self> {
+        Ok(WifiNetwork {
+            frequency_mhz: parse("frequency", &result.frequency)?,
+            flags: flags(&result.flags),
+            mac: result.mac,
+            ssid: result.name,
+            signal_dbm: result.signal,
+        })
+    }
+}
+
+// a network saved in wpa_supplicant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownNetwork {
+    pub id: i32,
+    pub ssid: String,
+    // ["CURRENT"] for the network in use
+    pub flags: Vec<String>,
+}
+
+impl From<NetworkResult> for KnownNetwork {
+    fn from(result: NetworkResult) -> Self {
+        KnownNetwork {
+            id: result.network_id,
+            flags: flags(&result.flags),
+            ssid: result.ssid,
+        }
+    }
+}
+
+// wpa_supplicant lists flags as `[WPA2-PSK-CCMP][ESS]`
+fn flags(flags: &str) -> Vec<String> {
+    flags
+        .split(['[', ']'])
+        .filter(|flag| !flag.trim().is_empty())
+        .map(|flag| flag.trim().to_string())
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct WifiClient(MechaClient);
+
+impl WifiClient {
+    pub(crate) fn new(client: MechaClient) -> Self {
+        WifiClient(client)
+    }
+
+    pub async fn scan(&self) -> Result<Vec<WifiNetwork>> {
+        let response = rpc!(
+            self.0,
+            NetworkManagerServiceClient::scan_wireless_network(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        response
+            .results
+            .into_iter()
+            .map(WifiNetwork::try_from)
+            .collect()
+    }
+
+    pub async fn known_networks(&self) -> Result<Vec<KnownNetwork>> {
+        let response = rpc!(
+            self.0,
+            NetworkManagerServiceClient::scan_known_wireless_network(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response
+            .results
+            .into_iter()
+            .map(KnownNetwork::from)
+            .collect())
+    }
+
+    pub async fn connect(&self, ssid: &str, psk: &str) -> Result<()> {
+        let response = rpc!(
+            self.0,
+            NetworkManagerServiceClient::connect_wireless_network(WifiConnectRequest {
+                ssid: ssid.to_string(),
+                psk: psk.to_string(),
+            }),
+            idempotent = false
+        )
+        .await?;
+        if !response.success {
+            return Err(operation_failed(format!(
+                "unable to connect to {}: {}",
+                ssid, response.message
+            )));
+        }
+        Ok(())
+    }
+
+    // remove a saved network
+    pub async fn remove(&self, network_id: i32) -> Result<()> {
+        let response = rpc!(
+            self.0,
+            NetworkManagerServiceClient::disconnect_wireless_network(RemoveNetworkRequest {
+                network_id
+            }),
+            idempotent = false
+        )
+        .await?;
+        if !response.success {
+            return Err(operation_failed(format!(
+                "unable to remove network {}: {}",
+                network_id, response.message
+            )));
+        }
+        Ok(())
+    }
+
+    pub async fn enabled(&self) -> Result<bool> {
+        let response = rpc!(
+            self.0,
+            NetworkManagerServiceClient::get_wifi_status(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        Ok(response.wifi_on)
+    }
+
+    pub async fn current_network(&self) -> Result<WifiNetwork> {
+        let response = rpc!(
+            self.0,
+            NetworkManagerServiceClient::get_current_network(Empty {}),
+            idempotent = true
+        )
+        .await?;
+        WifiNetwork::try_from(response)
+    }
+}
@@ -0,0 +1,463 @@
+use anyhow::{bail, Result};
+use mecha_battery_ctrl::PowerSupply as PowerSupplyData;
+use mecha_display_ctrl::{DisplayError, DisplayErrorCodes};
+use mecha_errors::{status, DomainError};
+use mecha_sdk_client::services::{BatteryStatus, CpuFrequency, Governor, KeySize, KeyType};
+use mecha_sdk_client::{ClientConfig, ClientError, MechaClient, RetryPolicy};
+use mecha_sdk_server::services::event_service::{BrightnessChanged, Payload};
+use mecha_sdk_server::services::{
+    CpuControl, CpuCtrlService, CpuFrequency as ServerCpuFrequency, CpuGovernorCtrlServiceServer,
+    DisplayControl, DisplayCtrlManager, DisplayCtrlServiceServer, EventManager, EventServiceServer,
+    KeySize as ServerKeySize, KeyType as ServerKeyType, PowerSupply, PowerSupplyInfo,
+    PowerSupplyServiceServer, TrustZoneControl, TrustZoneCtrlServiceManager,
+    TrustZoneCtrlServiceServer,
+};
+use mecha_trustzone_ctrl::{TrustZoneCtrlError, TrustZoneCtrlErrorCodes};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::copy_bidirectional;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Notify;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::metadata::MetadataMap;
+use tonic::transport::Server;
+use tonic::{Code, Status};
+
+#[derive(Clone, Default)]
+struct FakeDisplay {
+    brightness: Arc<Mutex<u8>>,
+}
+
+impl DisplayControl for FakeDisplay {
+    fn set_display_brightness(&self, brightness: u8) -> Result<()> {
+        if brightness > 244 {
+            bail!(DisplayError::new(
+                DisplayErrorCodes::InvalidBrightnessValueError,
+                "invalid brightness value".to_string(),
+            ));
+        }
+        *self.brightness.lock().unwrap() = brightness;
+        Ok(())
+    }
+
+    fn get_display_brightness(&self) -> Result<u8> {
+        Ok(*self.brightness.lock().unwrap())
+    }
+}
+
+#[derive(Clone)]
+struct FakeCpu {
+    governor: Arc<Mutex<String>>,
+    frequency: Arc<Mutex<String>>,
+}
+
+impl CpuControl for FakeCpu {
+    fn set_cpu_governor(&self, governor: &str) -> Result<()> {
+        *self.governor.lock().unwrap() = governor.to_string();
+        Ok(())
+    }
+
+    fn get_cpu_governor(&self) -> Result<String> {
+        Ok(self.governor.lock().unwrap().clone())
+    }
+
+    fn get_cpu_frequency(&self) -> Result<String> {
+        Ok(self.frequency.lock().unwrap().clone())
+    }
+
+    fn set_cpu_frequency(&self, frequency: ServerCpuFrequency) -> Result<()> {
+        let value = match frequency {
+            ServerCpuFrequency::Freq1200000 => "1200000",
+            ServerCpuFrequency::Freq1600000 => "1600000",
+            ServerCpuFrequency::Freq1800000 => "1800000",
+        };
+        *self.frequency.lock().unwrap() = value.to_string();
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct FakeBattery;
+
+impl PowerSupplyInfo for FakeBattery {
+    fn info(&self) -> Result<PowerSupplyData> {
+        Ok(PowerSupplyData {
+            name: "bq27441-0".to_string(),
+            r#type: "Battery".to_string(),
+            status: "Discharging".to_string(),
+            present: true,
+            voltage_now: 3912000,
+            current_now: -245000,
+            capacity: 76,
+            capacity_level: "Normal".to_string(),
+            temp: 287,
+            technology: "Li-ion".to_string(),
+            charge_full: 2830000,
+            charge_now: 2150000,
+            charge_full_design: 3000000,
+            manufacturer: "Texas Instruments".to_string(),
+        })
+    }
+
+    fn set_device(&mut self, _device: &str) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_device(&self) -> Result<String> {
+        Ok("/sys/class/power_supply/bq27441-0/uevent".to_string())
+    }
+
+    fn get_current(&self) -> Result<i64> {
+        Ok(-245000)
+    }
+}
+
+// a secure element whose key store can not be read, counting the attempts.
+// every attempt signals `entered` and takes `delay` to fail
+#[derive(Clone, Default)]
+struct UnreadableTrustZone {
+    calls: Arc<AtomicUsize>,
+    entered: Arc<Notify>,
+    delay: Duration,
+}
+
+impl UnreadableTrustZone {
+    fn unreadable(&self) -> Result<String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.entered.notify_one();
+        std::thread::sleep(self.delay);
+        bail!(TrustZoneCtrlError::new(
+            TrustZoneCtrlErrorCodes::FileReadError,
+            "unable to read /dev/optiga".to_string(),
+        ))
+    }
+}
+
+impl TrustZoneControl for UnreadableTrustZone {
+    fn read_trustzone_cert(&self, _output_file: &str, _region: &str) -> Result<String> {
+        self.unreadable()
+    }
+
+    fn write_trustzone_cert(&self, _cert_file: &str, _oid: &str) -> Result<()> {
+        self.unreadable().map(|_| ())
+    }
+
+    fn remove_trustzone_cert(&self, _oid: &str) -> Result<()> {
+        self.unreadable().map(|_| ())
+    }
+
+    fn generate_trustzone_key(
+        &self,
+        _oid: &str,
+        _key_type: ServerKeyType,
+        _key_size: ServerKeySize,
+        _output_file: &str,
+    ) -> Result<String> {
+        self.unreadable()
+    }
+
+    fn sign_trustzone_data(
+        &self,
+        _key_oid: &str,
+        _input_file: &str,
+        _output_file: &str,
+        _hash_before_sign: bool,
+    ) -> Result<String> {
+        self.unreadable()
+    }
+
+    fn verify_trustzone_data(
+        &self,
+        _pubkey_file: &str,
+        _input_file: &str,
+        _signature_file: &str,
+        _hash_before_verify: bool,
+    ) -> Result<String> {
+        self.unreadable()
+    }
+
+    fn derive_trustzone_key(
+        &self,
+        _secret_oid: &str,
+        _hkdf_type: u16,
+        _info_file: &str,
+        _salt_file: &str,
+        _output_file: &str,
+    ) -> Result<String> {
+        self.unreadable()
+    }
+
+    fn generate_trustzone_hmac(
+        &self,
+        _secret_oid: &str,
+        _hmac_type: u16,
+        _input_data: &str,
+        _output_file: &str,
+    ) -> Result<String> {
+        self.unreadable()
+    }
+}
+
+// display, cpu and battery services on a random local port
+async fn server(display: FakeDisplay, cpu: FakeCpu) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let serve = Server::builder()
+        .add_service(DisplayCtrlServiceServer::new(DisplayCtrlManager {
+            display_ctrl: display.into(),
+        }))
+        .add_service(CpuGovernorCtrlServiceServer::new(CpuCtrlService {
+            cpu_ctrl_manager: cpu.into(),
+        }))
+        .add_service(PowerSupplyServiceServer::new(PowerSupply {
+            power_supply: FakeBattery.into(),
+        }))
+        .serve_with_incoming(TcpListenerStream::new(listener));
+    tokio::spawn(serve);
+    addr.to_string()
+}
+
+fn fake_cpu() -> FakeCpu {
+    FakeCpu {
+        governor: Arc::new(Mutex::new("ondemand\n".to_string())),
+        frequency: Arc::new(Mutex::new("1200000\n".to_string())),
+    }
+}
+
+#[tokio::test]
+async fn typed_clients_parse_string_responses() {
+    let addr = server(FakeDisplay::default(), fake_cpu()).await;
+    // a bare host:port is taken as plain http
+    let client = MechaClient::connect(ClientConfig::new(addr)).await.unwrap();
+
+    let cpu = client.cpu();
+    assert_eq!(cpu.governor().await.unwrap(), Governor::Ondemand);
+    assert_eq!(cpu.frequency().await.unwrap(), 1200000);
+    cpu.set_frequency(CpuFrequency::Freq1600000).await.unwrap();
+    assert_eq!(cpu.frequency().await.unwrap(), 1600000);
+
+    let battery = client.battery().info().await.unwrap();
+    assert_eq!(battery.capacity, 76);
+    assert_eq!(battery.status, BatteryStatus::Discharging);
+    assert_eq!(battery.kind, "Battery");
+    assert!((battery.temperature_celsius - 28.7).abs() < f32::EPSILON);
+}
+
+#[tokio::test]
+async fn server_errors_carry_the_grpc_code() {
+    let display = FakeDisplay::default();
+    let addr = server(display.clone(), fake_cpu()).await;
+    let client = MechaClient::connect(ClientConfig::new(format!("http://{}", addr)))
+        .await
+        .unwrap();
+
+    client.display().set_brightness(120).await.unwrap();
+    assert_eq!(client.display().brightness().await.unwrap(), 120);
+    assert_eq!(*display.brightness.lock().unwrap(), 120);
+
+    let e = client.display().set_brightness(250).await.unwrap_err();
+    let e = e.downcast_ref::<ClientError>().unwrap();
+    assert_eq!(e.grpc_code(), Some(Code::InvalidArgument));
+    assert_eq!(e.reason(), Some("InvalidBrightnessValueError"));
+    assert_eq!(e.error_info.as_ref().unwrap().domain, "mecha_display_ctrl");
+    assert!(e.message.contains("set_brightness failed"));
+}
+
+#[tokio::test]
+async fn unavailable_calls_are_retried_with_backoff() {
+    // nothing listens on the port once the listener is dropped
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let retry = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(50),
+        max_backoff: Duration::from_millis(80),
+    };
+    let client =
+        MechaClient::connect_lazy(ClientConfig::new(format!("http://{}", addr)).retry(retry))
+            .unwrap();
+
+    let started = Instant::now();
+    let e = client.display().brightness().await.unwrap_err();
+    // 50ms then 80ms between the three attempts
+    assert!(started.elapsed() >= Duration::from_millis(130));
+    let e = e.downcast_ref::<ClientError>().unwrap();
+    assert_eq!(e.grpc_code(), Some(Code::Unavailable));
+
+    // a refused connection never sent the request, so unsafe calls retry too
+    let started = Instant::now();
+    client
+        .trustzone()
+        .generate_key("0xE0F1", KeyType::Sign, KeySize::Ecc256, "key.pem")
+        .await
+        .unwrap_err();
+    assert!(started.elapsed() >= Duration::from_millis(130));
+}
+
+#[tokio::test]
+async fn connect_fails_fast_without_a_server() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let e = MechaClient::connect(ClientConfig::new(format!("http://{}", addr)))
+        .await
+        .unwrap_err();
+    let e = e.downcast_ref::<ClientError>().unwrap();
+    assert_eq!(e.grpc_code(), None);
+}
+
+#[test]
+fn retry_policy_backoff_doubles_up_to_the_limit() {
+    let retry = RetryPolicy::default();
+    assert_eq!(retry.backoff(1), Duration::from_millis(100));
+    assert_eq!(retry.backoff(2), Duration::from_millis(200));
+    assert_eq!(retry.backoff(10), Duration::from_secs(2));
+
+    // unavailable without a failed connect behind it may have been sent
+    let dropped = Status::unavailable("connection reset");
+    assert!(!retry.retries(&dropped, false));
+    assert!(retry.retries(&dropped, true));
+    // a handler reported an unavailable device
+    let device = TrustZoneCtrlError::new(
+        TrustZoneCtrlErrorCodes::FileReadError,
+        "unable to read /dev/optiga".to_string(),
+    );
+    let device = status(device.code().kind(), &device);
+    assert!(!retry.retries(&device, false));
+    assert!(retry.retries(&device, true));
+    // the server replied without details
+    let mut headers = MetadataMap::new();
+    headers.insert("content-type", "application/grpc".parse().unwrap());
+    let replied = Status::with_metadata(Code::Unavailable, "overloaded", headers);
+    assert!(!retry.retries(&replied, false));
+
+    assert!(retry.retries(&Status::deadline_exceeded(""), true));
+    assert!(!retry.retries(&Status::deadline_exceeded(""), false));
+    assert!(!retry.retries(&Status::invalid_argument(""), true));
+}
+
+#[tokio::test]
+async fn unavailable_devices_are_not_retried_for_unsafe_calls() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let trustzone = UnreadableTrustZone::default();
+    tokio::spawn(
+        Server::builder()
+            .add_service(TrustZoneCtrlServiceServer::new(
+                TrustZoneCtrlServiceManager {
+                    trustzone_ctrl: trustzone.clone().into(),
+                },
+            ))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    let retry = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(10),
+    };
+    let client = MechaClient::connect(ClientConfig::new(addr.to_string()).retry(retry))
+        .await
+        .unwrap();
+
+    let e = client
+        .trustzone()
+        .generate_key("0xE0F1", KeyType::Sign, KeySize::Ecc256, "key.pem")
+        .await
+        .unwrap_err();
+    let e = e.downcast_ref::<ClientError>().unwrap();
+    assert_eq!(e.grpc_code(), Some(Code::Unavailable));
+    assert_eq!(trustzone.calls.load(Ordering::SeqCst), 1);
+
+    // reading a certificate is safe to repeat
+    client
+        .trustzone()
+        .read_certificate("0xE0E0", "cert.pem")
+        .await
+        .unwrap_err();
+    assert_eq!(trustzone.calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn event_streams_outlive_the_call_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let events = EventManager::default();
+    tokio::spawn(
+        Server::builder()
+            .add_service(EventServiceServer::new(events.clone()))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    let config = ClientConfig::new(addr.to_string()).timeout(Duration::from_millis(100));
+    let client = MechaClient::connect(config).await.unwrap();
+
+    let mut stream = client.events().subscribe(&["display"]).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    events
+        .bus
+        .publish(Payload::Brightness(BrightnessChanged { brightness: 42 }));
+
+    let event = stream.next().await.unwrap().unwrap();
+    assert_eq!(event.topic, "display");
+
+    let error = client.events().subscribe(&["nope"]).await.unwrap_err();
+    let error = error.downcast_ref::<ClientError>().unwrap();
+    assert_eq!(error.grpc_code(), Some(Code::InvalidArgument));
+}
+
+#[tokio::test]
+async fn unsafe_calls_are_not_retried_when_the_connection_drops_mid_call() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server = listener.local_addr().unwrap();
+    let trustzone = UnreadableTrustZone {
+        delay: Duration::from_millis(500),
+        ..UnreadableTrustZone::default()
+    };
+    tokio::spawn(
+        Server::builder()
+            .add_service(TrustZoneCtrlServiceServer::new(
+                TrustZoneCtrlServiceManager {
+                    trustzone_ctrl: trustzone.clone().into(),
+                },
+            ))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    // forwards every connection to the server, the first one is closed as
+    // soon as a handler runs
+    let proxy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = proxy.local_addr().unwrap();
+    let entered = trustzone.entered.clone();
+    tokio::spawn(async move {
+        let mut first = true;
+        loop {
+            let (mut client, _) = proxy.accept().await.unwrap();
+            let mut upstream = TcpStream::connect(server).await.unwrap();
+            let forward = tokio::spawn(async move {
+                let _ = copy_bidirectional(&mut client, &mut upstream).await;
+            });
+            if first {
+                first = false;
+                entered.notified().await;
+                forward.abort();
+            }
+        }
+    });
+    let retry = RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(10),
+    };
+    let client = MechaClient::connect(ClientConfig::new(addr.to_string()).retry(retry))
+        .await
+        .unwrap();
+
+    client
+        .trustzone()
+        .generate_key("0xE0F1", KeyType::Sign, KeySize::Ecc256, "key.pem")
+        .await
+        .unwrap_err();
+    assert_eq!(trustzone.calls.load(Ordering::SeqCst), 1);
+}
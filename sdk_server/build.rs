@@ -1,4 +1,9 @@
+use std::{env, path::PathBuf};
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    //descriptor set of every package, served by grpc reflection
+    let descriptor_path = PathBuf::from(env::var("OUT_DIR")?).join("mecha_descriptor.bin");
+
     let network_manager = "./proto/network_manager.proto";
     let display_manager = "./proto/display_manager.proto";
     let motion_sensor_manager = "./proto/motion_sensor_manager.proto";
@@ -9,32 +14,47 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let trustzone_ctrl = "./proto/trustzone_ctrl.proto";
     let battery_ctrl = "./proto/battery_ctrl.proto";
     let bluetooth_manager = "./proto/bluetooth_manager.proto";
+    let events = "./proto/events.proto";
+    let audit = "./proto/audit.proto";
+    let capabilities = "./proto/capabilities.proto";
+    let settings = "./proto/settings.proto";
+    let automation = "./proto/automation.proto";
 
-    tonic_build::configure().build_server(true).compile(
-        &[
-            network_manager,
-            display_manager,
-            motion_sensor_manager,
-            led_manager,
-            device_info,
-            device_metrics,
-            cpu_governor_ctrl,
-            trustzone_ctrl,
-            battery_ctrl,
-            bluetooth_manager,
-        ],
-        &[
-            "./proto/network_manager",
-            "./proto/display_manager",
-            "./proto/motion_sensor_manager",
-            "./proto/led_manager",
-            "./proto/device_info",
-            "./proto/metrics_manager",
-            "./proto/cpu_governor_ctrl",
-            "./proto/trustzone_ctrl",
-            "./proto/battery_ctrl",
-            "./proto/bluetooth_manager",
-        ],
-    )?;
+    tonic_build::configure()
+        .build_server(true)
+        .file_descriptor_set_path(descriptor_path)
+        .compile(
+            &[
+                network_manager,
+                display_manager,
+                motion_sensor_manager,
+                led_manager,
+                device_info,
+                device_metrics,
+                cpu_governor_ctrl,
+                trustzone_ctrl,
+                battery_ctrl,
+                bluetooth_manager,
+                events,
+                audit,
+                capabilities,
+                settings,
+                automation,
+            ],
+            &[
+                // google/api/annotations.proto for the rest gateway mapping
+                "./proto",
+                "./proto/network_manager",
+                "./proto/display_manager",
+                "./proto/motion_sensor_manager",
+                "./proto/led_manager",
+                "./proto/device_info",
+                "./proto/metrics_manager",
+                "./proto/cpu_governor_ctrl",
+                "./proto/trustzone_ctrl",
+                "./proto/battery_ctrl",
+                "./proto/bluetooth_manager",
+            ],
+        )?;
     Ok(())
 }
@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use tracing::{trace, warn};
+
+use crate::configs::Audit;
+
+// one state changing rpc, a line of the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    // milliseconds since the unix epoch
+    pub timestamp: u64,
+    // token name or jwt subject, none when auth is disabled
+    pub caller: Option<String>,
+    pub role: Option<String>,
+    pub peer: String,
+    // `/package.Service/Method`
+    pub method: String,
+    // the decoded request with secrets redacted, null if it could not be read
+    pub request: serde_json::Value,
+    pub code: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+}
+
+// every set field has to match
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    pub caller: Option<String>,
+    pub method: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        let method = self.method.as_ref().map_or(true, |method| {
+            entry.method.trim_start_matches('/') == method
+                || entry.method.ends_with(&format!("/{}", method))
+                || entry.method.ends_with(&format!(".{}", method))
+        });
+        method
+            && self
+                .caller
+                .as_ref()
+                .map_or(true, |caller| entry.caller.as_ref() == Some(caller))
+            && self.since.map_or(true, |since| entry.timestamp >= since)
+            && self.until.map_or(true, |until| entry.timestamp <= until)
+    }
+}
+
+// append-only json lines file, entries are never rewritten. callers
+// serialize access, see `Device`
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+    max_size: u64,
+    keep: usize,
+}
+
+impl AuditLog {
+    pub fn new(audit: &Audit) -> Self {
+        AuditLog {
+            path: PathBuf::from(&audit.path),
+            max_size: audit.max_size,
+            keep: audit.keep.max(1),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let size = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == ErrorKind::NotFound => 0,
+            Err(e) => return Err(e).context(format!("unable to stat {}", self.path.display())),
+        };
+        if size > 0 && size + line.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o640)
+            .open(&self.path)
+            .with_context(|| format!("unable to open {}", self.path.display()))?;
+        file.write_all(&line)
+            .with_context(|| format!("unable to write {}", self.path.display()))?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    // path.<keep> is dropped, every other file moves up by one
+    fn rotate(&self) -> Result<()> {
+        trace!(task = "rotate", "rotating {}", self.path.display());
+        for index in (1..self.keep).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated(index + 1))
+                    .with_context(|| format!("unable to rotate {}", from.display()))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+            .with_context(|| format!("unable to rotate {}", self.path.display()))
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    // the newest `limit` matching entries of the current and rotated files,
+    // oldest first
+    pub fn query(&self, filter: &AuditFilter, limit: usize) -> Result<Vec<AuditEntry>> {
+        let mut files: Vec<_> = (1..=self.keep).rev().map(|i| self.rotated(i)).collect();
+        files.push(self.path.clone());
+
+        let mut entries = Vec::new();
+        for path in files {
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).context(format!("unable to open {}", path.display())),
+            };
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                match serde_json::from_str::<AuditEntry>(&line) {
+                    Ok(entry) if filter.matches(&entry) => entries.push(entry),
+                    Ok(_) => {}
+                    Err(e) => warn!(
+                        task = "query",
+                        "skipping invalid entry in {}: {}",
+                        path.display(),
+                        e
+                    ),
+                }
+            }
+        }
+        let skip = entries.len().saturating_sub(limit);
+        entries.drain(..skip);
+        Ok(entries)
+    }
+}
@@ -0,0 +1,536 @@
+use anyhow::{anyhow, bail, Result};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time::MissedTickBehavior;
+use tonic::Status;
+use tracing::{info, trace, warn};
+
+use crate::configs::{Actions, Conditions, LedName, Rule, WifiCondition};
+use crate::services::{
+    CpuControl, Device, DeviceMetricsInfo, DisplayControl, LedColor, LedControl,
+    MotionSensorControl, PowerSupplyInfo, WifiControl,
+};
+use crate::settings::{cpu_frequency, Persisted, USERSPACE_GOVERNOR};
+
+// device state the conditions are checked against. a reading that failed,
+// or whose service is not registered, is unset
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Readings {
+    // battery capacity in percent
+    pub battery: Option<u8>,
+    pub battery_status: Option<String>,
+    pub cpu_usage: Option<f32>,
+    pub memory_used: Option<u64>,
+    pub motion: Option<bool>,
+    // seconds since the sensor last reported motion
+    pub idle: Option<u64>,
+    pub wifi: Option<WifiCondition>,
+}
+
+// one condition of a rule and the reading it was checked against
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub condition: String,
+    pub reading: String,
+    pub holds: bool,
+}
+
+// every condition that is set, in the order of the `Conditions` fields
+pub fn check(conditions: &Conditions, readings: &Readings) -> Vec<Check> {
+    let mut checks = Vec::new();
+    if let Some(below) = conditions.battery_below {
+        push(
+            &mut checks,
+            "battery_below",
+            below,
+            readings.battery,
+            |capacity| capacity < below,
+        );
+    }
+    if let Some(above) = conditions.battery_above {
+        push(
+            &mut checks,
+            "battery_above",
+            above,
+            readings.battery,
+            |capacity| capacity > above,
+        );
+    }
+    if let Some(status) = &conditions.battery_status {
+        push(
+            &mut checks,
+            "battery_status",
+            status,
+            readings.battery_status.as_ref(),
+            |current| current.eq_ignore_ascii_case(status),
+        );
+    }
+    if let Some(above) = conditions.cpu_usage_above {
+        push(
+            &mut checks,
+            "cpu_usage_above",
+            above,
+            readings.cpu_usage,
+            |usage| usage > above,
+        );
+    }
+    if let Some(above) = conditions.memory_used_above {
+        push(
+            &mut checks,
+            "memory_used_above",
+            above,
+            readings.memory_used,
+            |used| used > above,
+        );
+    }
+    if let Some(motion) = conditions.motion {
+        push(&mut checks, "motion", motion, readings.motion, |current| {
+            current == motion
+        });
+    }
+    if let Some(idle_for) = conditions.idle_for {
+        push(&mut checks, "idle_for", idle_for, readings.idle, |idle| {
+            idle >= idle_for
+        });
+    }
+    if let Some(wifi) = conditions.wifi {
+        push(
+            &mut checks,
+            "wifi",
+            wifi_state(wifi),
+            readings.wifi.map(wifi_state),
+            |current| current == wifi_state(wifi),
+        );
+    }
+    checks
+}
+
+fn push<T: fmt::Display, R: fmt::Display>(
+    checks: &mut Vec<Check>,
+    name: &str,
+    expected: T,
+    reading: Option<R>,
+    holds: impl FnOnce(R) -> bool,
+) {
+    checks.push(Check {
+        condition: format!("{} {}", name, expected),
+        reading: match &reading {
+            Some(reading) => reading.to_string(),
+            None => "unknown".to_string(),
+        },
+        holds: reading.map(holds).unwrap_or(false),
+    });
+}
+
+fn wifi_state(wifi: WifiCondition) -> &'static str {
+    match wifi {
+        WifiCondition::Connected => "connected",
+        WifiCondition::Disconnected => "disconnected",
+    }
+}
+
+// a rule needs at least one condition, and all of them have to hold
+pub fn holds(checks: &[Check]) -> bool {
+    !checks.is_empty() && checks.iter().all(|check| check.holds)
+}
+
+// the actions in the order they are applied
+pub fn describe(actions: &Actions) -> Vec<String> {
+    let mut described = Vec::new();
+    if let Some(brightness) = actions.brightness {
+        described.push(format!("brightness {}", brightness));
+    }
+    for led in &actions.set_leds {
+        described.push(format!("set_led {}", led_name(*led)));
+    }
+    for led in &actions.clear_leds {
+        described.push(format!("clear_led {}", led_name(*led)));
+    }
+    if let Some(governor) = &actions.governor {
+        described.push(format!("governor {}", governor));
+    }
+    if let Some(khz) = actions.cpu_frequency {
+        described.push(format!("cpu_frequency {}", khz));
+    }
+    described
+}
+
+fn led_name(led: LedName) -> &'static str {
+    match led {
+        LedName::Red => "red",
+        LedName::Green => "green",
+        LedName::Blue => "blue",
+    }
+}
+
+fn led_color(led: LedName) -> LedColor {
+    match led {
+        LedName::Red => LedColor::Red,
+        LedName::Green => LedColor::Green,
+        LedName::Blue => LedColor::Blue,
+    }
+}
+
+// a rule's actions that are due, see `Automation::evaluate`
+#[derive(Debug, Clone)]
+pub struct Fired {
+    pub rule: String,
+    pub actions: Actions,
+}
+
+#[derive(Debug, Clone)]
+pub struct RuleStatus {
+    pub rule: Rule,
+    pub enabled: bool,
+    // `then` was applied and `otherwise` is next
+    pub active: bool,
+    // times `then` or `otherwise` ran, and the last time in milliseconds
+    // since the unix epoch
+    pub fired: u64,
+    pub last_fired: u64,
+    pub last_error: Option<String>,
+}
+
+// what a rule makes of the latest readings, nothing is applied
+#[derive(Debug, Clone)]
+pub struct DryRun {
+    pub checks: Vec<Check>,
+    pub holds: bool,
+    // `then` when the conditions hold, `otherwise` when they do not
+    pub actions: Option<Actions>,
+    // milliseconds since the unix epoch
+    pub readings_at: u64,
+}
+
+// the configured rules and whether each is enabled and active, shared by
+// the scheduler and AutomationService. enabling or disabling a rule lasts
+// until the next start
+#[derive(Debug, Clone, Default)]
+pub struct Automation {
+    rules: Arc<Mutex<Vec<RuleStatus>>>,
+    readings: Arc<Mutex<Option<(u64, Readings)>>>,
+}
+
+impl Automation {
+    pub fn new(rules: &[Rule]) -> Self {
+        let rules = rules
+            .iter()
+            .map(|rule| RuleStatus {
+                rule: rule.clone(),
+                enabled: rule.enabled,
+                active: false,
+                fired: 0,
+                last_fired: 0,
+                last_error: None,
+            })
+            .collect();
+        Automation {
+            rules: Arc::new(Mutex::new(rules)),
+            readings: Arc::default(),
+        }
+    }
+
+    pub fn rules(&self) -> Vec<RuleStatus> {
+        self.lock_rules().clone()
+    }
+
+    // an enabled rule starts inactive, so its `then` runs on the next
+    // evaluation its conditions hold. disabling does not run `otherwise`
+    pub fn set_enabled(&self, name: &str, enabled: bool) -> Result<RuleStatus> {
+        let mut rules = self.lock_rules();
+        let status = match rules.iter_mut().find(|status| status.rule.name == name) {
+            Some(status) => status,
+            None => return Err(unknown_rule(name)),
+        };
+        if status.enabled != enabled {
+            status.enabled = enabled;
+            status.active = false;
+        }
+        Ok(status.clone())
+    }
+
+    // the actions due with these readings: `then` of the enabled rules whose
+    // conditions started to hold, `otherwise` of the ones whose stopped
+    pub fn evaluate(&self, readings: Readings) -> Vec<Fired> {
+        let mut fired = Vec::new();
+        for status in self.lock_rules().iter_mut().filter(|status| status.enabled) {
+            let holds = holds(&check(&status.rule.when, &readings));
+            if holds == status.active {
+                continue;
+            }
+            status.active = holds;
+            let actions = if holds {
+                Some(status.rule.then.clone())
+            } else {
+                status.rule.otherwise.clone()
+            };
+            if let Some(actions) = actions {
+                fired.push(Fired {
+                    rule: status.rule.name.clone(),
+                    actions,
+                });
+            }
+        }
+        *self.lock_readings() = Some((now(), readings));
+        fired
+    }
+
+    // outcome of applying what `evaluate` returned. a rule whose actions
+    // failed stays active, it is not retried until its conditions change
+    pub fn record(&self, name: &str, result: &Result<()>) {
+        if let Some(status) = self
+            .lock_rules()
+            .iter_mut()
+            .find(|status| status.rule.name == name)
+        {
+            status.fired += 1;
+            status.last_fired = now();
+            status.last_error = result.as_ref().err().map(|e| format!("{:#}", e));
+        }
+    }
+
+    // disabled rules can be dry run too
+    pub fn dry_run(&self, name: &str) -> Result<DryRun> {
+        let rule = match self
+            .lock_rules()
+            .iter()
+            .find(|status| status.rule.name == name)
+        {
+            Some(status) => status.rule.clone(),
+            None => return Err(unknown_rule(name)),
+        };
+        let (readings_at, readings) = self
+            .lock_readings()
+            .clone()
+            .ok_or_else(|| anyhow!(Status::unavailable("no readings yet")))?;
+        let checks = check(&rule.when, &readings);
+        let holds = holds(&checks);
+        Ok(DryRun {
+            actions: if holds {
+                Some(rule.then)
+            } else {
+                rule.otherwise
+            },
+            checks,
+            holds,
+            readings_at,
+        })
+    }
+
+    fn lock_rules(&self) -> MutexGuard<'_, Vec<RuleStatus>> {
+        self.rules.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_readings(&self) -> MutexGuard<'_, Option<(u64, Readings)>> {
+        self.readings.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn unknown_rule(name: &str) -> anyhow::Error {
+    Status::not_found(format!("unknown rule {}", name)).into()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// where the readings come from, only the hardware of registered services
+// is read
+pub struct Sensors<P, M, S, W> {
+    pub battery: Option<Device<P>>,
+    pub metrics: Option<Device<Mutex<M>>>,
+    pub motion: Option<Device<S>>,
+    pub wifi: Option<W>,
+}
+
+impl<P, M, S, W> Sensors<P, M, S, W>
+where
+    P: PowerSupplyInfo + Send + Sync + 'static,
+    M: DeviceMetricsInfo + Send + 'static,
+    S: MotionSensorControl + Send + Sync + 'static,
+    W: WifiControl + Send + Sync,
+{
+    // `last_motion` is moved forward when the sensor reports motion
+    pub async fn read(&self, last_motion: &mut Instant) -> Readings {
+        let mut readings = Readings::default();
+        if let Some(battery) = &self.battery {
+            match battery
+                .run("automation_battery", |battery| battery.info())
+                .await
+            {
+                Ok(info) => {
+                    readings.battery = Some(info.capacity);
+                    readings.battery_status = Some(info.status);
+                }
+                Err(e) => trace!(task = "automation", "unable to read battery: {:#}", e),
+            }
+        }
+        if let Some(metrics) = &self.metrics {
+            let usage = metrics
+                .run("automation_metrics", |metrics| {
+                    let mut metrics = metrics.lock().unwrap_or_else(PoisonError::into_inner);
+                    metrics.refresh();
+                    Ok((metrics.get_cpu_usage()?, metrics.get_memory_usage()?))
+                })
+                .await;
+            match usage {
+                Ok((cpu, memory)) => {
+                    readings.cpu_usage = Some(cpu);
+                    readings.memory_used = Some(memory);
+                }
+                Err(e) => trace!(task = "automation", "unable to read metrics: {:#}", e),
+            }
+        }
+        if let Some(motion) = &self.motion {
+            match motion
+                .run("automation_motion", |sensor| {
+                    sensor.detect_motion_sensor_event()
+                })
+                .await
+            {
+                Ok(moving) => {
+                    if moving {
+                        *last_motion = Instant::now();
+                    }
+                    readings.motion = Some(moving);
+                }
+                Err(e) => trace!(task = "automation", "unable to read motion: {:#}", e),
+            }
+            readings.idle = Some(last_motion.elapsed().as_secs());
+        }
+        if let Some(wifi) = &self.wifi {
+            match wifi.get_known_wifi_list().await {
+                Ok(networks) => {
+                    readings.wifi = Some(
+                        if networks
+                            .iter()
+                            .any(|network| network.flags.contains("[CURRENT]"))
+                        {
+                            WifiCondition::Connected
+                        } else {
+                            WifiCondition::Disconnected
+                        },
+                    )
+                }
+                Err(e) => trace!(task = "automation", "unable to read wifi: {:#}", e),
+            }
+        }
+        readings
+    }
+}
+
+// the controllers the actions drive. changes bypass the settings store,
+// rules reapply them instead of a restart
+pub struct Outputs<D, L, C> {
+    pub display: Option<Device<Persisted<D>>>,
+    pub led: Option<Device<Persisted<L>>>,
+    pub cpu: Option<Device<Persisted<C>>>,
+}
+
+impl<D, L, C> Outputs<D, L, C>
+where
+    D: DisplayControl + Send + Sync + 'static,
+    L: LedControl + Send + Sync + 'static,
+    C: CpuControl + Send + Sync + 'static,
+{
+    // stops at the first action that fails
+    pub async fn apply(&self, actions: &Actions) -> Result<()> {
+        if let Some(brightness) = actions.brightness {
+            let display = Self::output(&self.display, "display")?;
+            display
+                .run("automation_brightness", move |display| {
+                    display.inner().set_display_brightness(brightness)
+                })
+                .await?;
+        }
+        if !actions.set_leds.is_empty() || !actions.clear_leds.is_empty() {
+            let led = Self::output(&self.led, "led")?;
+            let (set, clear) = (actions.set_leds.clone(), actions.clear_leds.clone());
+            led.run("automation_led", move |led| {
+                for color in set {
+                    led.inner().set_led(led_color(color))?;
+                }
+                for color in clear {
+                    led.inner().clear_led(led_color(color))?;
+                }
+                Ok(())
+            })
+            .await?;
+        }
+        if actions.governor.is_some() || actions.cpu_frequency.is_some() {
+            let frequency = match actions.cpu_frequency {
+                Some(khz) => match cpu_frequency(&khz.to_string()) {
+                    Some(frequency) => Some(frequency),
+                    None => bail!("invalid cpu frequency {}", khz),
+                },
+                None => None,
+            };
+            let governor = actions.governor.clone();
+            let cpu = Self::output(&self.cpu, "cpu_governor")?;
+            cpu.run("automation_cpu", move |cpu| {
+                if let Some(governor) = governor {
+                    cpu.inner().set_cpu_governor(&governor)?;
+                }
+                if let Some(frequency) = frequency {
+                    // cpufreq only takes a frequency under userspace
+                    if cpu.inner().get_cpu_governor()?.trim() != USERSPACE_GOVERNOR {
+                        cpu.inner().set_cpu_governor(USERSPACE_GOVERNOR)?;
+                    }
+                    cpu.inner().set_cpu_frequency(frequency)?;
+                }
+                Ok(())
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    fn output<'a, T>(output: &'a Option<Device<T>>, service: &str) -> Result<&'a Device<T>> {
+        output
+            .as_ref()
+            .ok_or_else(|| anyhow!("the {} service is not registered", service))
+    }
+}
+
+// read the sensors every `interval`, evaluate the rules and apply what is
+// due. a failed action is logged and recorded on its rule
+pub async fn run_automation<P, M, S, W, D, L, C>(
+    automation: Automation,
+    sensors: Sensors<P, M, S, W>,
+    outputs: Outputs<D, L, C>,
+    interval: Duration,
+) where
+    P: PowerSupplyInfo + Send + Sync + 'static,
+    M: DeviceMetricsInfo + Send + 'static,
+    S: MotionSensorControl + Send + Sync + 'static,
+    W: WifiControl + Send + Sync,
+    D: DisplayControl + Send + Sync + 'static,
+    L: LedControl + Send + Sync + 'static,
+    C: CpuControl + Send + Sync + 'static,
+{
+    trace!(task = "automation", "init");
+    let mut last_motion = Instant::now();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let readings = sensors.read(&mut last_motion).await;
+        for fired in automation.evaluate(readings) {
+            info!(
+                task = "automation",
+                "rule {}: {}",
+                fired.rule,
+                describe(&fired.actions).join(", ")
+            );
+            let result = outputs.apply(&fired.actions).await;
+            if let Err(e) = &result {
+                warn!(task = "automation", "rule {} failed: {:#}", fired.rule, e);
+            }
+            automation.record(&fired.rule, &result);
+        }
+    }
+}
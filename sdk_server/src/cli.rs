@@ -0,0 +1,85 @@
+use anyhow::Result;
+use clap::Parser;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use crate::configs::{BaseConfig, LogFormat};
+
+// command line options, each one can also be set through its MECHA_*
+// environment variable. both take precedence over Config.yaml
+#[derive(Debug, Clone, Parser)]
+#[command(name = "mecha_sdk_server", version, about = "Mecha edge gRPC server")]
+pub struct Cli {
+    /// Path of the yaml configuration
+    #[arg(short, long, env = "MECHA_CONFIG", default_value = "./Config.yaml")]
+    pub config: PathBuf,
+
+    /// Port to listen on, overrides server.port
+    #[arg(short, long, env = "MECHA_PORT")]
+    pub port: Option<u16>,
+
+    /// Address to bind, overrides server.bind
+    #[arg(short, long, env = "MECHA_BIND")]
+    pub bind: Option<IpAddr>,
+
+    /// Log level (error, warn, info, debug, trace) or filter directives, overrides logging.level
+    #[arg(short, long, env = "MECHA_LOG_LEVEL")]
+    pub log_level: Option<String>,
+
+    /// Log output, overrides logging.format
+    #[arg(long, env = "MECHA_LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
+
+    /// Run the server against the simulated sysfs tree, overrides simulation.enabled
+    #[arg(long, env = "MECHA_SIMULATION")]
+    pub simulation: Option<bool>,
+
+    /// Root of the simulated sysfs tree, overrides simulation.root
+    #[arg(long, env = "MECHA_SIMULATION_ROOT")]
+    pub simulation_root: Option<String>,
+
+    /// Load and validate the configuration, then exit without serving
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print every problem found in the configuration and exit, non-zero when there are any
+    #[arg(long)]
+    pub check_config: bool,
+}
+
+impl Cli {
+    // the yaml config with every override applied and its interfaces resolved
+    pub fn load_config(&self) -> Result<BaseConfig> {
+        let mut config = self.load_file()?;
+        config.resolve_interfaces()?;
+        Ok(config)
+    }
+
+    // the yaml config with every override applied, interfaces as written
+    pub fn load_file(&self) -> Result<BaseConfig> {
+        let mut config = BaseConfig::load(&self.config)?;
+        self.apply(&mut config);
+        Ok(config)
+    }
+
+    pub fn apply(&self, config: &mut BaseConfig) {
+        if let Some(port) = self.port {
+            config.server.port = port;
+        }
+        if let Some(bind) = self.bind {
+            config.server.bind = bind;
+        }
+        if let Some(enabled) = self.simulation {
+            config.simulation.enabled = enabled;
+        }
+        if let Some(root) = &self.simulation_root {
+            config.simulation.root = root.clone();
+        }
+        if let Some(level) = &self.log_level {
+            config.logging.level = level.clone();
+        }
+        if let Some(format) = self.log_format {
+            config.logging.format = format;
+        }
+    }
+}
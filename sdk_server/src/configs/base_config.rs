@@ -1,44 +1,632 @@
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use mecha_simulator::SysfsRoot;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+use tracing::warn;
+
+use super::profile::merge;
+use crate::configs::{
+    read_compatible, strip_selectors, BoardProfile, BoardProfiles, ConfigReport, Discovery,
+    AUTO_DETECT, DEVICE_TREE_COMPATIBLE,
+};
+use crate::services::{DEFAULT_DEVICE_TIMEOUT, DEFAULT_DEVICE_WORKERS};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BaseConfig {
+    // board profile the interfaces start from, `auto` to detect it
+    #[serde(default)]
+    pub name: String,
+    // user board profiles, replacing bundled ones of the same name
+    #[serde(default)]
+    pub profiles_dir: Option<String>,
     pub server: GrpcConfig,
+    // keys set here override the board profile, entries are paths or
+    // selectors, see `Discovery`
+    #[serde(default, deserialize_with = "interfaces_with_selectors")]
     pub interfaces: Interfaces,
+    #[serde(default)]
+    pub simulation: Simulation,
+    #[serde(default)]
+    pub auth: Option<Auth>,
+    #[serde(default)]
+    pub services: Services,
+    #[serde(default)]
+    pub logging: Logging,
+    #[serde(default)]
+    pub audit: Option<Audit>,
+    #[serde(default)]
+    pub settings: Option<Settings>,
+    #[serde(default)]
+    pub automation: Option<Automation>,
+    // keys present in the yaml that no field consumed
+    #[serde(skip)]
+    pub unknown_keys: Vec<String>,
+    // selectors that matched no device, by key, with the reason
+    #[serde(skip)]
+    pub unresolved: Vec<(String, String)>,
+    // the `interfaces` section as written, laid over the board profile
+    #[serde(skip)]
+    interface_overrides: Value,
+}
+
+impl BaseConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let yaml = read_to_string(path)
+            .with_context(|| format!("unable to open config file {}", path.display()))?;
+        let mut unknown_keys = Vec::new();
+        let mut config: BaseConfig =
+            serde_ignored::deserialize(serde_yaml::Deserializer::from_str(&yaml), |key| {
+                unknown_keys.push(key.to_string())
+            })
+            .with_context(|| format!("invalid config file {}", path.display()))?;
+        config.interface_overrides = serde_yaml::from_str::<Value>(&yaml)
+            .ok()
+            .and_then(|yaml| yaml.get("interfaces").cloned())
+            .unwrap_or_default();
+        // the section is read as a whole, its unknown keys are not seen above
+        unknown_keys.extend(Interfaces::unknown_keys(&config.interface_overrides)?);
+        config.unknown_keys = unknown_keys;
+        Ok(config)
+    }
+
+    // the interfaces of the board profile with the selectors resolved and,
+    // in simulation mode, the device paths rebased onto the simulated tree
+    pub fn resolve_interfaces(&mut self) -> Result<()> {
+        let root = if self.simulation.enabled {
+            SysfsRoot::new(&self.simulation.root)
+        } else {
+            SysfsRoot::default()
+        };
+        self.apply_profile(&root)?;
+        if self.simulation.enabled {
+            self.interfaces.rebase(&root);
+        }
+        Ok(())
+    }
+
+    // replace the interfaces with the board profile named by `name`, or the
+    // one matching the device tree under `root` for `auto`, and the keys of
+    // the `interfaces` section on top. a name without a profile, or a board
+    // `auto` has no profile for, keeps the interfaces of the file. selectors
+    // are resolved against the devices under `root`
+    pub fn apply_profile(&mut self, root: &SysfsRoot) -> Result<()> {
+        let mut interfaces = match self.profile(root)? {
+            Some(profile) => {
+                self.name = profile.name.clone();
+                profile.interfaces(&self.interface_overrides)
+            }
+            None => {
+                let mut interfaces = serde_yaml::to_value(&self.interfaces)?;
+                merge(&mut interfaces, &self.interface_overrides);
+                interfaces
+            }
+        };
+        self.unresolved = Discovery::new(root).resolve_interfaces(&mut interfaces)?;
+        self.interfaces = serde_yaml::from_value(interfaces)
+            .with_context(|| format!("invalid interfaces for board profile {}", self.name))?;
+        Ok(())
+    }
+
+    fn profile(&self, root: &SysfsRoot) -> Result<Option<BoardProfile>> {
+        if self.name.is_empty() {
+            return Ok(None);
+        }
+        let profiles = BoardProfiles::load(self.profiles_dir.as_deref().map(Path::new))?;
+        if self.name != AUTO_DETECT {
+            return Ok(profiles.get(&self.name).cloned());
+        }
+        let path = root.rebase(DEVICE_TREE_COMPATIBLE);
+        let compatible = read_compatible(Path::new(&path))?;
+        let profile = profiles.detect(&compatible).cloned();
+        if profile.is_none() {
+            // an unlisted board still starts, on the interfaces of the file
+            warn!(
+                task = "apply_profile",
+                "no board profile matches {} ({}), known profiles: {}, using the interfaces of the config file",
+                path,
+                compatible.join(", "),
+                profiles.names().join(", ")
+            );
+        }
+        Ok(profile)
+    }
+
+    // full validation pass, see `ConfigReport`
+    pub fn check(&self) -> ConfigReport {
+        ConfigReport::new(self)
+    }
+
+    // fail on errors, warnings are only logged
+    pub fn validate(&self) -> Result<()> {
+        let report = self.check();
+        for issue in report.warnings() {
+            warn!(task = "validate_config", "{}", issue);
+        }
+        let errors = report.errors().count();
+        if errors > 0 {
+            bail!("{} configuration error(s):\n{}", errors, report);
+        }
+        Ok(())
+    }
+}
+
+// json lines record of every state changing rpc, rotated to `path.1` ..
+// `path.<keep>` once the file would grow past `max_size` bytes
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Audit {
+    pub path: String,
+    #[serde(default = "default_audit_max_size")]
+    pub max_size: u64,
+    #[serde(default = "default_audit_keep")]
+    pub keep: usize,
+}
+
+fn default_audit_max_size() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_audit_keep() -> usize {
+    5
+}
+
+// device settings saved as they change and restored on startup
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Settings {
+    pub path: String,
+}
+
+// rules evaluated every `interval` seconds against the battery, metrics,
+// motion and wifi state, see `crate::automation`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Automation {
+    #[serde(default = "default_automation_interval")]
+    pub interval: u64,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+fn default_automation_interval() -> u64 {
+    5
+}
+
+// `then` runs once when every condition of `when` starts to hold and
+// `otherwise` once when they stop holding
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Rule {
+    pub name: String,
+    #[serde(default = "default_rule_enabled")]
+    pub enabled: bool,
+    pub when: Conditions,
+    pub then: Actions,
+    #[serde(default)]
+    pub otherwise: Option<Actions>,
+}
+
+fn default_rule_enabled() -> bool {
+    true
+}
+
+// a condition on a state that can not be read does not hold
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Conditions {
+    // battery capacity in percent
+    pub battery_below: Option<u8>,
+    pub battery_above: Option<u8>,
+    // power supply status, e.g. Charging, Discharging or Full
+    pub battery_status: Option<String>,
+    // percent
+    pub cpu_usage_above: Option<f32>,
+    // bytes
+    pub memory_used_above: Option<u64>,
+    // the motion sensor reports motion, or not
+    pub motion: Option<bool>,
+    // seconds without motion
+    pub idle_for: Option<u64>,
+    pub wifi: Option<WifiCondition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WifiCondition {
+    Connected,
+    Disconnected,
+}
+
+// applied in the order of the fields, the cpu frequency switches the
+// governor to userspace first when another one is set
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Actions {
+    pub brightness: Option<u8>,
+    pub set_leds: Vec<LedName>,
+    pub clear_leds: Vec<LedName>,
+    // one of `scaling_available_governors`, e.g. powersave
+    pub governor: Option<String>,
+    // kHz, one of the `CpuFrequency` steps
+    pub cpu_frequency: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LedName {
+    Red,
+    Green,
+    Blue,
+}
+
+// `level` is a tracing filter, either a level (`info`) or per crate
+// directives (`info,mecha_display_ctrl=trace`). with `otlp` set, rpc and
+// device spans are also exported to an OpenTelemetry collector
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Logging {
+    pub level: String,
+    pub format: LogFormat,
+    pub otlp: Option<Otlp>,
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Logging {
+            level: "info".to_string(),
+            format: LogFormat::Text,
+            otlp: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+    Journald,
+}
+
+// grpc endpoint of the collector, e.g. http://localhost:4317
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Otlp {
+    pub endpoint: String,
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+fn default_service_name() -> String {
+    "mecha_sdk_server".to_string()
+}
+
+// when enabled every sysfs/devfs path is rebased onto `root`, which is
+// populated with a fake board tree at startup
+#[derive(Debug, Deserialize, Serialize, Default)]
+pub struct Simulation {
+    pub enabled: bool,
+    pub root: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GrpcConfig {
+    // `bind` and `port` make up the tcp listener used when no `listeners`
+    // are declared
+    #[serde(default = "default_port")]
     pub port: u16,
+    #[serde(default = "default_bind")]
+    pub bind: IpAddr,
+    #[serde(default)]
+    pub listeners: Vec<Listener>,
+    #[serde(default)]
+    pub tls: Option<Tls>,
+    #[serde(default)]
+    pub gateway: Option<Gateway>,
+    #[serde(default)]
+    pub prometheus: Option<Prometheus>,
+    // seconds between two hardware probes of the health service
+    #[serde(default = "default_health_interval")]
+    pub health_interval: u64,
+    // seconds in-flight rpcs and streams get to finish on shutdown
+    #[serde(default = "default_drain_timeout")]
+    pub drain_timeout: u64,
+    // sysfs and trustm_* calls running at once across all devices
+    #[serde(default = "default_device_workers")]
+    pub device_workers: usize,
+    // seconds a device call may take before the rpc fails with DEADLINE_EXCEEDED
+    #[serde(default = "default_device_timeout")]
+    pub device_timeout: u64,
+    // seconds between two reads of the state behind the EventService topics
+    #[serde(default = "default_event_interval")]
+    pub event_interval: u64,
+}
+
+impl GrpcConfig {
+    pub fn listeners(&self) -> Vec<Listener> {
+        if !self.listeners.is_empty() {
+            return self.listeners.clone();
+        }
+        vec![Listener {
+            tcp: Some(SocketAddr::new(self.bind, self.port)),
+            ..Listener::default()
+        }]
+    }
+}
+
+// either a tcp address or a unix socket path. the socket file is created
+// with `mode` (octal, e.g. "0660") and handed to `owner`/`group`, given as
+// names or numeric ids, so local clients are authorized by file permissions
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Listener {
+    pub tcp: Option<SocketAddr>,
+    pub unix: Option<String>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub mode: Option<String>,
+}
+
+fn default_port() -> u16 {
+    50052
+}
+
+fn default_bind() -> IpAddr {
+    IpAddr::V4(Ipv4Addr::UNSPECIFIED)
 }
 
+fn default_health_interval() -> u64 {
+    30
+}
+
+fn default_drain_timeout() -> u64 {
+    10
+}
+
+fn default_event_interval() -> u64 {
+    1
+}
+
+fn default_device_workers() -> usize {
+    DEFAULT_DEVICE_WORKERS
+}
+
+fn default_device_timeout() -> u64 {
+    DEFAULT_DEVICE_TIMEOUT.as_secs()
+}
+
+// optional http/1.1 front end for browsers, grpc-web for every service and
+// the json mapping of the `google.api.http` annotations in the protos
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Gateway {
+    pub listen: SocketAddr,
+    #[serde(default)]
+    pub cors: Cors,
+}
+
+// plain http listener serving `GET /metrics` for prometheus scrapers, it
+// carries no tls or token checks so bind it to a trusted interface
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Prometheus {
+    pub listen: SocketAddr,
+}
+
+// origins a browser may call the gateway from, `*` allows any. without
+// origins no cors headers are sent and only same-origin pages can call it
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Cors {
+    pub allowed_origins: Vec<String>,
+    // seconds a browser may cache a preflight response
+    pub max_age: u64,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Cors {
+            allowed_origins: Vec::new(),
+            max_age: 3600,
+        }
+    }
+}
+
+// pem encoded server identity, when `client_ca` is set clients presenting a
+// certificate signed by it are verified, `require_client_cert` rejects the
+// ones that present none
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Tls {
+    pub cert: String,
+    pub key: String,
+    #[serde(default)]
+    pub client_ca: Option<String>,
+    #[serde(default)]
+    pub require_client_cert: bool,
+}
+
+// bearer token authentication, callers are mapped to a role either through a
+// static token or the `role` claim of a HS256 jwt signed with `jwt_secret`.
+// each role lists the services (`MetricsService`) or methods
+// (`TrustZoneCtrlService/SignData`) it may call, `*` allows everything
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Auth {
+    #[serde(default)]
+    pub tokens: Vec<StaticToken>,
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+    pub roles: HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StaticToken {
+    pub name: String,
+    pub token: String,
+    pub role: String,
+}
+
+// every grpc service is enabled by default. a service with `probe` set is
+// also left out when its hardware probe fails at startup, so one binary can
+// serve board variants without e.g. a trustzone chip or bluetooth radio
 #[derive(Debug, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct Services {
+    pub network: ServiceToggle,
+    pub display: ServiceToggle,
+    pub motion_sensor: ServiceToggle,
+    pub led: ServiceToggle,
+    pub device_info: ServiceToggle,
+    pub metrics: ServiceToggle,
+    pub cpu_governor: ServiceToggle,
+    pub trustzone: ServiceToggle,
+    pub battery: ServiceToggle,
+    pub bluetooth: ServiceToggle,
+    pub events: ServiceToggle,
+    pub audit: ServiceToggle,
+    pub capabilities: ServiceToggle,
+    pub settings: ServiceToggle,
+    pub automation: ServiceToggle,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServiceToggle {
+    pub enabled: bool,
+    pub probe: bool,
+}
+
+impl Default for ServiceToggle {
+    fn default() -> Self {
+        ServiceToggle {
+            enabled: true,
+            probe: true,
+        }
+    }
+}
+
+// display, motion_sensor, led and battery are required, a missing section
+// is reported by the validation pass instead of failing the parse
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
 pub struct Interfaces {
     pub display: Display,
     pub motion_sensor: Gyroscope,
     pub led: Led,
     pub battery: Battery,
+    pub cpu: Cpu,
+    pub adc: Adc,
+    pub camera: Camera,
+    pub audio: Audio,
 }
-#[derive(Debug, Deserialize, Serialize, Default)]
+
+impl Interfaces {
+    // keys of an `interfaces` section that no field consumes, selectors count
+    // as set
+    pub fn unknown_keys(interfaces: &Value) -> Result<Vec<String>> {
+        if interfaces.is_null() {
+            return Ok(Vec::new());
+        }
+        let mut interfaces = interfaces.clone();
+        strip_selectors(&mut interfaces);
+        let mut unknown = Vec::new();
+        let _: Interfaces = serde_ignored::deserialize(interfaces, |key| {
+            unknown.push(format!("interfaces.{}", key))
+        })?;
+        Ok(unknown)
+    }
+
+    pub fn rebase(&mut self, root: &SysfsRoot) {
+        self.display.device = root.rebase(&self.display.device);
+        self.motion_sensor.x_axis = root.rebase(&self.motion_sensor.x_axis);
+        self.motion_sensor.y_axis = root.rebase(&self.motion_sensor.y_axis);
+        self.motion_sensor.z_axis = root.rebase(&self.motion_sensor.z_axis);
+        self.led.red_led = root.rebase(&self.led.red_led);
+        self.led.green_led = root.rebase(&self.led.green_led);
+        self.led.blue_led = root.rebase(&self.led.blue_led);
+        self.battery.device = root.rebase(&self.battery.device);
+        self.battery.current = root.rebase(&self.battery.current);
+        self.battery.capacity = root.rebase(&self.battery.capacity);
+        self.battery.voltage = root.rebase(&self.battery.voltage);
+        self.cpu.device = root.rebase(&self.cpu.device);
+        self.adc.channel_1 = root.rebase(&self.adc.channel_1);
+        self.adc.channel_2 = root.rebase(&self.adc.channel_2);
+        self.adc.sampling_frequency = root.rebase(&self.adc.sampling_frequency);
+        self.camera.device = root.rebase(&self.camera.device);
+    }
+}
+
+// selectors read as unset until `BaseConfig::apply_profile` resolves them
+fn interfaces_with_selectors<'de, D>(deserializer: D) -> std::result::Result<Interfaces, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut interfaces = Value::deserialize(deserializer)?;
+    if interfaces.is_null() {
+        interfaces = Value::Mapping(Mapping::new());
+    }
+    strip_selectors(&mut interfaces);
+    Interfaces::deserialize(interfaces).map_err(D::Error::custom)
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
 pub struct Display {
     pub device: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
 pub struct Gyroscope {
     pub x_axis: String,
     pub y_axis: String,
     pub z_axis: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
 pub struct Led {
     pub red_led: String,
     pub green_led: String,
     pub blue_led: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
 pub struct Battery {
     pub device: String,
     pub current: String,
+    pub capacity: String,
+    pub voltage: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Cpu {
+    pub device: String,
+}
+
+impl Default for Cpu {
+    fn default() -> Self {
+        Cpu {
+            device: String::from("/sys/devices/system/cpu/cpu0/cpufreq"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct Adc {
+    pub channel_1: String,
+    pub channel_2: String,
+    pub sampling_frequency: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct Camera {
+    pub device: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct Audio {
+    pub audio_file: String,
 }
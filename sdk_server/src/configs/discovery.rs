@@ -0,0 +1,228 @@
+use anyhow::{bail, Context, Result};
+use mecha_simulator::SysfsRoot;
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const BACKLIGHT: &str = "/sys/class/backlight";
+const LEDS: &str = "/sys/class/leds";
+const IIO: &str = "/sys/bus/iio/devices";
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+const CPUFREQ: &str = "/sys/devices/system/cpu/cpufreq";
+const CPU: &str = "/sys/devices/system/cpu";
+
+// an interface entry naming a device by class and attributes instead of by
+// path, e.g. `{iio: {name: bmi088_gyro}, attribute: in_anglvel_x_raw}`.
+// exactly one class is set
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Selector {
+    pub backlight: Option<Match>,
+    pub led: Option<Match>,
+    pub iio: Option<Match>,
+    pub power_supply: Option<Match>,
+    // the cpufreq policy covering this cpu
+    pub cpufreq: Option<u32>,
+    // file in the device directory, `brightness` for backlights and leds and
+    // `uevent` for power supplies unless set. required for iio devices
+    pub attribute: Option<String>,
+}
+
+impl Selector {
+    // exactly one class, and an attribute for iio devices
+    pub fn check(&self) -> Result<()> {
+        let classes = [
+            self.backlight.is_some(),
+            self.led.is_some(),
+            self.iio.is_some(),
+            self.power_supply.is_some(),
+            self.cpufreq.is_some(),
+        ];
+        if classes.iter().filter(|set| **set).count() != 1 {
+            bail!("a selector needs exactly one of backlight, led, iio, power_supply or cpufreq");
+        }
+        if self.iio.is_some() && self.attribute.is_none() {
+            bail!("an iio selector needs an attribute");
+        }
+        Ok(())
+    }
+}
+
+// the device directory name, or attribute files that must all hold the
+// given values
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Match {
+    Device(String),
+    Attributes(BTreeMap<String, String>),
+}
+
+impl Match {
+    fn matches(&self, device: &str, dir: &Path) -> bool {
+        match self {
+            Match::Device(name) => device == name,
+            Match::Attributes(attributes) => attributes.iter().all(|(attribute, expected)| {
+                fs::read_to_string(dir.join(attribute))
+                    .map(|value| value.trim() == expected)
+                    .unwrap_or(false)
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Match {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Match::Device(name) => write!(f, "{}", name),
+            Match::Attributes(attributes) => {
+                let attributes = attributes
+                    .iter()
+                    .map(|(attribute, expected)| format!("{}={}", attribute, expected))
+                    .collect::<Vec<_>>();
+                write!(f, "{}", attributes.join(", "))
+            }
+        }
+    }
+}
+
+// resolves selectors against the devices under `root`. resolved paths are
+// the ones on the board, rebasing them is left to the caller
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    root: SysfsRoot,
+}
+
+impl Discovery {
+    pub fn new(root: &SysfsRoot) -> Self {
+        Discovery { root: root.clone() }
+    }
+
+    pub fn resolve(&self, selector: &Selector) -> Result<String> {
+        selector.check()?;
+        let (device, attribute) = if let Some(backlight) = &selector.backlight {
+            (self.find(BACKLIGHT, backlight)?, Some("brightness"))
+        } else if let Some(led) = &selector.led {
+            (self.find(LEDS, led)?, Some("brightness"))
+        } else if let Some(iio) = &selector.iio {
+            (self.find(IIO, iio)?, None)
+        } else if let Some(power_supply) = &selector.power_supply {
+            (self.find(POWER_SUPPLY, power_supply)?, Some("uevent"))
+        } else {
+            (
+                self.cpufreq_policy(selector.cpufreq.unwrap_or_default())?,
+                None,
+            )
+        };
+        Ok(match selector.attribute.as_deref().or(attribute) {
+            Some(attribute) => format!("{}/{}", device, attribute),
+            None => device,
+        })
+    }
+
+    // replaces every selector in an `interfaces` section with the path it
+    // resolves to. selectors matching no device are left empty and returned
+    // with the reason, keyed like the validation pass
+    pub fn resolve_interfaces(&self, interfaces: &mut Value) -> Result<Vec<(String, String)>> {
+        let mut unresolved = Vec::new();
+        for (key, entry) in selectors(interfaces) {
+            let selector: Selector = serde_yaml::from_value(entry.clone())
+                .with_context(|| format!("invalid selector {}", key))?;
+            selector
+                .check()
+                .with_context(|| format!("invalid selector {}", key))?;
+            *entry = match self.resolve(&selector) {
+                Ok(path) => Value::String(path),
+                Err(e) => {
+                    unresolved.push((key, format!("{:#}", e)));
+                    Value::String(String::new())
+                }
+            };
+        }
+        Ok(unresolved)
+    }
+
+    // first device of the class, by directory name, that matches
+    fn find(&self, class: &str, matcher: &Match) -> Result<String> {
+        let dir = self.root.rebase(class);
+        let mut devices = fs::read_dir(&dir)
+            .with_context(|| format!("unable to read {}", dir))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        devices.sort();
+        match devices
+            .iter()
+            .find(|device| matcher.matches(device, &Path::new(&dir).join(device)))
+        {
+            Some(device) => Ok(format!("{}/{}", class, device)),
+            None => bail!("no device in {} matches {}", class, matcher),
+        }
+    }
+
+    // the policy listing `cpu` in affected_cpus, or the per cpu directory of
+    // kernels without policies
+    fn cpufreq_policy(&self, cpu: u32) -> Result<String> {
+        let cpu_name = cpu.to_string();
+        let policies = self.root.rebase(CPUFREQ);
+        if let Ok(entries) = fs::read_dir(&policies) {
+            let mut names = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| name.starts_with("policy"))
+                .collect::<Vec<_>>();
+            names.sort();
+            for name in names {
+                let affected =
+                    fs::read_to_string(Path::new(&policies).join(&name).join("affected_cpus"))
+                        .unwrap_or_default();
+                if affected
+                    .split_whitespace()
+                    .any(|affected| affected == cpu_name)
+                {
+                    return Ok(format!("{}/{}", CPUFREQ, name));
+                }
+            }
+        }
+        let device = format!("{}/cpu{}/cpufreq", CPU, cpu);
+        if !Path::new(&self.root.rebase(&device)).is_dir() {
+            bail!("no cpufreq policy covers cpu{}", cpu);
+        }
+        Ok(device)
+    }
+}
+
+// blanks every selector, for reading the section before it is resolved
+pub fn strip_selectors(interfaces: &mut Value) {
+    for (_, entry) in selectors(interfaces) {
+        *entry = Value::String(String::new());
+    }
+}
+
+// entries of an `interfaces` section that are mappings rather than paths
+fn selectors(interfaces: &mut Value) -> Vec<(String, &mut Value)> {
+    let mut found = Vec::new();
+    if let Value::Mapping(sections) = interfaces {
+        for (section, entries) in sections.iter_mut() {
+            if let Value::Mapping(entries) = entries {
+                for (entry, value) in entries.iter_mut() {
+                    if value.is_mapping() {
+                        found.push((format!("interfaces.{}.{}", key(section), key(entry)), value));
+                    }
+                }
+            }
+        }
+    }
+    found
+}
+
+fn key(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        key => serde_yaml::to_string(key)
+            .map(|key| key.trim().to_string())
+            .unwrap_or_default(),
+    }
+}
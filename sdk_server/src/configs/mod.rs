@@ -1,2 +1,17 @@
 mod base_config;
-pub use base_config::BaseConfig;
\ No newline at end of file
+pub use base_config::{
+    Actions, Audit, Auth, Automation, BaseConfig, Conditions, Cors, Gateway, GrpcConfig,
+    Interfaces, LedName, Listener, LogFormat, Logging, Otlp, Prometheus, Rule, ServiceToggle,
+    Services, Settings, StaticToken, Tls, WifiCondition,
+};
+
+mod discovery;
+pub use discovery::{strip_selectors, Discovery, Match, Selector};
+
+mod profile;
+pub use profile::{
+    read_compatible, BoardProfile, BoardProfiles, AUTO_DETECT, DEVICE_TREE_COMPATIBLE,
+};
+
+mod validate;
+pub use validate::{ConfigIssue, ConfigReport, Severity};
@@ -0,0 +1,157 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::configs::Interfaces;
+
+// hardware maps built into the binary, one per supported board
+const BUNDLED: &[(&str, &str)] = &[
+    (
+        "mecha-compute-g1.yaml",
+        include_str!("../../profiles/mecha-compute-g1.yaml"),
+    ),
+    (
+        "mecha-compute-g2.yaml",
+        include_str!("../../profiles/mecha-compute-g2.yaml"),
+    ),
+];
+
+// `name` value that picks the profile from the device tree
+pub const AUTO_DETECT: &str = "auto";
+
+// nul separated list, most specific board first
+pub const DEVICE_TREE_COMPATIBLE: &str = "/proc/device-tree/compatible";
+
+// the interfaces of one board variant and the device tree compatible
+// strings it is detected by
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoardProfile {
+    pub name: String,
+    #[serde(default)]
+    pub compatible: Vec<String>,
+    #[serde(default)]
+    pub interfaces: Value,
+}
+
+impl BoardProfile {
+    fn parse(source: &str, yaml: &str) -> Result<Self> {
+        let mut profile: BoardProfile = serde_yaml::from_str(yaml)
+            .with_context(|| format!("invalid board profile {}", source))?;
+        if profile.interfaces.is_null() {
+            profile.interfaces = Value::Mapping(Mapping::new());
+        }
+        let unknown = Interfaces::unknown_keys(&profile.interfaces)
+            .with_context(|| format!("invalid interfaces in board profile {}", source))?;
+        if !unknown.is_empty() {
+            bail!(
+                "unknown key(s) in board profile {}: {}",
+                source,
+                unknown.join(", ")
+            );
+        }
+        Ok(profile)
+    }
+
+    // the profile's `interfaces` section with every key set in `overrides`
+    // replaced, selectors still unresolved
+    pub fn interfaces(&self, overrides: &Value) -> Value {
+        let mut interfaces = self.interfaces.clone();
+        merge(&mut interfaces, overrides);
+        interfaces
+    }
+}
+
+// the bundled profiles, with the ones from a user directory in front. a user
+// profile replaces the bundled one of the same name
+#[derive(Debug, Clone)]
+pub struct BoardProfiles(Vec<BoardProfile>);
+
+impl BoardProfiles {
+    pub fn bundled() -> Self {
+        BoardProfiles(
+            BUNDLED
+                .iter()
+                .map(|(source, yaml)| {
+                    BoardProfile::parse(source, yaml).expect("bundled board profiles are valid")
+                })
+                .collect(),
+        )
+    }
+
+    // every `*.yaml` file in `dir` on top of the bundled profiles
+    pub fn load(dir: Option<&Path>) -> Result<Self> {
+        let mut profiles = Self::bundled();
+        let dir = match dir {
+            Some(dir) => dir,
+            None => return Ok(profiles),
+        };
+        let mut paths = fs::read_dir(dir)
+            .with_context(|| format!("unable to read board profiles in {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                matches!(
+                    path.extension().and_then(|extension| extension.to_str()),
+                    Some("yaml" | "yml")
+                )
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        for path in paths.iter().rev() {
+            let yaml = fs::read_to_string(path)
+                .with_context(|| format!("unable to read board profile {}", path.display()))?;
+            let profile = BoardProfile::parse(&path.display().to_string(), &yaml)?;
+            profiles.0.retain(|bundled| bundled.name != profile.name);
+            profiles.0.insert(0, profile);
+        }
+        Ok(profiles)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BoardProfile> {
+        self.0.iter().find(|profile| profile.name == name)
+    }
+
+    // the profile matching the most specific compatible string
+    pub fn detect(&self, compatible: &[String]) -> Option<&BoardProfile> {
+        compatible.iter().find_map(|board| {
+            self.0
+                .iter()
+                .find(|profile| profile.compatible.contains(board))
+        })
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.0.iter().map(|profile| profile.name.as_str()).collect()
+    }
+}
+
+// the entries of a device tree `compatible` property
+pub fn read_compatible(path: &Path) -> Result<Vec<String>> {
+    let compatible =
+        fs::read(path).with_context(|| format!("unable to read {}", path.display()))?;
+    Ok(compatible
+        .split(|byte| *byte == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| String::from_utf8_lossy(entry).into_owned())
+        .collect())
+}
+
+// `overrides` wins over `base`, mappings are merged key by key
+pub(crate) fn merge(base: &mut Value, overrides: &Value) {
+    match (base, overrides) {
+        (_, Value::Null) => {}
+        (Value::Mapping(base), Value::Mapping(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
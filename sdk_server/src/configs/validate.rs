@@ -0,0 +1,661 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+use tonic::transport::Uri;
+use tracing_subscriber::EnvFilter;
+
+use crate::automation::{check, describe, Readings};
+use crate::configs::{Actions, BaseConfig, BoardProfiles, ServiceToggle, Services, AUTO_DETECT};
+use crate::gateway::cors_layer;
+use crate::listener::{resolve, Endpoint};
+use crate::settings::{cpu_frequency, USERSPACE_GOVERNOR};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    // the server refuses to start
+    Error,
+    // logged at startup, e.g. hardware that is not present yet
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", severity, self.key, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Access {
+    Exists,
+    Read,
+    Write,
+}
+
+// every problem found in a config: unknown keys, missing required interfaces,
+// bad ports and sysfs paths that do not exist or lack the needed access
+#[derive(Debug, Default)]
+pub struct ConfigReport {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl ConfigReport {
+    pub fn new(config: &BaseConfig) -> Self {
+        let mut report = ConfigReport::default();
+        report.check_keys(config);
+        report.check_board(config);
+        report.check_server(config);
+        report.check_auth(config);
+        report.check_logging(config);
+        report.check_audit(config);
+        report.check_settings(config);
+        report.check_automation(config);
+        report.check_interfaces(config);
+        report
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &ConfigIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &ConfigIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    fn push(&mut self, severity: Severity, key: &str, message: String) {
+        self.issues.push(ConfigIssue {
+            severity,
+            key: key.to_string(),
+            message,
+        });
+    }
+
+    fn check_keys(&mut self, config: &BaseConfig) {
+        for key in &config.unknown_keys {
+            self.push(Severity::Error, key, "unknown key".to_string());
+        }
+    }
+
+    // a name without a bundled or user profile leaves the interfaces to
+    // this file alone
+    fn check_board(&mut self, config: &BaseConfig) {
+        let profiles = match BoardProfiles::load(config.profiles_dir.as_deref().map(Path::new)) {
+            Ok(profiles) => profiles,
+            Err(e) => {
+                self.push(Severity::Error, "profiles_dir", format!("{:#}", e));
+                return;
+            }
+        };
+        if config.name.trim().is_empty() {
+            self.push(
+                Severity::Warning,
+                "name",
+                "no board profile name, capabilities report an empty board".to_string(),
+            );
+        } else if config.name != AUTO_DETECT && profiles.get(&config.name).is_none() {
+            self.push(
+                Severity::Warning,
+                "name",
+                format!(
+                    "no board profile named {}, known profiles: {}",
+                    config.name,
+                    profiles.names().join(", ")
+                ),
+            );
+        }
+    }
+
+    fn check_server(&mut self, config: &BaseConfig) {
+        let server = &config.server;
+        if server.listeners.is_empty() {
+            self.check_port("server.port", server.port);
+        }
+        for (index, listener) in server.listeners.iter().enumerate() {
+            let key = format!("server.listeners.{}", index);
+            match resolve(listener) {
+                Ok(Endpoint::Tcp(addr)) => self.check_port(&key, addr.port()),
+                Ok(Endpoint::Unix(_)) => {}
+                Err(e) => self.push(Severity::Error, &key, format!("{:#}", e)),
+            }
+        }
+        if let Some(gateway) = &server.gateway {
+            self.check_port("server.gateway.listen", gateway.listen.port());
+            if let Err(e) = cors_layer(&gateway.cors) {
+                self.push(
+                    Severity::Error,
+                    "server.gateway.cors.allowed_origins",
+                    format!("{:#}", e),
+                );
+            }
+        }
+        if let Some(prometheus) = &server.prometheus {
+            self.check_port("server.prometheus.listen", prometheus.listen.port());
+        }
+        if server.health_interval == 0 {
+            self.push(
+                Severity::Error,
+                "server.health_interval",
+                "must be at least 1 second".to_string(),
+            );
+        }
+        if server.device_workers == 0 {
+            self.push(
+                Severity::Error,
+                "server.device_workers",
+                "must be at least 1".to_string(),
+            );
+        }
+        if server.device_timeout == 0 {
+            self.push(
+                Severity::Error,
+                "server.device_timeout",
+                "must be at least 1 second".to_string(),
+            );
+        }
+        if server.event_interval == 0 {
+            self.push(
+                Severity::Error,
+                "server.event_interval",
+                "must be at least 1 second".to_string(),
+            );
+        }
+        if let Some(tls) = &server.tls {
+            self.check_file(Severity::Error, "server.tls.cert", &tls.cert);
+            self.check_file(Severity::Error, "server.tls.key", &tls.key);
+            match &tls.client_ca {
+                Some(client_ca) => {
+                    self.check_file(Severity::Error, "server.tls.client_ca", client_ca)
+                }
+                None if tls.require_client_cert => self.push(
+                    Severity::Error,
+                    "server.tls.client_ca",
+                    "required when require_client_cert is set".to_string(),
+                ),
+                None => {}
+            }
+        }
+        if config.simulation.enabled && config.simulation.root.is_empty() {
+            self.push(
+                Severity::Error,
+                "simulation.root",
+                "must be set when simulation is enabled".to_string(),
+            );
+        }
+    }
+
+    fn check_port(&mut self, key: &str, port: u16) {
+        if port == 0 {
+            self.push(
+                Severity::Error,
+                key,
+                "port must be between 1 and 65535".to_string(),
+            );
+        } else if port < 1024 {
+            self.push(
+                Severity::Warning,
+                key,
+                format!("{} is a privileged port", port),
+            );
+        }
+    }
+
+    fn check_logging(&mut self, config: &BaseConfig) {
+        let logging = &config.logging;
+        if let Err(e) = EnvFilter::try_new(&logging.level) {
+            self.push(Severity::Error, "logging.level", e.to_string());
+        }
+        if let Some(otlp) = &logging.otlp {
+            if let Err(e) = otlp.endpoint.parse::<Uri>() {
+                self.push(Severity::Error, "logging.otlp.endpoint", e.to_string());
+            }
+        }
+    }
+
+    fn check_settings(&mut self, config: &BaseConfig) {
+        let settings = match &config.settings {
+            Some(settings) => settings,
+            None => return,
+        };
+        let dir = match Path::new(&settings.path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if !dir.is_dir() {
+            self.push(
+                Severity::Error,
+                "settings.path",
+                format!("directory {} does not exist", dir.display()),
+            );
+        }
+    }
+
+    // rule names are unique and every rule has a condition and an action.
+    // using a disabled service is only a warning
+    fn check_automation(&mut self, config: &BaseConfig) {
+        let automation = match &config.automation {
+            Some(automation) => automation,
+            None => return,
+        };
+        if automation.interval == 0 {
+            self.push(
+                Severity::Error,
+                "automation.interval",
+                "must be at least 1 second".to_string(),
+            );
+        }
+        let services = &config.services;
+        // a cpu that can not be read is reported with its interface
+        let governors = fs::read_to_string(
+            Path::new(&config.interfaces.cpu.device).join("scaling_available_governors"),
+        )
+        .ok();
+        let mut names = HashSet::new();
+        for (index, rule) in automation.rules.iter().enumerate() {
+            let key = format!("automation.rules.{}", index);
+            if rule.name.trim().is_empty() {
+                self.push(Severity::Error, &key, "rule without a name".to_string());
+            } else if !names.insert(rule.name.as_str()) {
+                self.push(
+                    Severity::Error,
+                    &key,
+                    format!("duplicate rule name {}", rule.name),
+                );
+            }
+
+            let when = &rule.when;
+            if check(when, &Readings::default()).is_empty() {
+                self.push(
+                    Severity::Error,
+                    &format!("{}.when", key),
+                    "needs at least one condition".to_string(),
+                );
+            }
+            for (name, percent) in [
+                ("battery_below", when.battery_below),
+                ("battery_above", when.battery_above),
+            ] {
+                if percent.is_some_and(|percent| percent > 100) {
+                    self.push(
+                        Severity::Error,
+                        &format!("{}.when.{}", key, name),
+                        "must be a percentage from 0 to 100".to_string(),
+                    );
+                }
+            }
+            let reads = [
+                (
+                    when.battery_below.is_some()
+                        || when.battery_above.is_some()
+                        || when.battery_status.is_some(),
+                    "battery",
+                    &services.battery,
+                ),
+                (
+                    when.cpu_usage_above.is_some() || when.memory_used_above.is_some(),
+                    "metrics",
+                    &services.metrics,
+                ),
+                (
+                    when.motion.is_some() || when.idle_for.is_some(),
+                    "motion_sensor",
+                    &services.motion_sensor,
+                ),
+                (when.wifi.is_some(), "network", &services.network),
+            ];
+            self.check_rule_services(
+                &format!("{}.when", key),
+                &reads,
+                "the condition never holds",
+            );
+
+            let governors = governors.as_deref();
+            self.check_actions(&format!("{}.then", key), &rule.then, services, governors);
+            if let Some(otherwise) = &rule.otherwise {
+                self.check_actions(
+                    &format!("{}.otherwise", key),
+                    otherwise,
+                    services,
+                    governors,
+                );
+            }
+        }
+    }
+
+    fn check_actions(
+        &mut self,
+        key: &str,
+        actions: &Actions,
+        services: &Services,
+        governors: Option<&str>,
+    ) {
+        if describe(actions).is_empty() {
+            self.push(
+                Severity::Error,
+                key,
+                "needs at least one action".to_string(),
+            );
+        }
+        if let Some(khz) = actions.cpu_frequency {
+            if cpu_frequency(&khz.to_string()).is_none() {
+                self.push(
+                    Severity::Error,
+                    &format!("{}.cpu_frequency", key),
+                    format!(
+                        "unsupported frequency {}, expected 1200000, 1600000 or 1800000",
+                        khz
+                    ),
+                );
+            }
+        }
+        if let Some(governor) = &actions.governor {
+            let key = format!("{}.governor", key);
+            match governors {
+                Some(available) if !available.split_whitespace().any(|name| name == governor) => {
+                    self.push(
+                        Severity::Error,
+                        &key,
+                        format!(
+                            "unsupported governor {}, the cpu offers {}",
+                            governor,
+                            available.trim()
+                        ),
+                    )
+                }
+                None if governor.trim().is_empty() => {
+                    self.push(Severity::Error, &key, "empty governor".to_string())
+                }
+                _ => {}
+            }
+            if actions.cpu_frequency.is_some() && governor != USERSPACE_GOVERNOR {
+                self.push(
+                    Severity::Error,
+                    &key,
+                    format!("cpu_frequency needs the {} governor", USERSPACE_GOVERNOR),
+                );
+            }
+        }
+        let drives = [
+            (actions.brightness.is_some(), "display", &services.display),
+            (
+                !actions.set_leds.is_empty() || !actions.clear_leds.is_empty(),
+                "led",
+                &services.led,
+            ),
+            (
+                actions.governor.is_some() || actions.cpu_frequency.is_some(),
+                "cpu_governor",
+                &services.cpu_governor,
+            ),
+        ];
+        self.check_rule_services(key, &drives, "the action fails");
+    }
+
+    fn check_rule_services(
+        &mut self,
+        key: &str,
+        uses: &[(bool, &str, &ServiceToggle)],
+        consequence: &str,
+    ) {
+        for (used, service, toggle) in uses {
+            if *used && !toggle.enabled {
+                self.push(
+                    Severity::Warning,
+                    key,
+                    format!("services.{} is disabled, {}", service, consequence),
+                );
+            }
+        }
+    }
+
+    fn check_audit(&mut self, config: &BaseConfig) {
+        let audit = match &config.audit {
+            Some(audit) => audit,
+            None => return,
+        };
+        let dir = match Path::new(&audit.path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if !dir.is_dir() {
+            self.push(
+                Severity::Error,
+                "audit.path",
+                format!("directory {} does not exist", dir.display()),
+            );
+        }
+        if audit.max_size == 0 {
+            self.push(
+                Severity::Error,
+                "audit.max_size",
+                "must be at least 1 byte".to_string(),
+            );
+        }
+        if audit.keep == 0 {
+            self.push(
+                Severity::Error,
+                "audit.keep",
+                "at least 1 rotated file must be kept".to_string(),
+            );
+        }
+    }
+
+    fn check_auth(&mut self, config: &BaseConfig) {
+        let auth = match &config.auth {
+            Some(auth) => auth,
+            None => return,
+        };
+        let mut seen = HashSet::new();
+        for token in &auth.tokens {
+            if !auth.roles.contains_key(&token.role) {
+                self.push(
+                    Severity::Error,
+                    "auth.tokens",
+                    format!("token {} uses undefined role {}", token.name, token.role),
+                );
+            }
+            if !seen.insert(&token.token) {
+                self.push(
+                    Severity::Error,
+                    "auth.tokens",
+                    format!("token {} is not unique", token.name),
+                );
+            }
+        }
+        if auth.tokens.is_empty() && auth.jwt_secret.is_none() {
+            self.push(
+                Severity::Warning,
+                "auth",
+                "no tokens and no jwt_secret, every call will be rejected".to_string(),
+            );
+        }
+    }
+
+    fn check_interfaces(&mut self, config: &BaseConfig) {
+        let interfaces = &config.interfaces;
+        let services = &config.services;
+        // ignored while the service using them is disabled
+        let required = [
+            (
+                "interfaces.display.device",
+                &interfaces.display.device,
+                Access::Write,
+                services.display.enabled,
+            ),
+            (
+                "interfaces.led.red_led",
+                &interfaces.led.red_led,
+                Access::Write,
+                services.led.enabled,
+            ),
+            (
+                "interfaces.led.green_led",
+                &interfaces.led.green_led,
+                Access::Write,
+                services.led.enabled,
+            ),
+            (
+                "interfaces.led.blue_led",
+                &interfaces.led.blue_led,
+                Access::Write,
+                services.led.enabled,
+            ),
+            (
+                "interfaces.motion_sensor.x_axis",
+                &interfaces.motion_sensor.x_axis,
+                Access::Read,
+                services.motion_sensor.enabled,
+            ),
+            (
+                "interfaces.motion_sensor.y_axis",
+                &interfaces.motion_sensor.y_axis,
+                Access::Read,
+                services.motion_sensor.enabled,
+            ),
+            (
+                "interfaces.motion_sensor.z_axis",
+                &interfaces.motion_sensor.z_axis,
+                Access::Read,
+                services.motion_sensor.enabled,
+            ),
+            (
+                "interfaces.battery.device",
+                &interfaces.battery.device,
+                Access::Read,
+                services.battery.enabled,
+            ),
+            (
+                "interfaces.battery.current",
+                &interfaces.battery.current,
+                Access::Read,
+                services.battery.enabled,
+            ),
+            (
+                "interfaces.cpu.device",
+                &interfaces.cpu.device,
+                Access::Exists,
+                services.cpu_governor.enabled,
+            ),
+        ];
+        // a selector without a device is like a path that does not exist
+        for (key, reason) in &config.unresolved {
+            self.push(Severity::Warning, key, reason.clone());
+        }
+        let unresolved = |key: &str| config.unresolved.iter().any(|(other, _)| other == key);
+        for (key, path, access, enabled) in required {
+            if !enabled || unresolved(key) {
+                continue;
+            }
+            if !path.is_empty() {
+                self.check_path(key, path, access);
+            } else {
+                self.push(
+                    Severity::Error,
+                    key,
+                    "required interface is missing".to_string(),
+                );
+            }
+        }
+
+        // optional, only checked when configured
+        let optional = [
+            (
+                "interfaces.battery.capacity",
+                &interfaces.battery.capacity,
+                Access::Read,
+            ),
+            (
+                "interfaces.battery.voltage",
+                &interfaces.battery.voltage,
+                Access::Read,
+            ),
+            (
+                "interfaces.adc.channel_1",
+                &interfaces.adc.channel_1,
+                Access::Read,
+            ),
+            (
+                "interfaces.adc.channel_2",
+                &interfaces.adc.channel_2,
+                Access::Read,
+            ),
+            (
+                "interfaces.adc.sampling_frequency",
+                &interfaces.adc.sampling_frequency,
+                Access::Write,
+            ),
+            (
+                "interfaces.camera.device",
+                &interfaces.camera.device,
+                Access::Read,
+            ),
+            (
+                "interfaces.audio.audio_file",
+                &interfaces.audio.audio_file,
+                Access::Read,
+            ),
+        ];
+        for (key, path, access) in optional {
+            if !path.is_empty() {
+                self.check_path(key, path, access);
+            }
+        }
+    }
+
+    // hardware may be absent or probed later, path problems are warnings
+    fn check_path(&mut self, key: &str, path: &str, access: Access) {
+        if !Path::new(path).exists() {
+            self.push(Severity::Warning, key, format!("{} does not exist", path));
+            return;
+        }
+        let result = match access {
+            Access::Exists => Ok(()),
+            Access::Read => File::open(path).map(|_| ()),
+            Access::Write => OpenOptions::new().write(true).open(path).map(|_| ()),
+        };
+        if let Err(e) = result {
+            let mode = match access {
+                Access::Write => "writable",
+                _ => "readable",
+            };
+            self.push(
+                Severity::Warning,
+                key,
+                format!("{} is not {}: {}", path, mode, e),
+            );
+        }
+    }
+
+    fn check_file(&mut self, severity: Severity, key: &str, path: &str) {
+        if let Err(e) = File::open(path) {
+            self.push(severity, key, format!("unable to read {}: {}", path, e));
+        }
+    }
+}
+
+impl fmt::Display for ConfigReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "  {}", issue)?;
+        }
+        Ok(())
+    }
+}
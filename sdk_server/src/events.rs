@@ -0,0 +1,228 @@
+use anyhow::Result;
+use mecha_network_manager::wifi::{Broadcast, WifiModule, WPA_SUPPLICANT_SOCKET};
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use tokio::time::MissedTickBehavior;
+use tracing::{trace, warn};
+
+use crate::services::event_service::{
+    BatteryStatusChanged, BluetoothPowerChanged, BrightnessChanged, Event, MotionDetected, Payload,
+    WifiState, WifiStateChanged,
+};
+use crate::services::{
+    BluetoothControl, Device, DisplayControl, MotionSensorControl, PowerSupplyInfo,
+};
+
+// topics a subscriber may filter on, one per payload
+pub const TOPICS: [&str; 5] = ["battery", "wifi", "motion", "display", "bluetooth"];
+
+// events kept for subscribers that fall behind before they miss some
+pub const DEFAULT_EVENT_CAPACITY: usize = 64;
+
+// fan out of device events, every subsystem publishes and every
+// EventService.Subscribe stream receives
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        EventBus { sender }
+    }
+
+    pub fn publish(&self, payload: Payload) {
+        let event = Event {
+            topic: topic(&payload).to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as u64)
+                .unwrap_or_default(),
+            payload: Some(payload),
+        };
+        trace!(task = "publish", "{:?}", event);
+        // no subscriber is not an error
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
+
+fn topic(payload: &Payload) -> &'static str {
+    match payload {
+        Payload::Battery(_) => "battery",
+        Payload::Wifi(_) => "wifi",
+        Payload::Motion(_) => "motion",
+        Payload::Brightness(_) => "display",
+        Payload::Bluetooth(_) => "bluetooth",
+    }
+}
+
+// read the state every `interval` while anyone is subscribed and publish what
+// `changed` makes of the previous and the current read. failed reads are
+// skipped, the first read after a quiet period only sets the baseline
+async fn poll<T, F, Fut>(
+    task: &'static str,
+    bus: EventBus,
+    interval: Duration,
+    mut read: F,
+    changed: fn(&T, &T) -> Option<Payload>,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    trace!(task = task, "init");
+    let mut last: Option<T> = None;
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        if !bus.has_subscribers() {
+            last = None;
+            continue;
+        }
+        let current = match read().await {
+            Ok(current) => current,
+            Err(e) => {
+                trace!(task = task, "unable to read state: {:#}", e);
+                continue;
+            }
+        };
+        if let Some(payload) = last.as_ref().and_then(|last| changed(last, &current)) {
+            bus.publish(payload);
+        }
+        last = Some(current);
+    }
+}
+
+// charging status or capacity changes
+pub async fn watch_battery<P>(battery: Device<P>, bus: EventBus, interval: Duration)
+where
+    P: PowerSupplyInfo + Send + Sync + 'static,
+{
+    let read = || {
+        battery.run("watch_battery", |battery| {
+            let info = battery.info()?;
+            Ok((info.status, info.capacity))
+        })
+    };
+    poll("watch_battery", bus, interval, read, |last, current| {
+        (last != current).then(|| {
+            Payload::Battery(BatteryStatusChanged {
+                status: current.0.clone(),
+                capacity: current.1.into(),
+            })
+        })
+    })
+    .await
+}
+
+// backlight changes, through the display service or anything else
+pub async fn watch_brightness<D>(display: Device<D>, bus: EventBus, interval: Duration)
+where
+    D: DisplayControl + Send + Sync + 'static,
+{
+    let read = || {
+        display.run("watch_brightness", |display| {
+            display.get_display_brightness()
+        })
+    };
+    poll("watch_brightness", bus, interval, read, |last, current| {
+        (last != current).then(|| {
+            Payload::Brightness(BrightnessChanged {
+                brightness: (*current).into(),
+            })
+        })
+    })
+    .await
+}
+
+// published once when the sensor starts reporting motion
+pub async fn watch_motion<M>(sensor: Device<M>, bus: EventBus, interval: Duration)
+where
+    M: MotionSensorControl + Send + Sync + 'static,
+{
+    let read = || {
+        sensor.run("watch_motion", |sensor| {
+            if !sensor.detect_motion_sensor_event()? {
+                return Ok(None);
+            }
+            sensor.read_motion_sensor_value().map(Some)
+        })
+    };
+    poll(
+        "watch_motion",
+        bus,
+        interval,
+        read,
+        |last, current| match (last, current) {
+            (None, Some((x_value, y_value, z_value))) => Some(Payload::Motion(MotionDetected {
+                x_value: *x_value,
+                y_value: *y_value,
+                z_value: *z_value,
+            })),
+            _ => None,
+        },
+    )
+    .await
+}
+
+// the adapter being powered on or off
+pub async fn watch_bluetooth<B>(bluetooth: B, bus: EventBus, interval: Duration)
+where
+    B: BluetoothControl,
+{
+    let read = || bluetooth.bluetooth_status();
+    poll("watch_bluetooth", bus, interval, read, |last, current| {
+        (last != current).then_some(Payload::Bluetooth(BluetoothPowerChanged {
+            enabled: *current,
+        }))
+    })
+    .await
+}
+
+// one connection to wpa_supplicant held for the lifetime of the server, its
+// broadcasts feed forward_wifi. reconnects `retry` after losing it
+pub async fn listen_wifi(retry: Duration) {
+    trace!(task = "listen_wifi", "init");
+    loop {
+        if let Err(e) = WifiModule::listen_broadcasts(WPA_SUPPLICANT_SOCKET).await {
+            warn!(task = "listen_wifi", "{:#}", e);
+        }
+        tokio::time::sleep(retry).await;
+    }
+}
+
+// connection changes wpa_supplicant reports to listen_wifi, ends when the
+// broadcast channel closes
+pub async fn forward_wifi(mut broadcasts: broadcast::Receiver<Broadcast>, bus: EventBus) {
+    trace!(task = "forward_wifi", "init");
+    loop {
+        let state = match broadcasts.recv().await {
+            Ok(Broadcast::Connected) => WifiState::Connected,
+            Ok(Broadcast::Disconnected) => WifiState::Disconnected,
+            Ok(Broadcast::NetworkNotFound) => WifiState::NetworkNotFound,
+            Ok(Broadcast::WrongPsk) => WifiState::WrongPsk,
+            Ok(Broadcast::Ready | Broadcast::Unknown(_)) => continue,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        bus.publish(Payload::Wifi(WifiStateChanged {
+            state: state.into(),
+        }));
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::{Context, Result};
+use std::time::Duration;
+use tonic::codegen::http::header::{HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use tonic::codegen::http::Method;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::configs::Cors;
+
+// request headers sent by grpc-web clients next to the bearer token
+const ALLOW_HEADERS: [&str; 3] = ["x-grpc-web", "x-user-agent", "grpc-timeout"];
+// grpc-web clients read the status from these response headers
+const EXPOSE_HEADERS: [&str; 3] = ["grpc-status", "grpc-message", "grpc-status-details-bin"];
+
+pub fn cors_layer(cors: &Cors) -> Result<CorsLayer> {
+    let mut allow_headers = vec![AUTHORIZATION, CONTENT_TYPE];
+    allow_headers.extend(ALLOW_HEADERS.map(HeaderName::from_static));
+    let layer = CorsLayer::new()
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::PATCH,
+        ])
+        .allow_headers(allow_headers)
+        .expose_headers(EXPOSE_HEADERS.map(HeaderName::from_static))
+        .max_age(Duration::from_secs(cors.max_age));
+
+    if cors.allowed_origins.iter().any(|origin| origin == "*") {
+        return Ok(layer.allow_origin(AllowOrigin::any()));
+    }
+    if cors.allowed_origins.is_empty() {
+        return Ok(layer);
+    }
+    let origins = cors
+        .allowed_origins
+        .iter()
+        .map(|origin| {
+            HeaderValue::from_str(origin).with_context(|| format!("invalid origin {}", origin))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(layer.allow_origin(origins))
+}
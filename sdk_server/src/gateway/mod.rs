@@ -0,0 +1,5 @@
+mod cors;
+pub use cors::cors_layer;
+
+mod rest;
+pub use rest::{RestLayer, RestRoute, RestRoutes, RestService};
@@ -0,0 +1,502 @@
+// every step of a transcoded request fails with the grpc status returned to the client
+#![allow(clippy::result_large_err)]
+
+use anyhow::{bail, Context as _, Result};
+use mecha_errors::{error_info, ERROR_INFO_TYPE_URL};
+use percent_encoding::percent_decode_str;
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, Kind, MethodDescriptor, ReflectMessage, SerializeOptions,
+    Value,
+};
+use std::error::Error as StdError;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::http::{header, HeaderValue, Method, Request, Response, StatusCode, Version};
+use tonic::codegen::{Body as _, BoxFuture, Service};
+use tonic::transport::Body;
+use tonic::{Code, Status};
+use tower::Layer;
+use tracing::{debug, trace};
+
+use crate::middleware::take_ready;
+
+// the method option holding the rest mapping of an rpc
+const HTTP_RULE: &str = "google.api.http";
+// largest json request body accepted
+const MAX_BODY_SIZE: usize = 64 * 1024;
+// request headers copied onto the grpc request, the rpc span continues the
+// caller's trace
+const FORWARDED_HEADERS: &[&str] = &["authorization", "traceparent", "tracestate"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(String),
+}
+
+// one `google.api.http` rule, e.g. `post: "/v1/led/{color}"`
+#[derive(Debug, Clone)]
+pub struct RestRoute {
+    pub method: Method,
+    pub path: String,
+    segments: Vec<Segment>,
+    // `body: "*"`, the json body fills the request message
+    body: bool,
+    rpc: MethodDescriptor,
+}
+
+impl RestRoute {
+    fn new(rule: &DynamicMessage, rpc: MethodDescriptor) -> Result<Self> {
+        let verbs = [
+            ("get", Method::GET),
+            ("put", Method::PUT),
+            ("post", Method::POST),
+            ("delete", Method::DELETE),
+            ("patch", Method::PATCH),
+        ];
+        let (method, path) = verbs
+            .into_iter()
+            .find_map(|(name, method)| {
+                if !rule.has_field_by_name(name) {
+                    return None;
+                }
+                let path = rule.get_field_by_name(name)?.as_str()?.to_string();
+                Some((method, path))
+            })
+            .context("only get, put, post, delete and patch are supported")?;
+
+        let body = match rule.get_field_by_name("body").as_deref().and_then(Value::as_str) {
+            None | Some("") => false,
+            Some("*") => true,
+            Some(body) => bail!("body {:?} is not supported, use \"*\"", body),
+        };
+
+        let input = rpc.input();
+        let segments = path
+            .trim_start_matches('/')
+            .split('/')
+            .map(|segment| {
+                match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                    Some(field) if input.get_field_by_name(field).is_some() => {
+                        Ok(Segment::Field(field.to_string()))
+                    }
+                    Some(field) => bail!("{} has no field {}", input.full_name(), field),
+                    None => Ok(Segment::Literal(segment.to_string())),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(RestRoute {
+            method,
+            path,
+            segments,
+            body,
+            rpc,
+        })
+    }
+
+    // the grpc path the route is forwarded to, e.g. `/ledmanager.LedCtrlService/SetLed`
+    pub fn grpc_path(&self) -> String {
+        format!(
+            "/{}/{}",
+            self.rpc.parent_service().full_name(),
+            self.rpc.name()
+        )
+    }
+
+    // path parameters when `path` matches the template
+    fn matches(&self, path: &str) -> Option<Vec<(String, String)>> {
+        let parts = path.trim_start_matches('/').split('/').collect::<Vec<_>>();
+        if parts.len() != self.segments.len() {
+            return None;
+        }
+        let mut params = Vec::new();
+        for (segment, part) in self.segments.iter().zip(parts) {
+            match segment {
+                Segment::Literal(literal) if literal == part => {}
+                Segment::Field(field) if !part.is_empty() => {
+                    params.push((field.clone(), part.to_string()))
+                }
+                _ => return None,
+            }
+        }
+        Some(params)
+    }
+}
+
+// rest routes of every rpc carrying a `google.api.http` option
+#[derive(Debug, Clone, Default)]
+pub struct RestRoutes {
+    routes: Vec<RestRoute>,
+}
+
+impl RestRoutes {
+    pub fn new(file_descriptor_set: &[u8]) -> Result<Self> {
+        trace!(task = "rest_routes", "init");
+        let pool = DescriptorPool::decode(file_descriptor_set)
+            .context("unable to decode the file descriptor set")?;
+        let rule = match pool.get_extension_by_name(HTTP_RULE) {
+            Some(rule) => rule,
+            None => return Ok(RestRoutes::default()),
+        };
+
+        let mut routes = Vec::new();
+        for service in pool.services() {
+            for rpc in service.methods() {
+                let options = rpc.options();
+                if !options.has_extension(&rule) {
+                    continue;
+                }
+                let value = options.get_extension(&rule);
+                if let Some(http) = value.as_message() {
+                    let route = RestRoute::new(http, rpc.clone())
+                        .with_context(|| format!("invalid http rule on {}", rpc.full_name()))?;
+                    routes.push(route);
+                }
+            }
+        }
+        Ok(RestRoutes { routes })
+    }
+
+    pub fn routes(&self) -> &[RestRoute] {
+        &self.routes
+    }
+
+    fn find(&self, method: &Method, path: &str) -> Option<(&RestRoute, Vec<(String, String)>)> {
+        self.routes
+            .iter()
+            .filter(|route| route.method == method)
+            .find_map(|route| route.matches(path).map(|params| (route, params)))
+    }
+}
+
+// tower layer answering rest requests by calling the grpc service the route
+// maps to, grpc and grpc-web requests pass through untouched
+#[derive(Debug, Clone)]
+pub struct RestLayer {
+    routes: Arc<RestRoutes>,
+}
+
+impl RestLayer {
+    pub fn new(routes: RestRoutes) -> Self {
+        RestLayer {
+            routes: Arc::new(routes),
+        }
+    }
+}
+
+impl<S> Layer<S> for RestLayer {
+    type Service = RestService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RestService {
+            inner,
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RestService<S> {
+    inner: S,
+    routes: Arc<RestRoutes>,
+}
+
+impl<S> Service<Request<Body>> for RestService<S>
+where
+    S: Service<Request<Body>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let grpc = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/grpc"));
+        if grpc {
+            return Box::pin(self.inner.call(request));
+        }
+
+        let mut inner = take_ready(&mut self.inner);
+        let routes = self.routes.clone();
+        Box::pin(async move {
+            let response = match transcode(&routes, &mut inner, request).await {
+                Ok(json) => json_response(StatusCode::OK, json),
+                Err(status) => {
+                    debug!(task = "rest_gateway", "{}", status.message());
+                    error_response(&status)
+                }
+            };
+            Ok(response)
+        })
+    }
+}
+
+// json request -> grpc frame -> inner service -> grpc frame -> json response
+async fn transcode<S>(
+    routes: &RestRoutes,
+    inner: &mut S,
+    request: Request<Body>,
+) -> Result<Vec<u8>, Status>
+where
+    S: Service<Request<Body>, Response = Response<BoxBody>>,
+    S::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+    let (parts, body) = request.into_parts();
+    let (route, params) = routes
+        .find(&parts.method, parts.uri.path())
+        .ok_or_else(|| {
+            Status::not_found(format!("no route for {} {}", parts.method, parts.uri.path()))
+        })?;
+
+    let input = route.rpc.input();
+    let mut message = DynamicMessage::new(input.clone());
+    if route.body {
+        let json = read_body(body).await?;
+        if !json.is_empty() {
+            let mut deserializer = serde_json::Deserializer::from_slice(&json);
+            message = DynamicMessage::deserialize(input, &mut deserializer)
+                .and_then(|message| deserializer.end().map(|_| message))
+                .map_err(|e| Status::invalid_argument(format!("invalid json body: {}", e)))?;
+        }
+    }
+    // without a body the fields not bound by the path come from the query
+    // string, e.g. `GET /v1/settings?prefix=led.`
+    if !route.body {
+        for (name, value) in query_params(parts.uri.query().unwrap_or_default())? {
+            set_param(&mut message, &name, &value)?;
+        }
+    }
+    for (name, value) in params {
+        set_param(&mut message, &name, &value)?;
+    }
+
+    // length prefixed, uncompressed grpc message
+    let payload = message.encode_to_vec();
+    let mut frame = Vec::with_capacity(payload.len() + 5);
+    frame.push(0);
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&payload);
+
+    let mut grpc = Request::new(Body::from(frame));
+    *grpc.method_mut() = Method::POST;
+    *grpc.uri_mut() = route
+        .grpc_path()
+        .parse()
+        .map_err(|_| Status::internal("invalid grpc path"))?;
+    *grpc.version_mut() = Version::HTTP_2;
+    // connection info and tls peer certificates stay with the request
+    *grpc.extensions_mut() = parts.extensions;
+    let headers = grpc.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/grpc"),
+    );
+    headers.insert(header::TE, HeaderValue::from_static("trailers"));
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = parts.headers.get(*name) {
+            headers.insert(*name, value.clone());
+        }
+    }
+
+    let response = inner
+        .call(grpc)
+        .await
+        .map_err(|e| Status::internal(e.into().to_string()))?;
+    let (parts, mut body) = response.into_parts();
+    check_status(&parts.headers)?;
+    if parts.status != StatusCode::OK {
+        return Err(Status::unknown(format!(
+            "grpc service replied with http status {}",
+            parts.status
+        )));
+    }
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        data.extend_from_slice(&chunk?);
+    }
+    if let Some(trailers) = body.trailers().await? {
+        check_status(&trailers)?;
+    }
+
+    // exactly one uncompressed message
+    if data.len() < 5 {
+        return Err(Status::internal("empty grpc response"));
+    }
+    if data[0] != 0 {
+        return Err(Status::internal("compressed grpc response"));
+    }
+    let length = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+    let payload = &data[5..];
+    if payload.len() != length {
+        return Err(Status::internal(format!(
+            "grpc response has {} message bytes, its header announced {}",
+            payload.len(),
+            length
+        )));
+    }
+    let reply = DynamicMessage::decode(route.rpc.output(), payload)
+        .map_err(|e| Status::internal(format!("invalid grpc response: {}", e)))?;
+
+    // every field is returned, including zero values
+    let options = SerializeOptions::new()
+        .skip_default_fields(false)
+        .use_proto_field_name(true);
+    let mut json = Vec::new();
+    reply
+        .serialize_with_options(&mut serde_json::Serializer::new(&mut json), &options)
+        .map_err(|e| Status::internal(e.to_string()))?;
+    Ok(json)
+}
+
+fn check_status(headers: &tonic::codegen::http::HeaderMap) -> Result<(), Status> {
+    match Status::from_header_map(headers) {
+        Some(status) if status.code() != Code::Ok => Err(status),
+        _ => Ok(()),
+    }
+}
+
+async fn read_body(mut body: Body) -> Result<Vec<u8>, Status> {
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| Status::invalid_argument(e.to_string()))?;
+        if data.len() + chunk.len() > MAX_BODY_SIZE {
+            return Err(Status::invalid_argument(format!(
+                "request body is larger than {} bytes",
+                MAX_BODY_SIZE
+            )));
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+// `name=value` pairs of a url encoded query string
+fn query_params(query: &str) -> Result<Vec<(String, String)>, Status> {
+    let decode = |part: &str| {
+        percent_decode_str(&part.replace('+', " "))
+            .decode_utf8()
+            .map(|decoded| decoded.into_owned())
+            .map_err(|_| Status::invalid_argument(format!("invalid query parameter {}", part)))
+    };
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((decode(name)?, decode(value)?))
+        })
+        .collect()
+}
+
+// path and query parameters are plain strings, converted to the field type;
+// enum values match their names case-insensitively, `/v1/led/red` is `Red`.
+// a repeated field takes one value per parameter, `?keys=a&keys=b`
+fn set_param(message: &mut DynamicMessage, name: &str, value: &str) -> Result<(), Status> {
+    let field = message
+        .descriptor()
+        .get_field_by_name(name)
+        .ok_or_else(|| Status::invalid_argument(format!("unknown field {}", name)))?;
+    let converted = match field.kind() {
+        Kind::String => Value::String(value.to_string()),
+        Kind::Bool => Value::Bool(parse(name, value)?),
+        Kind::Int32 | Kind::Sint32 | Kind::Sfixed32 => Value::I32(parse(name, value)?),
+        Kind::Int64 | Kind::Sint64 | Kind::Sfixed64 => Value::I64(parse(name, value)?),
+        Kind::Uint32 | Kind::Fixed32 => Value::U32(parse(name, value)?),
+        Kind::Uint64 | Kind::Fixed64 => Value::U64(parse(name, value)?),
+        Kind::Float => Value::F32(parse(name, value)?),
+        Kind::Double => Value::F64(parse(name, value)?),
+        Kind::Enum(descriptor) => {
+            let number = descriptor
+                .values()
+                .find(|v| v.name().eq_ignore_ascii_case(value))
+                .map(|v| v.number());
+            match number {
+                Some(number) => Value::EnumNumber(number),
+                None => Value::EnumNumber(parse(name, value)?),
+            }
+        }
+        _ => {
+            return Err(Status::invalid_argument(format!(
+                "{} can not be set from the url",
+                name
+            )))
+        }
+    };
+    if field.is_list() {
+        if let Some(list) = message.get_field_mut(&field).as_list_mut() {
+            list.push(converted);
+        }
+    } else {
+        message.set_field(&field, converted);
+    }
+    Ok(())
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, Status> {
+    value
+        .parse()
+        .map_err(|_| Status::invalid_argument(format!("invalid value {} for {}", value, name)))
+}
+
+fn json_response(status: StatusCode, json: Vec<u8>) -> Response<BoxBody> {
+    let body = Body::from(json)
+        .map_err(|e| Status::internal(e.to_string()))
+        .boxed_unsync();
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn error_response(status: &Status) -> Response<BoxBody> {
+    let mut json = serde_json::json!({
+        "code": status.code() as i32,
+        "message": status.message(),
+    });
+    // the same shape google api gateways use for google.rpc.Status details
+    if let Some(info) = error_info(status) {
+        json["details"] = serde_json::json!([{
+            "@type": ERROR_INFO_TYPE_URL,
+            "reason": info.reason,
+            "domain": info.domain,
+            "metadata": info.metadata,
+        }]);
+    }
+    json_response(http_status(status.code()), json.to_string().into_bytes())
+}
+
+// https://github.com/googleapis/googleapis/blob/master/google/rpc/code.proto
+fn http_status(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Cancelled => StatusCode::from_u16(499).unwrap_or(StatusCode::BAD_REQUEST),
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
@@ -0,0 +1,178 @@
+use mecha_network_manager::wifi::WPA_SUPPLICANT_SOCKET;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use tonic::server::NamedService;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+use tracing::{info, trace, warn};
+
+use crate::configs::{Interfaces, ServiceToggle};
+use crate::reload::Swappable;
+use crate::services::{
+    AuditManager, AuditServiceServer, AutomationManager, AutomationServiceServer, Bluetooth,
+    BluetoothControl, BluetoothController, BluetoothServiceServer, CapabilitiesManager,
+    CpuCtrlService, CpuGovernorCtrlServiceServer, DeviceCapabilitiesServiceServer, DeviceInfoCtrl,
+    DeviceInfoServiceServer, DeviceMetricsService, DisplayCtrlManager, DisplayCtrlServiceServer,
+    EventManager, EventServiceServer, LedCtrlManager, LedCtrlServiceServer, MetricsServiceServer,
+    MotionSensorManager, MotionSensorServiceServer, NetworkManager, NetworkManagerServiceServer,
+    PowerSupply, PowerSupplyServiceServer, SettingsManager, SettingsServiceServer, TrustZoneCtrl,
+    TrustZoneCtrlServiceManager, TrustZoneCtrlServiceServer,
+};
+
+#[derive(Debug, Clone)]
+pub enum Probe {
+    // every path has to exist
+    Paths(Vec<String>),
+    // a bluez session with a default adapter
+    Bluetooth,
+    // no backing hardware, always reachable
+    None,
+}
+
+#[derive(Debug, Clone)]
+pub struct HardwareProbe {
+    pub service: &'static str,
+    pub probe: Probe,
+}
+
+impl HardwareProbe {
+    fn new<S: NamedService>(probe: Probe) -> Self {
+        HardwareProbe {
+            service: S::NAME,
+            probe,
+        }
+    }
+
+    pub async fn is_reachable(&self) -> bool {
+        match &self.probe {
+            Probe::Paths(paths) => paths.iter().all(|path| Path::new(path).exists()),
+            Probe::Bluetooth => match BluetoothController::new().await {
+                Ok(controller) => controller.bluetooth_status().await.is_ok(),
+                Err(_) => false,
+            },
+            Probe::None => true,
+        }
+    }
+}
+
+// the hardware each grpc service depends on
+pub fn hardware_probes(interfaces: &Interfaces) -> Vec<HardwareProbe> {
+    vec![
+        HardwareProbe::new::<DisplayCtrlServiceServer<DisplayCtrlManager>>(Probe::Paths(vec![
+            interfaces.display.device.clone(),
+        ])),
+        HardwareProbe::new::<LedCtrlServiceServer<LedCtrlManager>>(Probe::Paths(vec![
+            interfaces.led.red_led.clone(),
+            interfaces.led.green_led.clone(),
+            interfaces.led.blue_led.clone(),
+        ])),
+        HardwareProbe::new::<MotionSensorServiceServer<MotionSensorManager>>(Probe::Paths(vec![
+            interfaces.motion_sensor.x_axis.clone(),
+            interfaces.motion_sensor.y_axis.clone(),
+            interfaces.motion_sensor.z_axis.clone(),
+        ])),
+        HardwareProbe::new::<PowerSupplyServiceServer<PowerSupply>>(Probe::Paths(vec![interfaces
+            .battery
+            .device
+            .clone()])),
+        HardwareProbe::new::<CpuGovernorCtrlServiceServer<CpuCtrlService>>(Probe::Paths(vec![
+            interfaces.cpu.device.clone(),
+        ])),
+        HardwareProbe::new::<NetworkManagerServiceServer<NetworkManager>>(Probe::Paths(vec![
+            WPA_SUPPLICANT_SOCKET.to_string(),
+        ])),
+        HardwareProbe::new::<TrustZoneCtrlServiceServer<TrustZoneCtrlServiceManager>>(
+            Probe::Paths(vec![TrustZoneCtrl::new().path]),
+        ),
+        HardwareProbe::new::<BluetoothServiceServer<Bluetooth>>(Probe::Bluetooth),
+        HardwareProbe::new::<DeviceInfoServiceServer<DeviceInfoCtrl>>(Probe::None),
+        HardwareProbe::new::<MetricsServiceServer<DeviceMetricsService>>(Probe::None),
+        HardwareProbe::new::<EventServiceServer<EventManager>>(Probe::None),
+        HardwareProbe::new::<AuditServiceServer<AuditManager>>(Probe::None),
+        HardwareProbe::new::<DeviceCapabilitiesServiceServer<CapabilitiesManager>>(Probe::None),
+        HardwareProbe::new::<SettingsServiceServer<SettingsManager>>(Probe::None),
+        HardwareProbe::new::<AutomationServiceServer<AutomationManager>>(Probe::None),
+    ]
+}
+
+// decides which services get registered at startup, a service is left out
+// when it is disabled in the config or when `probe` is set and its hardware
+// is unreachable. only the probes of admitted services are kept for health
+pub struct ServiceGate {
+    probes: Vec<HardwareProbe>,
+    admitted: Vec<HardwareProbe>,
+}
+
+impl ServiceGate {
+    pub fn new(probes: Vec<HardwareProbe>) -> Self {
+        ServiceGate {
+            probes,
+            admitted: Vec::new(),
+        }
+    }
+
+    pub async fn admit<S: NamedService>(
+        &mut self,
+        toggle: &ServiceToggle,
+        service: S,
+    ) -> Option<S> {
+        if !toggle.enabled {
+            info!(task = "service_gate", "{} is disabled", S::NAME);
+            return None;
+        }
+        let probe = self.probes.iter().find(|probe| probe.service == S::NAME);
+        if let Some(probe) = probe {
+            if toggle.probe && !probe.is_reachable().await {
+                warn!(
+                    task = "service_gate",
+                    "{} is disabled, hardware unreachable",
+                    S::NAME
+                );
+                return None;
+            }
+            self.admitted.push(probe.clone());
+        }
+        info!(task = "service_gate", "{} is enabled", S::NAME);
+        Some(service)
+    }
+
+    pub fn into_probes(self) -> Vec<HardwareProbe> {
+        self.admitted
+    }
+}
+
+// re-run every probe each `interval` and publish the result through the
+// grpc health service, only changes are logged. the probes are swapped when
+// a config reload moves a device path
+pub async fn report_health(
+    mut reporter: HealthReporter,
+    probes: Swappable<Vec<HardwareProbe>>,
+    interval: Duration,
+) {
+    trace!(task = "report_health", "init");
+    let mut last: HashMap<&'static str, bool> = HashMap::new();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let probes = probes.get().clone();
+        for probe in probes {
+            let reachable = probe.is_reachable().await;
+            if last.insert(probe.service, reachable) == Some(reachable) {
+                continue;
+            }
+
+            let status = if reachable {
+                info!(task = "report_health", "{} is serving", probe.service);
+                ServingStatus::Serving
+            } else {
+                warn!(
+                    task = "report_health",
+                    "{} is not serving, hardware unreachable", probe.service
+                );
+                ServingStatus::NotServing
+            };
+            reporter.set_service_status(probe.service, status).await;
+        }
+    }
+}
@@ -0,0 +1,16 @@
+pub mod audit;
+pub mod automation;
+pub mod cli;
+pub mod configs;
+pub mod events;
+pub mod gateway;
+pub mod health;
+pub mod listener;
+pub mod middleware;
+pub mod reload;
+pub mod services;
+pub mod settings;
+pub mod shutdown;
+pub mod systemd;
+pub mod telemetry;
+pub mod tls;
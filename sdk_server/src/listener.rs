@@ -0,0 +1,155 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::fmt;
+use std::fs;
+use std::net::SocketAddr;
+use std::os::unix::fs::{chown, DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use tokio::net::UnixListener;
+use tracing::{info, trace};
+
+use crate::configs::Listener;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Tcp(SocketAddr),
+    Unix(UnixSocket),
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Endpoint::Tcp(addr) => write!(f, "{}", addr),
+            Endpoint::Unix(socket) => write!(f, "unix:{}", socket.path.display()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixSocket {
+    pub path: PathBuf,
+    pub owner: Option<u32>,
+    pub group: Option<u32>,
+    pub mode: Option<u32>,
+}
+
+// check a configured listener and resolve owner and group to ids
+pub fn resolve(listener: &Listener) -> Result<Endpoint> {
+    match (&listener.tcp, &listener.unix) {
+        (Some(addr), None) => {
+            if listener.owner.is_some() || listener.group.is_some() || listener.mode.is_some() {
+                bail!("owner, group and mode only apply to unix sockets");
+            }
+            Ok(Endpoint::Tcp(*addr))
+        }
+        (None, Some(path)) if path.is_empty() => bail!("unix socket path is empty"),
+        (None, Some(path)) => Ok(Endpoint::Unix(UnixSocket {
+            path: PathBuf::from(path),
+            owner: listener.owner.as_deref().map(user_id).transpose()?,
+            group: listener.group.as_deref().map(group_id).transpose()?,
+            mode: listener.mode.as_deref().map(parse_mode).transpose()?,
+        })),
+        (Some(_), Some(_)) => bail!("set either tcp or unix, not both"),
+        (None, None) => bail!("one of tcp or unix is required"),
+    }
+}
+
+fn parse_mode(mode: &str) -> Result<u32> {
+    match u32::from_str_radix(mode.trim_start_matches("0o"), 8) {
+        Ok(bits) if bits <= 0o7777 => Ok(bits),
+        _ => bail!("invalid mode {}, expected octal such as \"0660\"", mode),
+    }
+}
+
+fn user_id(name: &str) -> Result<u32> {
+    lookup_id("/etc/passwd", name).with_context(|| format!("unknown user {}", name))
+}
+
+fn group_id(name: &str) -> Result<u32> {
+    lookup_id("/etc/group", name).with_context(|| format!("unknown group {}", name))
+}
+
+// numeric ids are used as-is, names are looked up in the passwd/group file,
+// both store the id in the third field
+fn lookup_id(database: &str, name: &str) -> Result<u32> {
+    if let Ok(id) = name.parse() {
+        return Ok(id);
+    }
+    let contents =
+        fs::read_to_string(database).with_context(|| format!("unable to read {}", database))?;
+    contents
+        .lines()
+        .find_map(|line| {
+            let mut fields = line.split(':');
+            if fields.next()? != name {
+                return None;
+            }
+            fields.nth(1)?.parse().ok()
+        })
+        .ok_or_else(|| anyhow!("{} has no entry for {}", database, name))
+}
+
+// bind the socket with its ownership and mode already applied, replacing a
+// stale socket file left behind by a previous run
+pub fn bind_unix(socket: &UnixSocket) -> Result<UnixListener> {
+    trace!(task = "bind_unix", "init");
+    let path = &socket.path;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("unable to create {}", parent.display()))?;
+    }
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            bail!("{} exists and is not a socket", path.display());
+        }
+        if UnixStream::connect(path).is_ok() {
+            bail!("{} is in use by another server", path.display());
+        }
+        fs::remove_file(path).with_context(|| format!("unable to remove {}", path.display()))?;
+        info!(
+            task = "bind_unix",
+            "removed stale socket {}",
+            path.display()
+        );
+    }
+
+    // bound inside a private directory next to the socket, so nobody can
+    // connect before ownership and mode are applied, then moved into place
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a socket path", path.display()))?;
+    let private = path.with_file_name(format!(
+        ".{}.{}",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&private);
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&private)
+        .with_context(|| format!("unable to create {}", private.display()))?;
+    let staged = private.join(name);
+    let listener = bind_private(socket, &staged).and_then(|listener| {
+        fs::rename(&staged, path)
+            .with_context(|| format!("unable to move the socket to {}", path.display()))?;
+        Ok(listener)
+    });
+    // takes the socket along when it was not moved
+    let _ = fs::remove_dir_all(&private);
+    listener
+}
+
+fn bind_private(socket: &UnixSocket, staged: &Path) -> Result<UnixListener> {
+    let path = &socket.path;
+    let listener = UnixListener::bind(staged)
+        .with_context(|| format!("unable to listen on {}", path.display()))?;
+    if socket.owner.is_some() || socket.group.is_some() {
+        chown(staged, socket.owner, socket.group)
+            .with_context(|| format!("unable to change the owner of {}", path.display()))?;
+    }
+    if let Some(mode) = socket.mode {
+        fs::set_permissions(staged, fs::Permissions::from_mode(mode))
+            .with_context(|| format!("unable to change the mode of {}", path.display()))?;
+    }
+    Ok(listener)
+}
@@ -1,59 +1,167 @@
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
-use std::{fs::File, io::BufReader};
-use tracing::{info, Level};
-use tracing_subscriber;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use prometheus::Registry;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{error, info, warn};
 
+use mecha_simulator::{SimulatedBoard, SysfsRoot};
+use tokio::task::JoinSet;
+use tokio_stream::wrappers::UnixListenerStream;
+use tonic::transport::server::TcpIncoming;
 use tonic::transport::Server;
+use tonic_web::GrpcWebLayer;
+use tower::ServiceBuilder;
 
-mod configs;
-use crate::configs::BaseConfig;
-
-mod services;
-use crate::services::{Battery, PowerSupply, PowerSupplyServiceServer};
-use crate::services::{Bluetooth, BluetoothServiceServer};
-use crate::services::{CpuCtrl, CpuCtrlService, CpuGovernorCtrlServiceServer};
-use crate::services::{DeviceInfoCtrl, DeviceInfoServiceServer};
-use crate::services::{DeviceMetrics, DeviceMetricsService, MetricsServiceServer};
-use crate::services::{DisplayCtrl, DisplayCtrlManager, DisplayCtrlServiceServer};
-use crate::services::{LedCtrl, LedCtrlManager, LedCtrlServiceServer};
-use crate::services::{MotionSensor, MotionSensorManager, MotionSensorServiceServer};
-use crate::services::{NetworkManager, NetworkManagerServiceServer};
-use crate::services::{TrustZoneCtrl, TrustZoneCtrlServiceManager, TrustZoneCtrlServiceServer};
+use mecha_sdk_server::audit::AuditLog;
+use mecha_sdk_server::automation::{run_automation, Automation, Outputs, Sensors};
+use mecha_sdk_server::cli::Cli;
+use mecha_sdk_server::events::{
+    forward_wifi, listen_wifi, watch_battery, watch_bluetooth, watch_brightness, watch_motion,
+    EventBus,
+};
+use mecha_sdk_server::gateway::{cors_layer, RestLayer, RestRoutes};
+use mecha_sdk_server::health::{hardware_probes, report_health, ServiceGate};
+use mecha_sdk_server::listener::{bind_unix, resolve, Endpoint};
+use mecha_sdk_server::middleware::{AuditLayer, Auditor, AuthLayer, Authorizer};
+use mecha_sdk_server::reload::{watch_config, ConfigReloader, Controllers, Swappable};
+use mecha_sdk_server::services::FILE_DESCRIPTOR_SET;
+use mecha_sdk_server::services::{AuditManager, AuditServiceServer};
+use mecha_sdk_server::services::{AutomationManager, AutomationServiceServer};
+use mecha_sdk_server::services::{BlockingPool, Device};
+use mecha_sdk_server::services::{Bluetooth, BluetoothServiceServer, OnDemandBluetooth};
+use mecha_sdk_server::services::{CapabilitiesManager, DeviceCapabilitiesServiceServer};
+use mecha_sdk_server::services::{CpuCtrl, CpuCtrlService, CpuGovernorCtrlServiceServer};
+use mecha_sdk_server::services::{DeviceInfoCtrl, DeviceInfoServiceServer};
+use mecha_sdk_server::services::{DeviceMetrics, DeviceMetricsService, MetricsServiceServer};
+use mecha_sdk_server::services::{DisplayCtrlManager, DisplayCtrlServiceServer};
+use mecha_sdk_server::services::{EventManager, EventServiceServer};
+use mecha_sdk_server::services::{LedCtrlManager, LedCtrlServiceServer};
+use mecha_sdk_server::services::{MotionSensorManager, MotionSensorServiceServer};
+use mecha_sdk_server::services::{NetworkManager, NetworkManagerServiceServer, WifiModule};
+use mecha_sdk_server::services::{PowerSupply, PowerSupplyServiceServer};
+use mecha_sdk_server::services::{SettingsManager, SettingsServiceServer};
+use mecha_sdk_server::services::{
+    TrustZoneCtrl, TrustZoneCtrlServiceManager, TrustZoneCtrlServiceServer,
+};
+use mecha_sdk_server::settings::{restore, Persisted, SettingsStore};
+use mecha_sdk_server::shutdown::{drain, wait_for_signal, Shutdown};
+use mecha_sdk_server::systemd;
+use mecha_sdk_server::telemetry::{
+    bind_metrics, init_logging, serve_metrics, DeviceGauges, RpcMetrics, RpcMetricsLayer,
+    RpcTraceLayer,
+};
+use mecha_sdk_server::tls::server_tls_config;
+
+// how often the config file is checked for changes
+const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+// wait before reconnecting to wpa_supplicant for wifi events
+const WIFI_RETRY: Duration = Duration::from_secs(5);
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let profile_file = File::open("./Config.yaml").expect("Failed to open config file");
-    let reader = BufReader::new(profile_file);
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli).await {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    //yaml config, command line and MECHA_* environment overrides on top
+    let mut config = cli.load_file()?;
+
+    //log level, output format and the optional OpenTelemetry exporter
+    let _logging = init_logging(&config.logging)?;
 
-    let config: BaseConfig = serde_yaml::from_reader(reader).expect("unable to rad yaml file");
+    //simulated hardware, every device path is rebased onto a generated sysfs tree
+    if config.simulation.enabled {
+        if !cli.dry_run && !cli.check_config {
+            SimulatedBoard::new(&SysfsRoot::new(&config.simulation.root)).generate()?;
+        }
+        info!(
+            task = "mecha_grpc_tracer",
+            "simulation mode, sysfs root: {}", config.simulation.root
+        );
+    }
+
+    //board profile, named in the config or detected from the device tree
+    config.resolve_interfaces()?;
+    info!(
+        task = "mecha_grpc_tracer",
+        "board profile: {}",
+        if config.name.is_empty() {
+            "none"
+        } else {
+            config.name.as_str()
+        }
+    );
 
-    //port for grpc server
-    let port = config.server.port;
-    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), port));
+    if cli.check_config {
+        let report = config.check();
+        if report.is_empty() {
+            println!("configuration {} is valid", cli.config.display());
+            return Ok(());
+        }
+        print!("{}", report);
+        bail!(
+            "{} problem(s) found in {}",
+            report.issues.len(),
+            cli.config.display()
+        );
+    }
+    config.validate()?;
+
+    //tcp addresses and unix sockets to serve on
+    let endpoints = config
+        .server
+        .listeners()
+        .iter()
+        .map(resolve)
+        .collect::<Result<Vec<_>>>()?;
 
     //network manager service
-    let network_service = NetworkManager::default();
+    let network_service: NetworkManager = NetworkManager::default();
+
+    //sysfs backed controllers, swapped when the config file is reloaded
+    let controllers = Controllers::new(&config.interfaces);
+
+    //blocking device calls run on a bounded pool, one at a time per device
+    let devices = BlockingPool::new(
+        config.server.device_workers,
+        Duration::from_secs(config.server.device_timeout),
+    );
+
+    //brightness, led states and cpu governor are saved as they change and restored at startup
+    let settings = match &config.settings {
+        Some(settings) => {
+            info!(task = "mecha_grpc_tracer", "settings at {}", settings.path);
+            Some(SettingsStore::open(Path::new(&settings.path))?)
+        }
+        None => None,
+    };
 
     //display manager service
-    let display_ctrl = DisplayCtrl::new(config.interfaces.display.device.as_str());
-    let display_service = DisplayCtrlManager { display_ctrl };
+    let display_service = DisplayCtrlManager {
+        display_ctrl: Device::new(
+            Persisted::new(controllers.display.clone(), settings.clone()),
+            &devices,
+        ),
+    };
 
     //motion sensor service
-    let motion_sensor = MotionSensor::new(
-        config.interfaces.motion_sensor.x_axis.as_str(),
-        config.interfaces.motion_sensor.y_axis.as_str(),
-        config.interfaces.motion_sensor.z_axis.as_str(),
-    );
-    let motion_sensor_manager = MotionSensorManager { motion_sensor };
+    let motion_sensor_manager = MotionSensorManager {
+        motion_sensor: Device::new(controllers.motion_sensor.clone(), &devices),
+    };
 
     //led manager service
-    let led_ctrl = LedCtrl::new(
-        config.interfaces.led.red_led.as_str(),
-        config.interfaces.led.green_led.as_str(),
-        config.interfaces.led.blue_led.as_str(),
-    );
-
-    let led_manager = LedCtrlManager { led_ctrl };
+    let led_manager = LedCtrlManager {
+        led_ctrl: Device::new(
+            Persisted::new(controllers.led.clone(), settings.clone()),
+            &devices,
+        ),
+    };
 
     //device info service
     let device_info = DeviceInfoCtrl::default();
@@ -65,50 +173,472 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     //cpu governor service
     let cpu_governor = CpuCtrlService {
-        cpu_ctrl_manager: CpuCtrl::new(),
+        cpu_ctrl_manager: Device::new(
+            Persisted::new(
+                CpuCtrl::with_path(config.interfaces.cpu.device.as_str()),
+                settings.clone(),
+            ),
+            &devices,
+        ),
     };
 
-    //trustzone service
-    let trustzone_ctrl = TrustZoneCtrlServiceManager {
-        trustzone_ctrl: TrustZoneCtrl::new(),
+    //trustzone service, a hung trustm tool is killed when its call times out
+    let trustzone = TrustZoneCtrl {
+        timeout: Some(Duration::from_secs(config.server.device_timeout)),
+        ..TrustZoneCtrl::new()
     };
-
-    let battery = Battery {
-        path: config.interfaces.battery.device.as_str().to_string(),
-        currnet_now: config.interfaces.battery.current.as_str().to_string(),
+    let trustzone_ctrl = TrustZoneCtrlServiceManager {
+        trustzone_ctrl: Device::new(trustzone, &devices),
     };
 
     //power service
     let power_supply = PowerSupply {
-        power_supply: battery,
+        power_supply: Device::new(controllers.battery.clone(), &devices),
+    };
+
+    //bluetooth service
+    let bluetooth: Bluetooth = Bluetooth::default();
+
+    //device events, fed by the watchers spawned below and ended on shutdown
+    let events = EventBus::default();
+    let shutdown = Shutdown::new();
+    let event_service = EventManager {
+        bus: events.clone(),
+        shutdown: shutdown.clone(),
+    };
+    let watched = (
+        display_service.display_ctrl.clone(),
+        motion_sensor_manager.motion_sensor.clone(),
+        power_supply.power_supply.clone(),
+    );
+    let restored = (
+        display_service.display_ctrl.clone(),
+        led_manager.led_ctrl.clone(),
+        cpu_governor.cpu_ctrl_manager.clone(),
+    );
+
+    //rules from the config evaluated on a timer, toggled and dry run through AutomationService
+    let automation = config.automation.as_ref().map(|automation| {
+        info!(
+            task = "mecha_grpc_tracer",
+            "{} automation rule(s)",
+            automation.rules.len()
+        );
+        Automation::new(&automation.rules)
+    });
+    let automated = (
+        power_supply.power_supply.clone(),
+        motion_sensor_manager.motion_sensor.clone(),
+        display_service.display_ctrl.clone(),
+        led_manager.led_ctrl.clone(),
+        cpu_governor.cpu_ctrl_manager.clone(),
+    );
+
+    //prometheus endpoint, rpc counters and latencies plus device gauges read per scrape
+    let registry = Registry::new();
+    let prometheus = match &config.server.prometheus {
+        Some(prometheus) => {
+            let gauges = DeviceGauges {
+                metrics: Some(Device::new(Mutex::new(DeviceMetrics::new()), &devices)),
+                battery: Some(power_supply.power_supply.clone()),
+                cpu: Some(cpu_governor.cpu_ctrl_manager.clone()),
+            };
+            let metrics = RpcMetrics::new(
+                &registry,
+                &[
+                    FILE_DESCRIPTOR_SET,
+                    tonic_health::pb::FILE_DESCRIPTOR_SET,
+                    tonic_reflection::pb::FILE_DESCRIPTOR_SET,
+                ],
+            )?;
+            Some((prometheus.listen, metrics, gauges))
+        }
+        None => None,
+    };
+
+    //unix sockets rely on file permissions and are always served plaintext
+    let unix_server = Server::builder();
+    let mut tcp_server = Server::builder();
+
+    //tls, and mutual tls when a client ca is configured
+    if let Some(tls) = &config.server.tls {
+        tcp_server = tcp_server.tls_config(server_tls_config(tls)?)?;
+        info!(task = "mecha_grpc_tracer", "tls enabled");
+    } else {
+        warn!(
+            task = "mecha_grpc_tracer",
+            "server.tls is not set, serving plaintext grpc"
+        );
+    }
+
+    //bearer token authentication and per role allow-lists
+    let authorizer = match &config.auth {
+        Some(auth) => {
+            info!(task = "mecha_grpc_tracer", "auth enabled");
+            Some(Authorizer::new(auth)?)
+        }
+        None => {
+            warn!(
+                task = "mecha_grpc_tracer",
+                "auth is not set, every caller may invoke every rpc"
+            );
+            None
+        }
+    };
+
+    //append-only record of state changing rpcs, queried through AuditService
+    let audit_log = config.audit.as_ref().map(|audit| {
+        info!(task = "mecha_grpc_tracer", "audit log at {}", audit.path);
+        Device::new(AuditLog::new(audit), &devices)
+    });
+
+    //grpc health service, per service status follows the hardware probes
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+
+    //server reflection for every compiled package
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build()?;
+
+    if cli.dry_run {
+        println!("configuration {} is valid", cli.config.display());
+        return Ok(());
+    }
+
+    //disabled services and the ones whose hardware is missing are not registered
+    let services = &config.services;
+    let probes = Swappable::default();
+    let mut gate = ServiceGate::new(hardware_probes(&config.interfaces));
+    let network_service = gate
+        .admit(
+            &services.network,
+            NetworkManagerServiceServer::new(network_service),
+        )
+        .await;
+    let display_service = gate
+        .admit(
+            &services.display,
+            DisplayCtrlServiceServer::new(display_service),
+        )
+        .await;
+    let motion_sensor_service = gate
+        .admit(
+            &services.motion_sensor,
+            MotionSensorServiceServer::new(motion_sensor_manager),
+        )
+        .await;
+    let led_service = gate
+        .admit(&services.led, LedCtrlServiceServer::new(led_manager))
+        .await;
+    let device_info_service = gate
+        .admit(
+            &services.device_info,
+            DeviceInfoServiceServer::new(device_info),
+        )
+        .await;
+    let metrics_service = gate
+        .admit(&services.metrics, MetricsServiceServer::new(device_metrics))
+        .await;
+    let cpu_governor_service = gate
+        .admit(
+            &services.cpu_governor,
+            CpuGovernorCtrlServiceServer::new(cpu_governor),
+        )
+        .await;
+    let trustzone_service = gate
+        .admit(
+            &services.trustzone,
+            TrustZoneCtrlServiceServer::new(trustzone_ctrl),
+        )
+        .await;
+    let power_supply_service = gate
+        .admit(
+            &services.battery,
+            PowerSupplyServiceServer::new(power_supply),
+        )
+        .await;
+    let bluetooth_service = gate
+        .admit(&services.bluetooth, BluetoothServiceServer::new(bluetooth))
+        .await;
+    let event_service = gate
+        .admit(&services.events, EventServiceServer::new(event_service))
+        .await;
+    let audit_service = match &audit_log {
+        Some(log) => {
+            gate.admit(
+                &services.audit,
+                AuditServiceServer::new(AuditManager { log: log.clone() }),
+            )
+            .await
+        }
+        None => None,
+    };
+    let settings_service = match &settings {
+        Some(store) => {
+            gate.admit(
+                &services.settings,
+                SettingsServiceServer::new(SettingsManager {
+                    store: Device::new(store.clone(), &devices),
+                }),
+            )
+            .await
+        }
+        None => None,
+    };
+    let automation_service = match &automation {
+        Some(automation) => {
+            gate.admit(
+                &services.automation,
+                AutomationServiceServer::new(AutomationManager {
+                    automation: automation.clone(),
+                }),
+            )
+            .await
+        }
+        None => None,
+    };
+    let capabilities_service = gate
+        .admit(
+            &services.capabilities,
+            DeviceCapabilitiesServiceServer::new(CapabilitiesManager {
+                board: config.name.clone(),
+                interfaces: Device::new(controllers.interfaces.clone(), &devices),
+                services: probes.clone(),
+            }),
+        )
+        .await;
+    probes.swap(gate.into_probes());
+
+    //saved settings are reapplied to the hardware of registered services
+    if let Some(store) = &settings {
+        let (display, led, cpu) = restored;
+        let mut applied = Vec::new();
+        if display_service.is_some() {
+            applied.extend(
+                restore(&display, store, |store, display| {
+                    store.restore_display(display.inner())
+                })
+                .await,
+            );
+        }
+        if led_service.is_some() {
+            applied.extend(restore(&led, store, |store, led| store.restore_led(led.inner())).await);
+        }
+        if cpu_governor_service.is_some() {
+            applied.extend(restore(&cpu, store, |store, cpu| store.restore_cpu(cpu.inner())).await);
+        }
+        info!(
+            task = "mecha_grpc_tracer",
+            "restored {} setting(s) from {}",
+            applied.len(),
+            store.path().display()
+        );
+    }
+
+    //only the hardware of registered services is watched
+    if event_service.is_some() {
+        let interval = Duration::from_secs(config.server.event_interval);
+        let (display, motion_sensor, battery) = watched;
+        if display_service.is_some() {
+            tokio::spawn(watch_brightness(display, events.clone(), interval));
+        }
+        if motion_sensor_service.is_some() {
+            tokio::spawn(watch_motion(motion_sensor, events.clone(), interval));
+        }
+        if power_supply_service.is_some() {
+            tokio::spawn(watch_battery(battery, events.clone(), interval));
+        }
+        if bluetooth_service.is_some() {
+            tokio::spawn(watch_bluetooth(OnDemandBluetooth, events.clone(), interval));
+        }
+        if network_service.is_some() {
+            tokio::spawn(forward_wifi(
+                WifiModule::subscribe_broadcasts(),
+                events.clone(),
+            ));
+            tokio::spawn(listen_wifi(WIFI_RETRY));
+        }
+    }
+
+    //rules only read and drive the hardware of registered services
+    if let (Some(automation), Some(section)) = (automation, &config.automation) {
+        let (battery, motion_sensor, display, led, cpu) = automated;
+        let sensors = Sensors {
+            battery: power_supply_service.is_some().then_some(battery),
+            metrics: metrics_service
+                .is_some()
+                .then(|| Device::new(Mutex::new(DeviceMetrics::new()), &devices)),
+            motion: motion_sensor_service.is_some().then_some(motion_sensor),
+            wifi: network_service.is_some().then(WifiModule::new),
+        };
+        let outputs = Outputs {
+            display: display_service.is_some().then_some(display),
+            led: led_service.is_some().then_some(led),
+            cpu: cpu_governor_service.is_some().then_some(cpu),
+        };
+        tokio::spawn(run_automation(
+            automation,
+            sensors,
+            outputs,
+            Duration::from_secs(section.interval.max(1)),
+        ));
+    }
+
+    tokio::spawn(report_health(
+        health_reporter,
+        probes.clone(),
+        Duration::from_secs(config.server.health_interval.max(1)),
+    ));
+
+    let drain_timeout = Duration::from_secs(config.server.drain_timeout);
+
+    //every listener checks bearer tokens, the gateway included
+    let auth_layer = AuthLayer::new(authorizer);
+
+    //only calls that passed the auth layer are audited
+    let audit_layer = AuditLayer::new(
+        audit_log
+            .map(|log| Auditor::new(log, FILE_DESCRIPTOR_SET))
+            .transpose()?,
+    );
+
+    //rpcs rejected by the auth layer are counted too
+    let metrics_layer =
+        RpcMetricsLayer::new(prometheus.as_ref().map(|(_, metrics, _)| metrics.clone()));
+
+    //cors, rest and grpc-web are translated in front of the auth layer
+    let gateway = match &config.server.gateway {
+        Some(gateway) => {
+            let layers = ServiceBuilder::new()
+                .layer(cors_layer(&gateway.cors)?)
+                .layer(RestLayer::new(RestRoutes::new(FILE_DESCRIPTOR_SET)?))
+                .layer(GrpcWebLayer::new())
+                .layer(RpcTraceLayer)
+                .layer(metrics_layer.clone())
+                .layer(auth_layer.clone())
+                .layer(audit_layer.clone())
+                .into_inner();
+            Some((gateway.listen, layers))
+        }
+        None => None,
     };
 
-    println!("Mecha Edge Server listening on {}", addr);
+    //hot reload on SIGHUP or when the config file changes
+    let config_path = cli.config.clone();
+    let reloader = ConfigReloader::new(move || cli.load_config(), config, controllers, probes);
+    tokio::spawn(async move {
+        if let Err(e) = watch_config(reloader, config_path, CONFIG_WATCH_INTERVAL).await {
+            error!(task = "watch_config", "config reload disabled: {:#}", e);
+        }
+    });
+
+    //SIGTERM/SIGINT stop accepting connections and drain in-flight rpcs
+    let trigger = shutdown.clone();
+    tokio::spawn(async move {
+        if let Err(e) = wait_for_signal().await {
+            error!(task = "shutdown", "{:#}", e);
+            return;
+        }
+        systemd::notify_stopping();
+        trigger.trigger();
+    });
+
+    //every listener serves the same services, health first as tonic only
+    //answers unknown services with UNIMPLEMENTED when the first one is registered
+    macro_rules! routes {
+        ($server:expr) => {
+            $server
+                .add_service(health_service.clone())
+                .add_service(reflection_service.clone())
+                .add_optional_service(network_service.clone())
+                .add_optional_service(display_service.clone())
+                .add_optional_service(motion_sensor_service.clone())
+                .add_optional_service(led_service.clone())
+                .add_optional_service(device_info_service.clone())
+                .add_optional_service(metrics_service.clone())
+                .add_optional_service(cpu_governor_service.clone())
+                .add_optional_service(trustzone_service.clone())
+                .add_optional_service(power_supply_service.clone())
+                .add_optional_service(bluetooth_service.clone())
+                .add_optional_service(event_service.clone())
+                .add_optional_service(audit_service.clone())
+                .add_optional_service(settings_service.clone())
+                .add_optional_service(automation_service.clone())
+                .add_optional_service(capabilities_service.clone())
+        };
+    }
 
-    let subscriber = tracing_subscriber::fmt()
-        // filter spans/events with level TRACE or higher.
-        .with_max_level(Level::TRACE)
-        // build but do not install the subscriber.
-        .finish();
+    //bind every listener before notifying systemd, so readiness means clients can connect
+    let mut serving = JoinSet::new();
+    for endpoint in endpoints {
+        match &endpoint {
+            Endpoint::Tcp(addr) => {
+                let incoming = TcpIncoming::new(*addr, true, None)
+                    .map_err(|e| anyhow!(e))
+                    .with_context(|| format!("unable to listen on {}", addr))?;
+                let serve = routes!(tcp_server
+                    .clone()
+                    .layer(RpcTraceLayer)
+                    .layer(metrics_layer.clone())
+                    .layer(auth_layer.clone())
+                    .layer(audit_layer.clone()))
+                .serve_with_incoming_shutdown(incoming, shutdown.triggered());
+                let shutdown = shutdown.clone();
+                serving.spawn(async move { drain(serve, &shutdown, drain_timeout).await });
+            }
+            Endpoint::Unix(socket) => {
+                let incoming = UnixListenerStream::new(bind_unix(socket)?);
+                let serve = routes!(unix_server
+                    .clone()
+                    .layer(RpcTraceLayer)
+                    .layer(metrics_layer.clone())
+                    .layer(auth_layer.clone())
+                    .layer(audit_layer.clone()))
+                .serve_with_incoming_shutdown(incoming, shutdown.triggered());
+                let shutdown = shutdown.clone();
+                let path = socket.path.clone();
+                serving.spawn(async move {
+                    let result = drain(serve, &shutdown, drain_timeout).await;
+                    let _ = std::fs::remove_file(&path);
+                    result
+                });
+            }
+        }
+        println!("Mecha Edge Server listening on {}", endpoint);
+    }
+
+    //http/1.1 gateway, grpc-web and the json rest mapping of the proto files
+    if let Some((addr, layers)) = gateway {
+        let incoming = TcpIncoming::new(addr, true, None)
+            .map_err(|e| anyhow!(e))
+            .with_context(|| format!("unable to listen on {}", addr))?;
+        let serve = routes!(tcp_server.clone().accept_http1(true).layer(layers))
+            .serve_with_incoming_shutdown(incoming, shutdown.triggered());
+        let shutdown = shutdown.clone();
+        serving.spawn(async move { drain(serve, &shutdown, drain_timeout).await });
+        println!("Mecha Edge Server gateway listening on {}", addr);
+    }
+
+    //prometheus text format over plain http
+    if let Some((addr, _, gauges)) = prometheus {
+        let serve = serve_metrics(bind_metrics(addr)?, registry, gauges, &shutdown)?;
+        serving.spawn(serve);
+        println!("Mecha Edge Server metrics listening on {}", addr);
+    }
 
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
     info!(
         task = "mecha_grpc_tracer",
         result = "success",
         "grpc server started"
     );
-    Server::builder()
-        .add_service(NetworkManagerServiceServer::new(network_service))
-        .add_service(DisplayCtrlServiceServer::new(display_service))
-        .add_service(MotionSensorServiceServer::new(motion_sensor_manager))
-        .add_service(LedCtrlServiceServer::new(led_manager))
-        .add_service(DeviceInfoServiceServer::new(device_info))
-        .add_service(MetricsServiceServer::new(device_metrics))
-        .add_service(CpuGovernorCtrlServiceServer::new(cpu_governor))
-        .add_service(TrustZoneCtrlServiceServer::new(trustzone_ctrl))
-        .add_service(PowerSupplyServiceServer::new(power_supply))
-        .add_service(BluetoothServiceServer::new(Bluetooth::default()))
-        .serve(addr)
-        .await?;
+    systemd::notify_ready();
+    systemd::spawn_watchdog();
+
+    //the first listener that fails stops the server
+    while let Some(result) = serving.join_next().await {
+        result??;
+    }
+    info!(task = "mecha_grpc_tracer", "grpc server stopped");
     Ok(())
 }
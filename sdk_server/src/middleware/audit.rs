@@ -0,0 +1,211 @@
+use anyhow::{Context as _, Result};
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, SerializeOptions, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, Body as _, BoxFuture, Service};
+use tonic::transport::Body;
+use tonic::{Code, Status};
+use tower::Layer;
+use tracing::{error, trace};
+
+use super::{take_ready, Caller};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::services::Device;
+use crate::telemetry::peer;
+
+// rpcs that change the state of the device
+pub const AUDITED: &[&str] = &[
+    "/displaymanager.DisplayCtrlService/SetBrightness",
+    "/led_ctrl.LedCtrlService/SetLed",
+    "/led_ctrl.LedCtrlService/ClearLed",
+    "/cpugovernorctrl.CPUGovernorCtrlService/SetGovernor",
+    "/cpugovernorctrl.CPUGovernorCtrlService/SetCPUFrequency",
+    "/networkmanager.NetworkManagerService/ConnectWirelessNetwork",
+    "/networkmanager.NetworkManagerService/DisconnectWirelessNetwork",
+    "/trustzonectrl.TrustZoneCtrlService/WriteCertificate",
+    "/trustzonectrl.TrustZoneCtrlService/RemoveCertificate",
+    "/trustzonectrl.TrustZoneCtrlService/GenerateKey",
+    "/battery.PowerSupplyService/SetDevice",
+    "/bluetooth.BluetoothService/EnableBluetooth",
+    "/bluetooth.BluetoothService/DisableBluetooth",
+    "/settings.SettingsService/ResetSettings",
+    "/automation.AutomationService/EnableRule",
+    "/automation.AutomationService/DisableRule",
+];
+
+// request fields never written to the log
+const REDACTED: &[&str] = &["networkmanager.WifiConnectRequest.psk"];
+
+// largest message the grpc services decode, tonic's default
+const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+// a message and its 5 byte grpc frame header
+const MAX_FRAME_SIZE: usize = MAX_MESSAGE_SIZE + 5;
+const REDACTED_VALUE: &str = "[redacted]";
+
+// decodes the requests of audited rpcs and appends one entry per call
+pub struct Auditor {
+    log: Device<AuditLog>,
+    inputs: HashMap<String, MessageDescriptor>,
+}
+
+impl Auditor {
+    pub fn new(log: Device<AuditLog>, file_descriptor_set: &[u8]) -> Result<Self> {
+        trace!(task = "new", "init");
+        let pool = DescriptorPool::decode(file_descriptor_set)
+            .context("unable to decode the file descriptor set")?;
+        let mut inputs = HashMap::new();
+        for path in AUDITED {
+            let (service, method) = path
+                .trim_start_matches('/')
+                .split_once('/')
+                .unwrap_or_default();
+            let input = pool
+                .get_service_by_name(service)
+                .and_then(|service| service.methods().find(|rpc| rpc.name() == method))
+                .map(|rpc| rpc.input())
+                .with_context(|| format!("audited rpc {} is not in the descriptor set", path))?;
+            inputs.insert(path.to_string(), input);
+        }
+        Ok(Auditor { log, inputs })
+    }
+
+    // the request as json, `null` for compressed or undecodable frames
+    fn summary(&self, path: &str, frame: &[u8]) -> serde_json::Value {
+        let (Some(input), Some((0, payload))) = (self.inputs.get(path), frame.split_first()) else {
+            return serde_json::Value::Null;
+        };
+        let Some(payload) = payload.get(4..) else {
+            return serde_json::Value::Null;
+        };
+        let mut message = match DynamicMessage::decode(input.clone(), payload) {
+            Ok(message) => message,
+            Err(_) => return serde_json::Value::Null,
+        };
+        for field in input.fields() {
+            if REDACTED.contains(&field.full_name()) && message.has_field(&field) {
+                message.set_field(&field, Value::String(REDACTED_VALUE.to_string()));
+            }
+        }
+        let options = SerializeOptions::new()
+            .skip_default_fields(false)
+            .use_proto_field_name(true);
+        message
+            .serialize_with_options(serde_json::value::Serializer, &options)
+            .unwrap_or_default()
+    }
+
+    async fn record(&self, entry: AuditEntry) {
+        let method = entry.method.clone();
+        if let Err(e) = self.log.run("append", move |log| log.append(&entry)).await {
+            error!(task = "record", "unable to audit {}: {:#}", method, e);
+        }
+    }
+}
+
+// tower layer auditing the rpcs in `AUDITED`, it sits behind the auth layer
+// so rejected calls never reach it. a layer without an auditor records nothing
+#[derive(Clone, Default)]
+pub struct AuditLayer {
+    auditor: Option<Arc<Auditor>>,
+}
+
+impl AuditLayer {
+    pub fn new(auditor: Option<Auditor>) -> Self {
+        AuditLayer {
+            auditor: auditor.map(Arc::new),
+        }
+    }
+}
+
+impl<S> Layer<S> for AuditLayer {
+    type Service = Audited<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Audited {
+            inner,
+            auditor: self.auditor.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Audited<S> {
+    inner: S,
+    auditor: Option<Arc<Auditor>>,
+}
+
+impl<S> Service<http::Request<Body>> for Audited<S>
+where
+    S: Service<http::Request<Body>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<Body>) -> Self::Future {
+        let mut inner = take_ready(&mut self.inner);
+        let path = request.uri().path().to_string();
+        let auditor = match &self.auditor {
+            Some(auditor) if auditor.inputs.contains_key(&path) => auditor.clone(),
+            _ => return Box::pin(inner.call(request)),
+        };
+
+        let caller = request.extensions().get::<Caller>().cloned();
+        let peer = peer(&request);
+        Box::pin(async move {
+            // the body is read once for the log and handed on as is
+            let (parts, body) = request.into_parts();
+            let frame = match read_frame(body).await {
+                Ok(frame) => frame,
+                Err(status) => return Ok(status.to_http()),
+            };
+            let summary = auditor.summary(&path, &frame);
+            let response = inner
+                .call(http::Request::from_parts(parts, Body::from(frame)))
+                .await?;
+
+            // failed calls carry their status in the headers
+            let status = Status::from_header_map(response.headers());
+            let entry = AuditEntry {
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_millis() as u64)
+                    .unwrap_or_default(),
+                role: caller.as_ref().map(|caller| caller.role.clone()),
+                caller: caller.map(|caller| caller.name),
+                peer,
+                method: path,
+                request: summary,
+                code: format!("{:?}", status.as_ref().map_or(Code::Ok, Status::code)),
+                message: status
+                    .map(|status| status.message().to_string())
+                    .unwrap_or_default(),
+            };
+            auditor.record(entry).await;
+            Ok(response)
+        })
+    }
+}
+
+async fn read_frame(mut body: Body) -> Result<Vec<u8>, Status> {
+    let mut frame = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| Status::invalid_argument(e.to_string()))?;
+        if frame.len() + chunk.len() > MAX_FRAME_SIZE {
+            return Err(Status::resource_exhausted(format!(
+                "request is larger than {} bytes",
+                MAX_MESSAGE_SIZE
+            )));
+        }
+        frame.extend_from_slice(&chunk);
+    }
+    Ok(frame)
+}
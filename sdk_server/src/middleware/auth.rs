@@ -0,0 +1,229 @@
+use anyhow::{bail, Result};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::Status;
+use tower::Layer;
+use tracing::{trace, warn};
+
+use super::take_ready;
+use crate::configs::Auth;
+
+// services callers may reach without a token, probes rarely carry credentials
+const PUBLIC_SERVICES: &[&str] = &["/grpc.health.v1.Health/"];
+
+// identity of an authenticated caller, stored in the request extensions so
+// services and later layers can tell who is calling
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller {
+    pub name: String,
+    pub role: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken,
+    PermissionDenied { role: String, path: String },
+}
+
+impl From<AuthError> for Status {
+    fn from(err: AuthError) -> Self {
+        match err {
+            AuthError::MissingToken => Status::unauthenticated("missing bearer token"),
+            AuthError::InvalidToken => Status::unauthenticated("invalid bearer token"),
+            AuthError::PermissionDenied { role, path } => {
+                Status::permission_denied(format!("role {} is not allowed to call {}", role, path))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    role: String,
+}
+
+pub struct Authorizer {
+    tokens: HashMap<String, Caller>,
+    jwt_key: Option<DecodingKey>,
+    roles: HashMap<String, Vec<String>>,
+}
+
+impl Authorizer {
+    pub fn new(auth: &Auth) -> Result<Self> {
+        trace!(task = "new", "init");
+        let mut tokens = HashMap::new();
+        for token in &auth.tokens {
+            if !auth.roles.contains_key(&token.role) {
+                bail!(
+                    "auth token {} uses undefined role {}",
+                    token.name,
+                    token.role
+                );
+            }
+            let caller = Caller {
+                name: token.name.clone(),
+                role: token.role.clone(),
+            };
+            if tokens.insert(token.token.clone(), caller).is_some() {
+                bail!("auth token {} is not unique", token.name);
+            }
+        }
+
+        Ok(Authorizer {
+            tokens,
+            jwt_key: auth
+                .jwt_secret
+                .as_ref()
+                .map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+            roles: auth.roles.clone(),
+        })
+    }
+
+    // validate the `authorization` header and check that the caller's role may
+    // invoke `path` (`/package.Service/Method`)
+    pub fn check(&self, authorization: Option<&str>, path: &str) -> Result<Caller, AuthError> {
+        let caller = self.authenticate(authorization)?;
+        if !self.is_allowed(&caller.role, path) {
+            warn!(
+                task = "auth",
+                "{} ({}) is not allowed to call {}", caller.name, caller.role, path
+            );
+            return Err(AuthError::PermissionDenied {
+                role: caller.role,
+                path: path.to_string(),
+            });
+        }
+        Ok(caller)
+    }
+
+    fn authenticate(&self, authorization: Option<&str>) -> Result<Caller, AuthError> {
+        let token = match authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+            Some(token) => token.trim(),
+            None => return Err(AuthError::MissingToken),
+        };
+
+        if let Some(caller) = self.tokens.get(token) {
+            return Ok(caller.clone());
+        }
+
+        if let Some(key) = &self.jwt_key {
+            match decode::<Claims>(token, key, &Validation::new(Algorithm::HS256)) {
+                Ok(data) if self.roles.contains_key(&data.claims.role) => {
+                    return Ok(Caller {
+                        name: data.claims.sub,
+                        role: data.claims.role,
+                    })
+                }
+                Ok(data) => {
+                    warn!(
+                        task = "auth",
+                        "jwt for {} has unknown role {}", data.claims.sub, data.claims.role
+                    );
+                }
+                Err(e) => warn!(task = "auth", "invalid jwt: {}", e),
+            }
+        }
+
+        Err(AuthError::InvalidToken)
+    }
+
+    fn is_allowed(&self, role: &str, path: &str) -> bool {
+        let (full_service, method) = match path.trim_start_matches('/').split_once('/') {
+            Some(parts) => parts,
+            None => return false,
+        };
+        let service = full_service.rsplit('.').next().unwrap_or(full_service);
+
+        let rules = match self.roles.get(role) {
+            Some(rules) => rules,
+            None => return false,
+        };
+        rules.iter().any(|rule| {
+            if rule == "*" {
+                return true;
+            }
+            let (rule_service, rule_method) = match rule.split_once('/') {
+                Some((rule_service, rule_method)) => (rule_service, Some(rule_method)),
+                None => (rule.as_str(), None),
+            };
+            (rule_service == full_service || rule_service == service)
+                && rule_method.is_none_or(|m| m == "*" || m == method)
+        })
+    }
+}
+
+// tower layer running the authorizer in front of every grpc service, a
+// layer without an authorizer lets every request through
+#[derive(Clone, Default)]
+pub struct AuthLayer {
+    authorizer: Option<Arc<Authorizer>>,
+}
+
+impl AuthLayer {
+    pub fn new(authorizer: Option<Authorizer>) -> Self {
+        AuthLayer {
+            authorizer: authorizer.map(Arc::new),
+        }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = AuthService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuthService {
+            inner,
+            authorizer: self.authorizer.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuthService<S> {
+    inner: S,
+    authorizer: Option<Arc<Authorizer>>,
+}
+
+impl<S, B> Service<http::Request<B>> for AuthService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        let path = request.uri().path();
+        let public = PUBLIC_SERVICES
+            .iter()
+            .any(|prefix| path.starts_with(prefix));
+        if let (Some(authorizer), false) = (&self.authorizer, public) {
+            let authorization = request
+                .headers()
+                .get(http::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok());
+            match authorizer.check(authorization, request.uri().path()) {
+                Ok(caller) => {
+                    request.extensions_mut().insert(caller);
+                }
+                Err(err) => return Box::pin(async move { Ok(Status::from(err).to_http()) }),
+            }
+        }
+
+        let mut inner = take_ready(&mut self.inner);
+        Box::pin(inner.call(request))
+    }
+}
@@ -0,0 +1,15 @@
+mod auth;
+pub use auth::{AuthError, AuthLayer, AuthService, Authorizer, Caller};
+
+mod audit;
+pub use audit::{AuditLayer, Audited, Auditor, AUDITED};
+
+/// Takes the inner service of a tower service for one call.
+///
+/// Only the inner service `poll_ready` was called on is ready to take the
+/// request, so that one is handed out and a fresh clone is left in its place
+/// for the next `poll_ready`.
+pub(crate) fn take_ready<S: Clone>(inner: &mut S) -> S {
+    let clone = inner.clone();
+    std::mem::replace(inner, clone)
+}
@@ -0,0 +1,369 @@
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard};
+use std::time::{Duration, SystemTime};
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::{error, info, trace, warn};
+
+use crate::configs::{BaseConfig, Interfaces};
+use crate::health::{hardware_probes, HardwareProbe};
+use crate::services::{
+    Battery, DisplayControl, DisplayCtrl, LedColor, LedControl, LedCtrl, MotionSensor,
+    MotionSensorControl, PowerSupplyInfo,
+};
+use mecha_battery_ctrl::PowerSupply as PowerSupplyData;
+
+// interface sections whose controllers are swapped on reload, every other
+// change only takes effect after a restart
+const RELOADABLE: [&str; 4] = [
+    "interfaces.display.",
+    "interfaces.led.",
+    "interfaces.motion_sensor.",
+    "interfaces.battery.",
+];
+
+// a value shared between the running services and the reloader, readers
+// always see the instance stored last
+#[derive(Debug, Default)]
+pub struct Swappable<T>(Arc<RwLock<T>>);
+
+impl<T> Clone for Swappable<T> {
+    fn clone(&self) -> Self {
+        Swappable(self.0.clone())
+    }
+}
+
+impl<T> Swappable<T> {
+    pub fn new(inner: T) -> Self {
+        Swappable(Arc::new(RwLock::new(inner)))
+    }
+
+    pub fn swap(&self, inner: T) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = inner;
+    }
+
+    pub fn get(&self) -> RwLockReadGuard<'_, T> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: DisplayControl> DisplayControl for Swappable<T> {
+    fn set_display_brightness(&self, brightness: u8) -> Result<()> {
+        self.get().set_display_brightness(brightness)
+    }
+
+    fn get_display_brightness(&self) -> Result<u8> {
+        self.get().get_display_brightness()
+    }
+}
+
+impl<T: LedControl> LedControl for Swappable<T> {
+    fn set_led(&self, color: LedColor) -> Result<()> {
+        self.get().set_led(color)
+    }
+
+    fn clear_led(&self, color: LedColor) -> Result<()> {
+        self.get().clear_led(color)
+    }
+}
+
+impl<T: MotionSensorControl> MotionSensorControl for Swappable<T> {
+    fn read_motion_sensor_value(&self) -> Result<(f64, f64, f64)> {
+        self.get().read_motion_sensor_value()
+    }
+
+    fn detect_motion_sensor_event(&self) -> Result<bool> {
+        self.get().detect_motion_sensor_event()
+    }
+}
+
+impl<T: PowerSupplyInfo> PowerSupplyInfo for Swappable<T> {
+    fn info(&self) -> Result<PowerSupplyData> {
+        self.get().info()
+    }
+
+    fn set_device(&mut self, device: &str) -> Result<()> {
+        self.0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .set_device(device)
+    }
+
+    fn get_device(&self) -> Result<String> {
+        self.get().get_device()
+    }
+
+    fn get_current(&self) -> Result<i64> {
+        self.get().get_current()
+    }
+}
+
+// the sysfs backed controllers that follow the config file, and the device
+// paths they were built from
+#[derive(Debug, Clone)]
+pub struct Controllers {
+    pub display: Swappable<DisplayCtrl>,
+    pub led: Swappable<LedCtrl>,
+    pub motion_sensor: Swappable<MotionSensor>,
+    pub battery: Swappable<Battery>,
+    pub interfaces: Swappable<Interfaces>,
+}
+
+impl Controllers {
+    pub fn new(interfaces: &Interfaces) -> Self {
+        Controllers {
+            display: Swappable::new(display_ctrl(interfaces)),
+            led: Swappable::new(led_ctrl(interfaces)),
+            motion_sensor: Swappable::new(motion_sensor(interfaces)),
+            battery: Swappable::new(battery(interfaces)),
+            interfaces: Swappable::new(interfaces.clone()),
+        }
+    }
+
+    // replace the controllers whose interface section changed
+    fn apply(&self, interfaces: &Interfaces, changes: &[ConfigChange]) {
+        let changed = |section: &str| changes.iter().any(|change| change.key.starts_with(section));
+        if changed("interfaces.display.") {
+            self.display.swap(display_ctrl(interfaces));
+        }
+        if changed("interfaces.led.") {
+            self.led.swap(led_ctrl(interfaces));
+        }
+        if changed("interfaces.motion_sensor.") {
+            self.motion_sensor.swap(motion_sensor(interfaces));
+        }
+        if changed("interfaces.battery.") {
+            self.battery.swap(battery(interfaces));
+        }
+        if changes.iter().any(ConfigChange::is_reloadable) {
+            self.interfaces.swap(interfaces.clone());
+        }
+    }
+}
+
+fn display_ctrl(interfaces: &Interfaces) -> DisplayCtrl {
+    DisplayCtrl::new(interfaces.display.device.as_str())
+}
+
+fn led_ctrl(interfaces: &Interfaces) -> LedCtrl {
+    LedCtrl::new(
+        interfaces.led.red_led.as_str(),
+        interfaces.led.green_led.as_str(),
+        interfaces.led.blue_led.as_str(),
+    )
+}
+
+fn motion_sensor(interfaces: &Interfaces) -> MotionSensor {
+    MotionSensor::new(
+        interfaces.motion_sensor.x_axis.as_str(),
+        interfaces.motion_sensor.y_axis.as_str(),
+        interfaces.motion_sensor.z_axis.as_str(),
+    )
+}
+
+fn battery(interfaces: &Interfaces) -> Battery {
+    Battery {
+        path: interfaces.battery.device.clone(),
+        currnet_now: interfaces.battery.current.clone(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigChange {
+    pub key: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl ConfigChange {
+    pub fn is_reloadable(&self) -> bool {
+        RELOADABLE
+            .iter()
+            .any(|section| self.key.starts_with(section))
+    }
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |value: &Option<String>| match value {
+            // tokens and jwt secrets never reach the log
+            Some(_) if self.key.starts_with("auth") => "<redacted>".to_string(),
+            Some(value) => value.clone(),
+            None => "<unset>".to_string(),
+        };
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.key,
+            value(&self.old),
+            value(&self.new)
+        )
+    }
+}
+
+// every leaf key whose value differs between the two configs
+pub fn diff(old: &BaseConfig, new: &BaseConfig) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    let old = serde_yaml::to_value(old).unwrap_or(Value::Null);
+    let new = serde_yaml::to_value(new).unwrap_or(Value::Null);
+    diff_values("", Some(&old), Some(&new), &mut changes);
+    changes
+}
+
+fn diff_values(
+    key: &str,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<ConfigChange>,
+) {
+    if let (Some(Value::Mapping(old)), Some(Value::Mapping(new))) = (old, new) {
+        let added = new.keys().filter(|name| !old.contains_key(*name));
+        for name in old.keys().chain(added) {
+            let child = match name.as_str() {
+                Some(name) if key.is_empty() => name.to_string(),
+                Some(name) => format!("{}.{}", key, name),
+                None => continue,
+            };
+            diff_values(&child, old.get(name), new.get(name), changes);
+        }
+        return;
+    }
+    if old != new {
+        changes.push(ConfigChange {
+            key: key.to_string(),
+            old: old.and_then(scalar),
+            new: new.and_then(scalar),
+        });
+    }
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(value.clone()),
+        value => serde_yaml::to_string(value)
+            .ok()
+            .map(|value| value.trim_end().to_string()),
+    }
+}
+
+// keeps the running config and swaps controllers and health probes when a
+// reloaded one passes validation
+pub struct ConfigReloader<F> {
+    load: F,
+    config: BaseConfig,
+    controllers: Controllers,
+    probes: Swappable<Vec<HardwareProbe>>,
+}
+
+impl<F> ConfigReloader<F>
+where
+    F: FnMut() -> Result<BaseConfig>,
+{
+    pub fn new(
+        load: F,
+        config: BaseConfig,
+        controllers: Controllers,
+        probes: Swappable<Vec<HardwareProbe>>,
+    ) -> Self {
+        ConfigReloader {
+            load,
+            config,
+            controllers,
+            probes,
+        }
+    }
+
+    pub fn config(&self) -> &BaseConfig {
+        &self.config
+    }
+
+    // an invalid config is rejected and the running one kept
+    pub fn reload(&mut self) -> Result<Vec<ConfigChange>> {
+        let config = (self.load)()?;
+        config.validate()?;
+
+        let changes = diff(&self.config, &config);
+        for change in &changes {
+            if change.is_reloadable() {
+                info!(task = "reload_config", "{}", change);
+            } else {
+                warn!(
+                    task = "reload_config",
+                    "{}, takes effect after a restart", change
+                );
+            }
+        }
+        self.controllers.apply(&config.interfaces, &changes);
+
+        // registered services keep their probe, pointed at the new paths
+        if changes.iter().any(ConfigChange::is_reloadable) {
+            let services = self
+                .probes
+                .get()
+                .iter()
+                .map(|probe| probe.service)
+                .collect::<Vec<_>>();
+            let probes = hardware_probes(&config.interfaces)
+                .into_iter()
+                .filter(|probe| services.contains(&probe.service))
+                .collect();
+            self.probes.swap(probes);
+        }
+        self.config = config;
+        Ok(changes)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// reload on SIGHUP and whenever the modification time of `path` changes,
+// checked every `interval`
+pub async fn watch_config<F>(
+    mut reloader: ConfigReloader<F>,
+    path: PathBuf,
+    interval: Duration,
+) -> Result<()>
+where
+    F: FnMut() -> Result<BaseConfig>,
+{
+    trace!(task = "watch_config", "init");
+    let mut hangup = signal(SignalKind::hangup()).context("unable to handle SIGHUP")?;
+    let mut ticker = tokio::time::interval(interval);
+    let mut last = modified(&path);
+    loop {
+        tokio::select! {
+            _ = hangup.recv() => {
+                info!(task = "watch_config", "SIGHUP received, reloading {}", path.display());
+            }
+            _ = ticker.tick() => {
+                let current = modified(&path);
+                if current == last {
+                    continue;
+                }
+                last = current;
+                info!(task = "watch_config", "{} changed, reloading", path.display());
+            }
+        }
+        match reloader.reload() {
+            Ok(changes) if changes.is_empty() => {
+                info!(task = "watch_config", "configuration unchanged")
+            }
+            Ok(changes) => info!(
+                task = "watch_config",
+                "configuration reloaded, {} change(s)",
+                changes.len()
+            ),
+            Err(e) => error!(
+                task = "watch_config",
+                "reload rejected, keeping the running configuration: {:#}", e
+            ),
+        }
+    }
+}
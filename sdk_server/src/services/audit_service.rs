@@ -0,0 +1,70 @@
+use tonic::{Request, Response, Status};
+
+use super::{into_status, Device};
+use crate::audit::{AuditEntry, AuditFilter, AuditLog};
+
+#[allow(non_snake_case)]
+pub mod audit {
+    tonic::include_proto!("audit");
+}
+
+pub use audit::{
+    audit_service_server::{AuditService, AuditServiceServer},
+    AuditRecord, QueryRequest, QueryResponse,
+};
+
+pub const DEFAULT_QUERY_LIMIT: u32 = 100;
+pub const MAX_QUERY_LIMIT: u32 = 1000;
+
+// reads the audit log the audit layer writes, through the same device so
+// queries never see a half rotated log
+#[derive(Debug, Clone)]
+pub struct AuditManager {
+    pub log: Device<AuditLog>,
+}
+
+impl From<AuditEntry> for AuditRecord {
+    fn from(entry: AuditEntry) -> Self {
+        AuditRecord {
+            timestamp: entry.timestamp,
+            caller: entry.caller.unwrap_or_default(),
+            role: entry.role.unwrap_or_default(),
+            peer: entry.peer,
+            method: entry.method,
+            request: entry.request.to_string(),
+            code: entry.code,
+            message: entry.message,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl AuditService for AuditManager {
+    async fn query(
+        &self,
+        request: Request<QueryRequest>,
+    ) -> Result<Response<QueryResponse>, Status> {
+        let request = request.into_inner();
+        let limit = match request.limit {
+            0 => DEFAULT_QUERY_LIMIT,
+            limit => limit.min(MAX_QUERY_LIMIT),
+        };
+        let filter = AuditFilter {
+            caller: Some(request.caller).filter(|caller| !caller.is_empty()),
+            method: Some(request.method).filter(|method| !method.is_empty()),
+            since: Some(request.since).filter(|since| *since > 0),
+            until: Some(request.until).filter(|until| *until > 0),
+        };
+
+        match self
+            .log
+            .run("query", move |log| log.query(&filter, limit as usize))
+            .await
+        {
+            Ok(entries) => Ok(Response::new(QueryResponse {
+                records: entries.into_iter().map(AuditRecord::from).collect(),
+            })),
+            Err(err) => Err(into_status(err)),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use tonic::{Request, Response, Status};
+
+use super::into_status;
+use crate::automation::{check, describe, Automation, RuleStatus};
+
+#[allow(non_snake_case)]
+pub mod automation {
+    tonic::include_proto!("automation");
+}
+
+pub use automation::{
+    automation_service_server::{AutomationService, AutomationServiceServer},
+    ConditionCheck, DryRunResponse, ListRulesRequest, ListRulesResponse, Rule, RuleRequest,
+};
+
+// lists the rules the scheduler evaluates, toggles and dry runs them
+#[derive(Debug, Clone)]
+pub struct AutomationManager {
+    pub automation: Automation,
+}
+
+impl From<RuleStatus> for Rule {
+    fn from(status: RuleStatus) -> Self {
+        Rule {
+            conditions: check(&status.rule.when, &Default::default())
+                .into_iter()
+                .map(|check| check.condition)
+                .collect(),
+            then: describe(&status.rule.then),
+            otherwise: status
+                .rule
+                .otherwise
+                .as_ref()
+                .map(describe)
+                .unwrap_or_default(),
+            name: status.rule.name,
+            enabled: status.enabled,
+            active: status.active,
+            fired: status.fired,
+            last_fired: status.last_fired,
+            last_error: status.last_error.unwrap_or_default(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl AutomationService for AutomationManager {
+    async fn list_rules(
+        &self,
+        _request: Request<ListRulesRequest>,
+    ) -> Result<Response<ListRulesResponse>, Status> {
+        Ok(Response::new(ListRulesResponse {
+            rules: self
+                .automation
+                .rules()
+                .into_iter()
+                .map(Rule::from)
+                .collect(),
+        }))
+    }
+
+    async fn enable_rule(&self, request: Request<RuleRequest>) -> Result<Response<Rule>, Status> {
+        match self
+            .automation
+            .set_enabled(&request.into_inner().name, true)
+        {
+            Ok(status) => Ok(Response::new(status.into())),
+            Err(err) => Err(into_status(err)),
+        }
+    }
+
+    async fn disable_rule(&self, request: Request<RuleRequest>) -> Result<Response<Rule>, Status> {
+        match self
+            .automation
+            .set_enabled(&request.into_inner().name, false)
+        {
+            Ok(status) => Ok(Response::new(status.into())),
+            Err(err) => Err(into_status(err)),
+        }
+    }
+
+    async fn dry_run_rule(
+        &self,
+        request: Request<RuleRequest>,
+    ) -> Result<Response<DryRunResponse>, Status> {
+        let name = request.into_inner().name;
+        match self.automation.dry_run(&name) {
+            Ok(dry_run) => Ok(Response::new(DryRunResponse {
+                name,
+                holds: dry_run.holds,
+                checks: dry_run
+                    .checks
+                    .into_iter()
+                    .map(|check| ConditionCheck {
+                        condition: check.condition,
+                        reading: check.reading,
+                        holds: check.holds,
+                    })
+                    .collect(),
+                actions: dry_run.actions.as_ref().map(describe).unwrap_or_default(),
+                readings_at: dry_run.readings_at,
+            })),
+            Err(err) => Err(into_status(err)),
+        }
+    }
+}
@@ -2,9 +2,11 @@ use anyhow::Result;
 pub use mecha_battery_ctrl::{Battery, PowerSupplyInfo};
 use tonic::{Request, Response, Status};
 
+use super::{into_status, Device};
+
 #[derive(Default)]
-pub struct PowerSupply {
-    pub power_supply: Battery,
+pub struct PowerSupply<P = Battery> {
+    pub power_supply: Device<P>,
 }
 
 pub mod power_supply {
@@ -17,14 +19,21 @@ pub use power_supply::{
 };
 
 #[tonic::async_trait]
-impl PowerSupplyService for PowerSupply {
+impl<P> PowerSupplyService for PowerSupply<P>
+where
+    P: PowerSupplyInfo + Send + Sync + 'static,
+{
     async fn get_power_supply_info(
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<GetPowerSupplyInfoResponse>, Status> {
-        let power_supply_info = match self.power_supply.info() {
+        let power_supply_info = match self
+            .power_supply
+            .run("get_power_supply_info", |power_supply| power_supply.info())
+            .await
+        {
             Ok(info) => info,
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
         let respose = GetPowerSupplyInfoResponse {
@@ -64,13 +73,17 @@ impl PowerSupplyService for PowerSupply {
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<GetDeviceResponse>, Status> {
-        let device = match self.power_supply.get_device() {
+        let device = match self
+            .power_supply
+            .run("get_device", |power_supply| power_supply.get_device())
+            .await
+        {
             Ok(device) => device,
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
         let response = GetDeviceResponse {
-            device_path: device.to_string(),
+            device_path: device,
         };
 
         Ok(Response::new(response))
@@ -80,9 +93,13 @@ impl PowerSupplyService for PowerSupply {
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<GetCurrentResponse>, Status> {
-        let current = match self.power_supply.get_current() {
+        let current = match self
+            .power_supply
+            .run("get_current", |power_supply| power_supply.get_current())
+            .await
+        {
             Ok(current) => current,
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
         let response = GetCurrentResponse {
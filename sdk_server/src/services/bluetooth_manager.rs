@@ -1,9 +1,33 @@
 use anyhow::Result;
-use mecha_bluetooth_manager::BluetoothController;
+pub use mecha_bluetooth_manager::{BluetoothControl, BluetoothController};
 use tonic::{Request, Response, Status};
 
+use super::into_status;
+
+#[derive(Debug, Default)]
+pub struct Bluetooth<B = OnDemandBluetooth> {
+    pub bluetooth: B,
+}
+
+// opens a new bluez session for every call, so the server still starts on
+// boards where bluetoothd is not running
 #[derive(Debug, Default)]
-pub struct Bluetooth {}
+pub struct OnDemandBluetooth;
+
+#[tonic::async_trait]
+impl BluetoothControl for OnDemandBluetooth {
+    async fn bluetooth_status(&self) -> Result<bool> {
+        BluetoothController::new().await?.bluetooth_status().await
+    }
+
+    async fn enable_bluetooth(&self) -> Result<()> {
+        BluetoothController::new().await?.enable_bluetooth().await
+    }
+
+    async fn disable_bluetooth(&self) -> Result<()> {
+        BluetoothController::new().await?.disable_bluetooth().await
+    }
+}
 
 #[allow(non_snake_case)]
 pub mod bluetooth {
@@ -11,28 +35,24 @@ pub mod bluetooth {
 }
 
 pub use bluetooth::{
-    bluetooth_service_server::{BluetoothService,BluetoothServiceServer}, BluetoothStatus, Empty, EmptyResponse,
+    bluetooth_service_server::{BluetoothService, BluetoothServiceServer},
+    BluetoothStatus, Empty, EmptyResponse,
 };
 
 #[tonic::async_trait]
-impl BluetoothService for Bluetooth {
+impl<B> BluetoothService for Bluetooth<B>
+where
+    B: BluetoothControl + Send + Sync + 'static,
+{
     async fn get_bluetooth_status(
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<BluetoothStatus>, Status> {
-        //try to crearte a new bluetooth controller or return an error using match
-        let controller = match BluetoothController::new().await {
-            Ok(controller) => controller,
-            Err(e) => {
-                return Err(Status::from_error(e.into()));
-            }
-        };
-
         //try to get the bluetooth status or return an error using match
-        let status = match controller.bluetooth_status().await {
+        let status = match self.bluetooth.bluetooth_status().await {
             Ok(status) => status,
             Err(e) => {
-                return Err(Status::from_error(e.into()));
+                return Err(into_status(e));
             }
         };
 
@@ -44,17 +64,10 @@ impl BluetoothService for Bluetooth {
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<EmptyResponse>, Status> {
-        let controller = match BluetoothController::new().await {
-            Ok(controller) => controller,
-            Err(e) => {
-                return Err(Status::from_error(e.into()));
-            }
-        };
-
-        match controller.enable_bluetooth().await {
+        match self.bluetooth.enable_bluetooth().await {
             Ok(_) => {}
             Err(e) => {
-                return Err(Status::from_error(e.into()));
+                return Err(into_status(e));
             }
         };
 
@@ -65,17 +78,10 @@ impl BluetoothService for Bluetooth {
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<EmptyResponse>, Status> {
-        let controller = match BluetoothController::new().await {
-            Ok(controller) => controller,
-            Err(e) => {
-                return Err(Status::from_error(e.into()));
-            }
-        };
-
-        match controller.disable_bluetooth().await {
+        match self.bluetooth.disable_bluetooth().await {
             Ok(_) => {}
             Err(e) => {
-                return Err(Status::from_error(e.into()));
+                return Err(into_status(e));
             }
         };
 
@@ -0,0 +1,172 @@
+use std::fs::read_to_string;
+use std::path::Path;
+use tonic::{Request, Response, Status};
+
+use super::{into_status, Device};
+use crate::configs::Interfaces;
+use crate::health::HardwareProbe;
+use crate::reload::Swappable;
+
+#[allow(non_snake_case)]
+pub mod capabilities {
+    tonic::include_proto!("capabilities");
+}
+
+pub use capabilities::{
+    device_capabilities_service_server::{
+        DeviceCapabilitiesService, DeviceCapabilitiesServiceServer,
+    },
+    AdcCapabilities, BatteryCapabilities, CameraCapabilities, Capabilities, CpuCapabilities,
+    DisplayCapabilities, GetCapabilitiesRequest, LedCapabilities, MotionSensorCapabilities,
+};
+
+// version of the api in the proto files and the rest paths
+pub const PROTO_VERSION: &str = "v1";
+
+// describes the board profile and the hardware behind it. the configured
+// interfaces are probed on every call, so a reload that moves a device path
+// is reported right away
+#[derive(Debug, Clone)]
+pub struct CapabilitiesManager {
+    pub board: String,
+    pub interfaces: Device<Swappable<Interfaces>>,
+    // probes of the registered services, see `ServiceGate`
+    pub services: Swappable<Vec<HardwareProbe>>,
+}
+
+#[tonic::async_trait]
+impl DeviceCapabilitiesService for CapabilitiesManager {
+    async fn get_capabilities(
+        &self,
+        _request: Request<GetCapabilitiesRequest>,
+    ) -> Result<Response<Capabilities>, Status> {
+        let hardware = match self
+            .interfaces
+            .run("get_capabilities", |interfaces| {
+                Ok(probe_hardware(&interfaces.get()))
+            })
+            .await
+        {
+            Ok(hardware) => hardware,
+            Err(err) => return Err(into_status(err)),
+        };
+
+        Ok(Response::new(Capabilities {
+            board: self.board.clone(),
+            sdk_version: env!("CARGO_PKG_VERSION").to_string(),
+            proto_version: PROTO_VERSION.to_string(),
+            services: self
+                .services
+                .get()
+                .iter()
+                .map(|probe| probe.service.to_string())
+                .collect(),
+            ..hardware
+        }))
+    }
+}
+
+// what each configured interface offers, missing devices are left out
+// instead of failing the call
+pub fn probe_hardware(interfaces: &Interfaces) -> Capabilities {
+    let display = &interfaces.display.device;
+    let led = &interfaces.led;
+    let motion_sensor = &interfaces.motion_sensor;
+    let axes = present(&[
+        ("x", &motion_sensor.x_axis),
+        ("y", &motion_sensor.y_axis),
+        ("z", &motion_sensor.z_axis),
+    ]);
+    let cpu = Path::new(&interfaces.cpu.device);
+    let adc = &interfaces.adc;
+    let channels = present(&[("channel_1", &adc.channel_1), ("channel_2", &adc.channel_2)]);
+
+    Capabilities {
+        display: Some(DisplayCapabilities {
+            available: exists(display),
+            max_brightness: sibling(display, "max_brightness")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+        }),
+        led: Some(LedCapabilities {
+            colors: present(&[
+                ("red", &led.red_led),
+                ("green", &led.green_led),
+                ("blue", &led.blue_led),
+            ]),
+        }),
+        motion_sensor: Some(MotionSensorCapabilities {
+            available: axes.len() == 3,
+            axes,
+            name: sibling(&motion_sensor.x_axis, "name").unwrap_or_default(),
+        }),
+        cpu: Some(CpuCapabilities {
+            available: cpu.is_dir(),
+            governors: read(&cpu.join("scaling_available_governors"))
+                .map(|governors| governors.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+            frequencies: read(&cpu.join("scaling_available_frequencies"))
+                .map(|frequencies| {
+                    frequencies
+                        .split_whitespace()
+                        .filter_map(|frequency| frequency.parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            min_frequency: read(&cpu.join("cpuinfo_min_freq"))
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+            max_frequency: read(&cpu.join("cpuinfo_max_freq"))
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+        }),
+        battery: Some(BatteryCapabilities {
+            available: exists(&interfaces.battery.device),
+            technology: read(Path::new(&interfaces.battery.device))
+                .and_then(|uevent| {
+                    uevent.lines().find_map(|line| {
+                        line.strip_prefix("POWER_SUPPLY_TECHNOLOGY=")
+                            .map(String::from)
+                    })
+                })
+                .unwrap_or_default(),
+        }),
+        adc: Some(AdcCapabilities {
+            name: sibling(&adc.channel_1, "name")
+                .or_else(|| sibling(&adc.channel_2, "name"))
+                .unwrap_or_default(),
+            channels,
+        }),
+        camera: Some(CameraCapabilities {
+            available: exists(&interfaces.camera.device),
+        }),
+        ..Default::default()
+    }
+}
+
+fn exists(path: &str) -> bool {
+    !path.is_empty() && Path::new(path).exists()
+}
+
+// names of the devices that exist
+fn present(devices: &[(&str, &String)]) -> Vec<String> {
+    devices
+        .iter()
+        .filter(|(_, path)| exists(path))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+fn read(path: &Path) -> Option<String> {
+    read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+// an attribute in the sysfs directory of `path`
+fn sibling(path: &str, name: &str) -> Option<String> {
+    if path.is_empty() {
+        return None;
+    }
+    read(&Path::new(path).parent()?.join(name))
+}
@@ -1,10 +1,13 @@
 use anyhow::Result;
-pub use mecha_cpu_governor_ctrl::{CpuCtrl, CpuFrequency};
+pub use mecha_cpu_governor_ctrl::{CpuControl, CpuCtrl, CpuFrequency};
 use tonic::{Request, Response, Status};
 
+use super::{into_status, Device};
+use crate::settings::USERSPACE_GOVERNOR;
+
 #[derive(Debug)]
-pub struct CpuCtrlService {
-    pub cpu_ctrl_manager: CpuCtrl,
+pub struct CpuCtrlService<C = CpuCtrl> {
+    pub cpu_ctrl_manager: Device<C>,
 }
 
 #[allow(non_snake_case)]
@@ -18,14 +21,21 @@ pub use cpu_governor_ctrl::{
 };
 
 #[tonic::async_trait]
-impl CpuGovernorCtrlService for CpuCtrlService {
+impl<C> CpuGovernorCtrlService for CpuCtrlService<C>
+where
+    C: CpuControl + Send + Sync + 'static,
+{
     async fn get_governor(
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<GovernorResponse>, Status> {
-        let governor = match self.cpu_ctrl_manager.get_cpu_governor() {
+        let governor = match self
+            .cpu_ctrl_manager
+            .run("get_governor", |cpu| cpu.get_cpu_governor())
+            .await
+        {
             Ok(governor) => GovernorResponse { result: governor },
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
         Ok(Response::new(governor))
@@ -35,10 +45,18 @@ impl CpuGovernorCtrlService for CpuCtrlService {
         &self,
         request: Request<GovernorRequest>,
     ) -> Result<Response<Empty>, Status> {
-        let _governor = request.into_inner().governor.to_string();
-        match self.cpu_ctrl_manager.set_cpu_governor() {
+        // an empty governor keeps selecting userspace, as it always did
+        let governor = match request.into_inner().governor {
+            governor if governor.is_empty() => USERSPACE_GOVERNOR.to_string(),
+            governor => governor,
+        };
+        match self
+            .cpu_ctrl_manager
+            .run("set_governor", move |cpu| cpu.set_cpu_governor(&governor))
+            .await
+        {
             Ok(_) => Ok(Response::new(Empty {})),
-            Err(err) => Err(Status::from_error(err.into())),
+            Err(err) => Err(into_status(err)),
         }
     }
 
@@ -46,11 +64,15 @@ impl CpuGovernorCtrlService for CpuCtrlService {
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<CpuFrequencyResponse>, Status> {
-        let cpu_frequency = match self.cpu_ctrl_manager.get_cpu_frequency() {
+        let cpu_frequency = match self
+            .cpu_ctrl_manager
+            .run("get_cpu_frequency", |cpu| cpu.get_cpu_frequency())
+            .await
+        {
             Ok(cpu_frequency) => CpuFrequencyResponse {
                 result: cpu_frequency,
             },
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
         Ok(Response::new(cpu_frequency))
@@ -72,9 +94,15 @@ impl CpuGovernorCtrlService for CpuCtrlService {
             }
         };
 
-        match self.cpu_ctrl_manager.set_cpu_frequency(cpu_frequency) {
+        match self
+            .cpu_ctrl_manager
+            .run("set_cpu_frequency", move |cpu| {
+                cpu.set_cpu_frequency(cpu_frequency)
+            })
+            .await
+        {
             Ok(_) => Ok(Response::new(Empty {})),
-            Err(err) => Err(Status::from_error(err.into())),
+            Err(err) => Err(into_status(err)),
         }
     }
 }
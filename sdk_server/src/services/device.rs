@@ -0,0 +1,126 @@
+use anyhow::Result;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tonic::Status;
+use tracing::{info_span, warn};
+
+pub const DEFAULT_DEVICE_WORKERS: usize = 4;
+pub const DEFAULT_DEVICE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// threads of tokio's blocking pool the device calls may occupy at once, and
+// how long a call may take, waiting for its device included
+#[derive(Debug, Clone)]
+pub struct BlockingPool {
+    permits: Arc<Semaphore>,
+    timeout: Duration,
+}
+
+impl BlockingPool {
+    pub fn new(workers: usize, timeout: Duration) -> Self {
+        BlockingPool {
+            permits: Arc::new(Semaphore::new(workers.max(1))),
+            timeout,
+        }
+    }
+}
+
+impl Default for BlockingPool {
+    fn default() -> Self {
+        BlockingPool::new(DEFAULT_DEVICE_WORKERS, DEFAULT_DEVICE_TIMEOUT)
+    }
+}
+
+// a controller doing blocking sysfs or subprocess work. calls run off the
+// async workers one at a time, so a slow device only delays its own callers
+pub struct Device<T> {
+    inner: Arc<T>,
+    lock: Arc<Mutex<()>>,
+    pool: BlockingPool,
+}
+
+impl<T> Device<T> {
+    pub fn new(inner: T, pool: &BlockingPool) -> Self {
+        Device {
+            inner: Arc::new(inner),
+            lock: Arc::new(Mutex::new(())),
+            pool: pool.clone(),
+        }
+    }
+
+    pub fn get(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> Clone for Device<T> {
+    fn clone(&self) -> Self {
+        Device {
+            inner: self.inner.clone(),
+            lock: self.lock.clone(),
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Device<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Device").field(&self.inner).finish()
+    }
+}
+
+impl<T: Default> Default for Device<T> {
+    fn default() -> Self {
+        Device::from(T::default())
+    }
+}
+
+impl<T> From<T> for Device<T> {
+    fn from(inner: T) -> Self {
+        Device::new(inner, &BlockingPool::default())
+    }
+}
+
+impl<T: Send + Sync + 'static> Device<T> {
+    // run `call` on the blocking pool. a call that runs out of time fails
+    // with DEADLINE_EXCEEDED but keeps the device until it returns, so the
+    // hardware never sees two calls at once
+    pub async fn run<R, F>(&self, task: &'static str, call: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&T) -> Result<R> + Send + 'static,
+    {
+        let work = async {
+            let device = self.lock.clone().lock_owned().await;
+            let permit = self
+                .pool
+                .permits
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("device pool is never closed");
+            let inner = self.inner.clone();
+            // the blocking thread does not inherit the rpc span
+            let span = info_span!("device", task = task);
+            tokio::task::spawn_blocking(move || {
+                let _held = (device, permit);
+                let _entered = span.enter();
+                call(&inner)
+            })
+            .await
+        };
+        match tokio::time::timeout(self.pool.timeout, work).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => Err(Status::internal(format!("{} failed: {}", task, e)).into()),
+            Err(_) => {
+                warn!(task = task, "no answer within {:?}", self.pool.timeout);
+                Err(Status::deadline_exceeded(format!(
+                    "{} did not finish within {:?}",
+                    task, self.pool.timeout
+                ))
+                .into())
+            }
+        }
+    }
+}
@@ -2,6 +2,8 @@ use anyhow::Result;
 use mecha_device_info::DeviceInfo;
 use tonic::{Request, Response, Status};
 
+use super::into_status;
+
 #[derive(Debug, Default)]
 pub struct DeviceInfoCtrl {
     device_info: DeviceInfo,
@@ -25,7 +27,7 @@ impl DeviceInfoService for DeviceInfoCtrl {
     ) -> Result<Response<CpuInfoResponse>, Status> {
         let deviceinfo = match self.device_info.get_cpu_info() {
             Ok(cpu_info) => cpu_info,
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
         let response = CpuInfoResponse {
@@ -46,7 +48,7 @@ impl DeviceInfoService for DeviceInfoCtrl {
     ) -> Result<Response<DiskInfoResponse>, Status> {
         let disk_info = match self.device_info.get_disk_info() {
             Ok(disk_info) => disk_info,
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
         let response = DiskInfoResponse {
@@ -78,7 +80,7 @@ impl DeviceInfoService for DeviceInfoCtrl {
                     available_memory: memory_info.available_memory,
                 }),
             })),
-            Err(err) => Err(Status::from_error(err.into())),
+            Err(err) => Err(into_status(err)),
         }
     }
 }
@@ -1,10 +1,12 @@
 use anyhow::Result;
 use tonic::{Request, Response, Status};
 
-pub use mecha_display_ctrl::DisplayCtrl;
+use super::{into_status, Device};
 
-pub struct DisplayCtrlManager {
-    pub display_ctrl: DisplayCtrl,
+pub use mecha_display_ctrl::{DisplayControl, DisplayCtrl};
+
+pub struct DisplayCtrlManager<D = DisplayCtrl> {
+    pub display_ctrl: Device<D>,
 }
 
 #[allow(non_snake_case)]
@@ -18,18 +20,27 @@ pub use displaymanager::{
 };
 
 #[tonic::async_trait]
-impl DisplayCtrlService for DisplayCtrlManager {
+impl<D> DisplayCtrlService for DisplayCtrlManager<D>
+where
+    D: DisplayControl + Send + Sync + 'static,
+{
     async fn set_brightness(
         &self,
         request: Request<SetBrightnessRequest>,
     ) -> Result<Response<SetBrightnessResponse>, Status> {
         let brightness = request.into_inner().brightness;
 
-        match self.display_ctrl.set_display_brightness(brightness as u8) {
+        match self
+            .display_ctrl
+            .run("set_brightness", move |display| {
+                display.set_display_brightness(brightness as u8)
+            })
+            .await
+        {
             Ok(_) => Ok(Response::new(SetBrightnessResponse {})), // Return a successful response.
             Err(err) => {
                 // Convert the error into a gRPC status and return it.
-                Err(Status::from_error(err.into()))
+                Err(into_status(err))
             }
         }
     }
@@ -38,14 +49,18 @@ impl DisplayCtrlService for DisplayCtrlManager {
         &self,
         _request: Request<GetBrightnessRequest>,
     ) -> Result<Response<GetBrightnessResponse>, Status> {
-        match self.display_ctrl.get_display_brightness() {
+        match self
+            .display_ctrl
+            .run("get_brightness", |display| display.get_display_brightness())
+            .await
+        {
             Ok(brightness) => {
                 // Construct a successful response with the brightness value.
                 Ok(Response::new(GetBrightnessResponse {
                     brightness: brightness.into(),
                 }))
             }
-            Err(err) => Err(Status::from_error(err.into())),
+            Err(err) => Err(into_status(err)),
         }
     }
 }
@@ -0,0 +1,84 @@
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::{trace, warn};
+
+use crate::events::{EventBus, TOPICS};
+use crate::shutdown::Shutdown;
+
+#[allow(non_snake_case)]
+pub mod events {
+    tonic::include_proto!("events");
+}
+
+pub use events::{
+    event::Payload,
+    event_service_server::{EventService, EventServiceServer},
+    wifi_state_changed::State as WifiState,
+    BatteryStatusChanged, BluetoothPowerChanged, BrightnessChanged, Event, MotionDetected,
+    SubscribeRequest, WifiStateChanged,
+};
+
+// events buffered per subscriber before the stream applies backpressure
+const SUBSCRIBER_BUFFER: usize = 16;
+
+// streams the bus to every subscriber until it disconnects or the server
+// shuts down
+#[derive(Debug, Clone, Default)]
+pub struct EventManager {
+    pub bus: EventBus,
+    pub shutdown: Shutdown,
+}
+
+#[tonic::async_trait]
+impl EventService for EventManager {
+    type SubscribeStream = ReceiverStream<Result<Event, Status>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let topics = request.into_inner().topics;
+        if let Some(unknown) = topics
+            .iter()
+            .find(|topic| !TOPICS.contains(&topic.as_str()))
+        {
+            return Err(Status::invalid_argument(format!(
+                "unknown topic {}, expected one of {}",
+                unknown,
+                TOPICS.join(", ")
+            )));
+        }
+
+        let mut events = self.bus.subscribe();
+        let shutdown = self.shutdown.triggered();
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_BUFFER);
+        tokio::spawn(async move {
+            trace!(task = "subscribe", "subscribed to {:?}", topics);
+            tokio::pin!(shutdown);
+            loop {
+                let event = tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = sender.closed() => break,
+                    event = events.recv() => event,
+                };
+                match event {
+                    Ok(event) if topics.is_empty() || topics.contains(&event.topic) => {
+                        if sender.send(Ok(event)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    // a slow subscriber misses events instead of holding up the bus
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!(task = "subscribe", "subscriber missed {} events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            trace!(task = "subscribe", "subscription closed");
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
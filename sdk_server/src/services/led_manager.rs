@@ -1,6 +1,8 @@
 use tonic::{Request, Response, Status};
 
-pub use mecha_led_ctrl::{LedColor, LedCtrl};
+use super::{into_status, Device};
+
+pub use mecha_led_ctrl::{LedColor, LedControl, LedCtrl};
 
 #[allow(non_snake_case)]
 pub mod ledmanager {
@@ -12,12 +14,15 @@ pub use ledmanager::{
     Empty, LedColor as LedColorProto,
 };
 
-pub struct LedCtrlManager {
-    pub led_ctrl: LedCtrl,
+pub struct LedCtrlManager<L = LedCtrl> {
+    pub led_ctrl: Device<L>,
 }
 
 #[tonic::async_trait]
-impl LedCtrlService for LedCtrlManager {
+impl<L> LedCtrlService for LedCtrlManager<L>
+where
+    L: LedControl + Send + Sync + 'static,
+{
     async fn set_led(&self, request: Request<LedColorProto>) -> Result<Response<Empty>, Status> {
         let color = request.into_inner().color;
         // Match the color and convert it to the corresponding LedColor variant.
@@ -31,9 +36,13 @@ impl LedCtrlService for LedCtrlManager {
             }
         };
 
-        match self.led_ctrl.set_led(selected_led) {
+        match self
+            .led_ctrl
+            .run("set_led", move |led| led.set_led(selected_led))
+            .await
+        {
             Ok(_) => Ok(Response::new(Empty {})),
-            Err(err) => Err(Status::from_error(err.into())),
+            Err(err) => Err(into_status(err)),
         }
     }
 
@@ -52,9 +61,13 @@ impl LedCtrlService for LedCtrlManager {
         };
 
         // Return an empty response if the LED was cleared successfully or else return error
-        match self.led_ctrl.clear_led(selected_led) {
+        match self
+            .led_ctrl
+            .run("clear_led", move |led| led.clear_led(selected_led))
+            .await
+        {
             Ok(_) => Ok(Response::new(Empty {})),
-            Err(err) => Err(Status::from_error(err.into())),
+            Err(err) => Err(into_status(err)),
         }
     }
 }
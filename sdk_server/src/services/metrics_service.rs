@@ -1,10 +1,12 @@
 use anyhow::Result;
-pub use mecha_metrics::DeviceMetrics;
+pub use mecha_metrics::{DeviceMetrics, DeviceMetricsInfo};
 use tonic::{Request, Response, Status};
 
+use super::into_status;
+
 #[derive(Debug, Default)]
-pub struct DeviceMetricsService {
-    pub metrics: DeviceMetrics,
+pub struct DeviceMetricsService<M = DeviceMetrics> {
+    pub metrics: M,
 }
 
 #[allow(non_snake_case)]
@@ -18,19 +20,20 @@ pub use metrics::{
 };
 
 #[tonic::async_trait]
-impl MetricsService for DeviceMetricsService {
+impl<M> MetricsService for DeviceMetricsService<M>
+where
+    M: DeviceMetricsInfo + Send + Sync + 'static,
+{
     async fn get_cpu_usage(
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<GetCpuUsageResponse>, Status> {
         let cpu_usage = match self.metrics.get_cpu_usage() {
             Ok(cpu_usage) => cpu_usage,
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
-        let response = GetCpuUsageResponse {
-            cpu_usage: cpu_usage,
-        };
+        let response = GetCpuUsageResponse { cpu_usage };
 
         Ok(Response::new(response))
     }
@@ -41,12 +44,10 @@ impl MetricsService for DeviceMetricsService {
     ) -> Result<Response<GetMemoryUsageResponse>, Status> {
         let memory_usage = match self.metrics.get_memory_usage() {
             Ok(memory_usage) => memory_usage,
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
-        let response = GetMemoryUsageResponse {
-            memory_usage: memory_usage,
-        };
+        let response = GetMemoryUsageResponse { memory_usage };
 
         Ok(Response::new(response))
     }
@@ -57,12 +58,10 @@ impl MetricsService for DeviceMetricsService {
     ) -> Result<Response<GetDiskUsageResponse>, Status> {
         let disk_usage = match self.metrics.get_disk_usage() {
             Ok(disk_usage) => disk_usage,
-            Err(err) => return Err(Status::from_error(err.into())),
+            Err(err) => return Err(into_status(err)),
         };
 
-        let response = GetDiskUsageResponse {
-            disk_usage: disk_usage,
-        };
+        let response = GetDiskUsageResponse { disk_usage };
 
         Ok(Response::new(response))
     }
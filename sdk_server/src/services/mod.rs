@@ -1,31 +1,68 @@
-mod network_manager_service;
-pub use network_manager_service::{NetworkManager, NetworkManagerServiceServer};
+pub mod network_manager_service;
+pub use network_manager_service::{
+    NetworkManager, NetworkManagerServiceServer, WifiControl, WifiModule,
+};
 
-mod display_manager_service;
-pub use display_manager_service::{DisplayCtrl, DisplayCtrlManager, DisplayCtrlServiceServer};
+pub mod display_manager_service;
+pub use display_manager_service::{
+    DisplayControl, DisplayCtrl, DisplayCtrlManager, DisplayCtrlServiceServer,
+};
 
-mod motion_sensor_service;
-pub use motion_sensor_service::{MotionSensor, MotionSensorManager, MotionSensorServiceServer};
+pub mod motion_sensor_service;
+pub use motion_sensor_service::{
+    MotionSensor, MotionSensorControl, MotionSensorManager, MotionSensorServiceServer,
+};
 
-mod led_manager;
-pub use led_manager::{LedCtrl, LedCtrlManager, LedCtrlServiceServer};
+pub mod led_manager;
+pub use led_manager::{LedColor, LedControl, LedCtrl, LedCtrlManager, LedCtrlServiceServer};
 
-mod device_info_service;
+pub mod device_info_service;
 pub use device_info_service::{DeviceInfoCtrl, DeviceInfoServiceServer};
 
-mod metrics_service;
-pub use metrics_service::{DeviceMetrics, DeviceMetricsService, MetricsServiceServer};
+pub mod metrics_service;
+pub use metrics_service::{
+    DeviceMetrics, DeviceMetricsInfo, DeviceMetricsService, MetricsServiceServer,
+};
 
-mod cpu_ctrl_service;
-pub use cpu_ctrl_service::{CpuCtrl, CpuCtrlService, CpuGovernorCtrlServiceServer};
+pub mod cpu_ctrl_service;
+pub use cpu_ctrl_service::{
+    CpuControl, CpuCtrl, CpuCtrlService, CpuFrequency, CpuGovernorCtrlServiceServer,
+};
 
-mod trustzone_ctrl_service;
+pub mod trustzone_ctrl_service;
 pub use trustzone_ctrl_service::{
-    TrustZoneCtrl, TrustZoneCtrlService, TrustZoneCtrlServiceManager, TrustZoneCtrlServiceServer,
+    KeySize, KeyType, TrustZoneControl, TrustZoneCtrl, TrustZoneCtrlService,
+    TrustZoneCtrlServiceManager, TrustZoneCtrlServiceServer,
+};
+
+pub mod battery_ctrl_service;
+pub use battery_ctrl_service::{Battery, PowerSupply, PowerSupplyInfo, PowerSupplyServiceServer};
+
+pub mod bluetooth_manager;
+pub use bluetooth_manager::{
+    Bluetooth, BluetoothControl, BluetoothController, BluetoothServiceServer, OnDemandBluetooth,
 };
 
-mod battery_ctrl_service;
-pub use battery_ctrl_service::{PowerSupply, PowerSupplyServiceServer,Battery};
+pub mod event_service;
+pub use event_service::{EventManager, EventServiceServer};
+
+pub mod audit_service;
+pub use audit_service::{AuditManager, AuditServiceServer};
+
+pub mod capabilities_service;
+pub use capabilities_service::{CapabilitiesManager, DeviceCapabilitiesServiceServer};
+
+pub mod settings_service;
+pub use settings_service::{SettingsManager, SettingsServiceServer};
+
+pub mod automation_service;
+pub use automation_service::{AutomationManager, AutomationServiceServer};
+
+mod status;
+pub use status::into_status;
+
+mod device;
+pub use device::{BlockingPool, Device, DEFAULT_DEVICE_TIMEOUT, DEFAULT_DEVICE_WORKERS};
 
-mod bluetooth_manager;
-pub use bluetooth_manager::{Bluetooth, BluetoothServiceServer};
+// encoded descriptors of every package above, registered with grpc reflection
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("mecha_descriptor");
@@ -1,10 +1,12 @@
 use tonic::{Request, Response, Status};
 
-pub use mecha_motion_sensor::MotionSensor;
+use super::{into_status, Device};
+
+pub use mecha_motion_sensor::{MotionSensor, MotionSensorControl};
 
 #[derive(Default)]
-pub struct MotionSensorManager {
-    pub motion_sensor: MotionSensor,
+pub struct MotionSensorManager<M = MotionSensor> {
+    pub motion_sensor: Device<M>,
 }
 
 #[allow(non_snake_case)]
@@ -18,12 +20,19 @@ pub use motionsensor::{
 };
 
 #[tonic::async_trait]
-impl MotionSensorService for MotionSensorManager {
+impl<M> MotionSensorService for MotionSensorManager<M>
+where
+    M: MotionSensorControl + Send + Sync + 'static,
+{
     async fn read_value(
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<ReadValueResponse>, Status> {
-        match self.motion_sensor.read_motion_sensor_value() {
+        match self
+            .motion_sensor
+            .run("read_value", |sensor| sensor.read_motion_sensor_value())
+            .await
+        {
             Ok((x_value, y_value, z_value)) => {
                 // Construct a successful response with the motion sensor values.
                 Ok(Response::new(ReadValueResponse {
@@ -32,7 +41,7 @@ impl MotionSensorService for MotionSensorManager {
                     z_value,
                 }))
             }
-            Err(err) => Err(Status::from_error(err.into())),
+            Err(err) => Err(into_status(err)),
         }
     }
 
@@ -40,9 +49,15 @@ impl MotionSensorService for MotionSensorManager {
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<DetectEventResponse>, Status> {
-        match self.motion_sensor.detect_motion_sensor_event() {
+        match self
+            .motion_sensor
+            .run("detect_motion", |sensor| {
+                sensor.detect_motion_sensor_event()
+            })
+            .await
+        {
             Ok(is_motion_detected) => Ok(Response::new(DetectEventResponse { is_motion_detected })),
-            Err(err) => Err(Status::from_error(err.into())),
+            Err(err) => Err(into_status(err)),
         }
     }
 }
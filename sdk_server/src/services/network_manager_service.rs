@@ -1,8 +1,13 @@
-use mecha_network_manager::wifi::WifiModule;
+pub use mecha_network_manager::wifi::{WifiControl, WifiModule};
 use tonic::{Request, Response, Status};
+use tracing::info;
+
+use super::into_status;
 
 #[derive(Default)]
-pub struct NetworkManager {}
+pub struct NetworkManager<W = WifiModule> {
+    pub wifi: W,
+}
 
 const NETWORK_CONNECT_SUCCESS_MESSAGE: &str = "WiFi connection successful";
 const NETWORK_CONNECT_FAILURE_MESSAGE: &str = "WiFi connection failed";
@@ -47,7 +52,7 @@ impl ResponseMessage for RemoveNetworkResponse {
     }
 }
 
-impl NetworkManager {
+impl<W: WifiControl> NetworkManager<W> {
     fn handle_response<T: ResponseMessage>(
         &self,
         result: Result<(), &str>,
@@ -68,7 +73,7 @@ impl NetworkManager {
     }
 
     async fn connect_to_wifi(&self, ssid: &str, psk: &str) -> Result<(), &str> {
-        let connect_wifi = WifiModule::connect_wireless_network(ssid, psk).await;
+        let connect_wifi = self.wifi.connect_wireless_network(ssid, psk).await;
 
         match connect_wifi {
             Ok(_) => Ok(()),
@@ -77,7 +82,7 @@ impl NetworkManager {
     }
 
     async fn remove_wifi_network(&self, network_id: usize) -> Result<(), &str> {
-        let remove_network = WifiModule::remove_wireless_network(network_id).await;
+        let remove_network = self.wifi.remove_wireless_network(network_id).await;
 
         match remove_network {
             Ok(_) => Ok(()),
@@ -87,7 +92,10 @@ impl NetworkManager {
 }
 
 #[tonic::async_trait]
-impl NetworkManagerService for NetworkManager {
+impl<W> NetworkManagerService for NetworkManager<W>
+where
+    W: WifiControl + Send + Sync + 'static,
+{
     async fn scan_wireless_network(
         &self,
         _request: Request<Empty>,
@@ -95,16 +103,15 @@ impl NetworkManagerService for NetworkManager {
         // Implement your async get_wifi logic here
         let mut scan_results = ScanResults::default();
 
-        log::info!("Starting All Wifi List Function");
-        let wifi_service = WifiModule::new();
+        info!(task = "scan_wireless_network", "scanning for networks");
 
         //get wifi list from mecha_edge_sdk
         // Attempt to get the wifi list from mecha_edge_sdk and handle errors.
-        let wifi_list = match wifi_service.scan_wireless_network().await {
+        let wifi_list = match self.wifi.scan_wireless_network().await {
             Ok(wifi_list) => wifi_list,
             Err(err) => {
                 // Convert the error into a gRPC Status and return it.
-                return Err(Status::from_error(err.into()));
+                return Err(into_status(err));
             }
         };
         //add wifi list to scan_results
@@ -164,14 +171,17 @@ impl NetworkManagerService for NetworkManager {
     ) -> Result<Response<NetworkResults>, Status> {
         // Implement your async get_known_wifi logic here
         let mut scan_results = NetworkResults::default();
-        log::info!("Starting Known Wifi List Function");
+        info!(
+            task = "scan_known_wireless_network",
+            "listing known networks"
+        );
 
         //get wifi list from mecha_edge_sdk
-        let wifi_list = match WifiModule::get_known_wifi_list().await {
+        let wifi_list = match self.wifi.get_known_wifi_list().await {
             Ok(wifi_list) => wifi_list,
             Err(err) => {
                 // Convert the error into a gRPC Status and return it.
-                return Err(Status::from_error(err.into()));
+                return Err(into_status(err));
             }
         };
         //add wifi list to scan_results
@@ -192,7 +202,7 @@ impl NetworkManagerService for NetworkManager {
         _request: Request<Empty>,
     ) -> Result<Response<WifiStatusResponse>, Status> {
         // Implement your logic to check Wi-Fi status here
-        let wifi_on = WifiModule::wifi_status(); // This should return true if Wi-Fi is on, false otherwise.
+        let wifi_on = self.wifi.wifi_status(); // This should return true if Wi-Fi is on, false otherwise.
 
         let wifi_status_response = WifiStatusResponse { wifi_on };
 
@@ -204,12 +214,11 @@ impl NetworkManagerService for NetworkManager {
         _request: Request<Empty>,
     ) -> Result<Response<ScanResult>, Status> {
         // Implement your logic to get current Wi-Fi network here
-        let wifi_service = WifiModule::new();
-        let current_network = match wifi_service.current_wifi_network().await {
+        let current_network = match self.wifi.current_wifi_network().await {
             Ok(current_network) => current_network,
             Err(err) => {
                 // Convert the error into a gRPC Status and return it.
-                return Err(Status::from_error(err.into()));
+                return Err(into_status(err));
             }
         };
 
@@ -220,8 +229,7 @@ impl NetworkManagerService for NetworkManager {
             flags: current_network.flags,
             name: current_network.name,
         };
- 
+
         Ok(Response::new(scan_result))
     }
 }
-
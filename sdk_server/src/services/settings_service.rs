@@ -0,0 +1,54 @@
+use tonic::{Request, Response, Status};
+
+use super::{into_status, Device};
+use crate::settings::SettingsStore;
+
+#[allow(non_snake_case)]
+pub mod settings {
+    tonic::include_proto!("settings");
+}
+
+pub use settings::{
+    settings_service_server::{SettingsService, SettingsServiceServer},
+    GetSettingsRequest, GetSettingsResponse, ResetSettingsRequest, ResetSettingsResponse, Setting,
+};
+
+// reads and resets the settings the display, led and cpu services save
+#[derive(Debug, Clone)]
+pub struct SettingsManager {
+    pub store: Device<SettingsStore>,
+}
+
+#[tonic::async_trait]
+impl SettingsService for SettingsManager {
+    async fn get_settings(
+        &self,
+        request: Request<GetSettingsRequest>,
+    ) -> Result<Response<GetSettingsResponse>, Status> {
+        let prefix = request.into_inner().prefix;
+        let settings = self
+            .store
+            .get()
+            .all()
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .map(|(key, value)| Setting { key, value })
+            .collect();
+        Ok(Response::new(GetSettingsResponse { settings }))
+    }
+
+    async fn reset_settings(
+        &self,
+        request: Request<ResetSettingsRequest>,
+    ) -> Result<Response<ResetSettingsResponse>, Status> {
+        let keys = request.into_inner().keys;
+        match self
+            .store
+            .run("reset_settings", move |store| store.reset(&keys))
+            .await
+        {
+            Ok(keys) => Ok(Response::new(ResetSettingsResponse { keys })),
+            Err(err) => Err(into_status(err)),
+        }
+    }
+}
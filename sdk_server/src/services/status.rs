@@ -0,0 +1,67 @@
+use mecha_battery_ctrl::PowerSupplyError;
+use mecha_bluetooth_manager::BluetoothError;
+use mecha_cpu_governor_ctrl::CpuCtrlError;
+use mecha_device_info::DeviceInfoError;
+use mecha_display_ctrl::DisplayError;
+use mecha_errors::{status, DomainError, ErrorKind};
+use mecha_led_ctrl::LedCtrlError;
+use mecha_metrics::DeviceMetricsError;
+use mecha_motion_sensor::MotionSensorError;
+use mecha_network_manager::wifi::WifiError;
+use mecha_trustzone_ctrl::TrustZoneCtrlError;
+use std::io;
+use tonic::Status;
+
+// the first device crate error found in `err`, either bailed directly or
+// attached as context
+fn domain_error(err: &anyhow::Error) -> Option<&dyn DomainError> {
+    macro_rules! downcast {
+        ($($error:ty),*) => {
+            $(
+                if let Some(error) = err.downcast_ref::<$error>() {
+                    return Some(error);
+                }
+            )*
+        };
+    }
+    downcast!(
+        DisplayError,
+        LedCtrlError,
+        MotionSensorError,
+        PowerSupplyError,
+        CpuCtrlError,
+        DeviceInfoError,
+        DeviceMetricsError,
+        WifiError,
+        BluetoothError,
+        TrustZoneCtrlError
+    );
+    None
+}
+
+// convert an error returned by a device crate to a grpc status. device errors
+// carry their code as google.rpc.ErrorInfo, anything else is UNKNOWN. the
+// crates keep the io error they failed on as the source, so a root only
+// sysfs attribute is PERMISSION_DENIED whatever code the crate picked
+pub fn into_status(err: anyhow::Error) -> Status {
+    if let Some(status) = err.downcast_ref::<Status>() {
+        return status.clone();
+    }
+    let denied = err.chain().any(|cause| {
+        cause
+            .downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::PermissionDenied)
+    });
+    let Some(error) = domain_error(&err) else {
+        if denied {
+            return Status::permission_denied(err.to_string());
+        }
+        return Status::unknown(err.to_string());
+    };
+    let kind = if denied {
+        ErrorKind::PermissionDenied
+    } else {
+        error.code().kind()
+    };
+    status(kind, error)
+}
@@ -1,5 +1,7 @@
-pub use mecha_trustzone_ctrl::{KeySize, KeyType, TrustZoneCtrl};
+pub use mecha_trustzone_ctrl::{KeySize, KeyType, TrustZoneControl, TrustZoneCtrl};
 use tonic::{Request, Response, Status};
+
+use super::{into_status, Device};
 pub mod trustzone {
     tonic::include_proto!("trustzonectrl");
 }
@@ -13,12 +15,15 @@ pub use trustzone::{
 };
 
 #[derive(Debug, Default)]
-pub struct TrustZoneCtrlServiceManager {
-    pub trustzone_ctrl: TrustZoneCtrl,
+pub struct TrustZoneCtrlServiceManager<T = TrustZoneCtrl> {
+    pub trustzone_ctrl: Device<T>,
 }
 
 #[tonic::async_trait]
-impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
+impl<T> TrustZoneCtrlService for TrustZoneCtrlServiceManager<T>
+where
+    T: TrustZoneControl + Send + Sync + 'static,
+{
     async fn read_certification(
         &self,
         request: Request<ReadCertificationRequest>,
@@ -30,13 +35,16 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
         //read the certification from the trustzone
         match self
             .trustzone_ctrl
-            .read_trustzone_cert(&output_file, &region)
+            .run("read_certification", move |trustzone| {
+                trustzone.read_trustzone_cert(&output_file, &region)
+            })
+            .await
         {
             Ok(certificate) => {
                 // Construct a successful response with the certification.
                 Ok(Response::new(ReadCertificationResponse { certificate }))
             }
-            Err(err) => Err(Status::from_error(err.into())),
+            Err(err) => Err(into_status(err)),
         }
     }
 
@@ -53,12 +61,17 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
         //write the certification to the trustzone
         match self
             .trustzone_ctrl
-            .write_trustzone_cert(&cert_file, &region)
+            .run("write_certificate", move |trustzone| {
+                trustzone.write_trustzone_cert(&cert_file, &region)
+            })
+            .await
         {
             Ok(()) => {
                 // Return success as true.
                 Ok(Response::new(WriteCertificateResponse { success: true }))
             }
+            // the tool may still be running, its outcome is unknown
+            Err(err) if err.is::<Status>() => Err(into_status(err)),
             Err(_) => {
                 // Return success as false and include an error message if needed.
                 Ok(Response::new(WriteCertificateResponse { success: false }))
@@ -75,11 +88,18 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
         let oid = request.oid;
 
         // Call the remove_trustzone_cert function using TrustZoneCtrl.
-        match self.trustzone_ctrl.remove_trustzone_cert(&oid) {
+        match self
+            .trustzone_ctrl
+            .run("remove_certificate", move |trustzone| {
+                trustzone.remove_trustzone_cert(&oid)
+            })
+            .await
+        {
             Ok(()) => {
                 // Return success as true.
                 Ok(Response::new(RemoveCertificateResponse { success: true }))
             }
+            Err(err) if err.is::<Status>() => Err(into_status(err)),
             Err(_) => {
                 // Return success as false.
                 Ok(Response::new(RemoveCertificateResponse { success: false }))
@@ -131,7 +151,10 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
         // Call the generate_trustzone_key function using TrustZoneCtrl.
         match self
             .trustzone_ctrl
-            .generate_trustzone_key(&oid, key_type, key_size, &output_file)
+            .run("generate_key", move |trustzone| {
+                trustzone.generate_trustzone_key(&oid, key_type, key_size, &output_file)
+            })
+            .await
         {
             Ok(public_key) => {
                 // Return the generated public key without an error message.
@@ -139,7 +162,7 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
             }
             Err(err) => {
                 // Return an empty response since error information is not needed.
-                Err(Status::from_error(err.into()))
+                Err(into_status(err))
             }
         }
     }
@@ -155,19 +178,20 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
         let hash_before_sign = request.hash_before_sign;
 
         // Call the sign_trustzone_data function using TrustZoneCtrl.
-        match self.trustzone_ctrl.sign_trustzone_data(
-            &key_oid,
-            &input_file,
-            &output_file,
-            hash_before_sign,
-        ) {
+        match self
+            .trustzone_ctrl
+            .run("sign_data", move |trustzone| {
+                trustzone.sign_trustzone_data(&key_oid, &input_file, &output_file, hash_before_sign)
+            })
+            .await
+        {
             Ok(signed_data) => {
                 // Return the signed data without an error message.
                 Ok(Response::new(SignDataResponse { signed_data }))
             }
             Err(err) => {
                 // Return an empty response since error information is not needed.
-                Err(Status::from_error(err.into()))
+                Err(into_status(err))
             }
         }
     }
@@ -185,12 +209,18 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
 
         // Your existing logic to verify the data using TrustZoneCtrl.
         // Call the verify_trustzone_data function using TrustZoneCtrl.
-        match self.trustzone_ctrl.verify_trustzone_data(
-            &pubkey_file,
-            &input_file,
-            &signature_file,
-            hash_before_verify,
-        ) {
+        match self
+            .trustzone_ctrl
+            .run("verify_data", move |trustzone| {
+                trustzone.verify_trustzone_data(
+                    &pubkey_file,
+                    &input_file,
+                    &signature_file,
+                    hash_before_verify,
+                )
+            })
+            .await
+        {
             Ok(_) => {
                 // Return a success message.
                 Ok(Response::new(VerifyDataResponse {
@@ -199,7 +229,7 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
             }
             Err(err) => {
                 // Return an empty response since error information is not needed.
-                Err(Status::from_error(err.into()))
+                Err(into_status(err))
             }
         }
     }
@@ -218,20 +248,29 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
 
         // Your existing logic to derive the key using TrustZoneCtrl.
         // Call the derive_trustzone_key function using TrustZoneCtrl.
-        match self.trustzone_ctrl.derive_trustzone_key(
-            &secret_oid,
-            hkdf_type.try_into().unwrap(),
-            &info_file,
-            &salt_file,
-            &output_file,
-        ) {
+        let Ok(hkdf_type) = u16::try_from(hkdf_type) else {
+            return Err(Status::invalid_argument("Invalid hkdf type"));
+        };
+        match self
+            .trustzone_ctrl
+            .run("derive_key", move |trustzone| {
+                trustzone.derive_trustzone_key(
+                    &secret_oid,
+                    hkdf_type,
+                    &info_file,
+                    &salt_file,
+                    &output_file,
+                )
+            })
+            .await
+        {
             Ok(derived_key) => {
                 // Return the derived key without an error message.
                 Ok(Response::new(DeriveKeyResponse { derived_key }))
             }
             Err(err) => {
                 // Return an empty response since error information is not needed.
-                Err(Status::from_error(err.into()))
+                Err(into_status(err))
             }
         }
     }
@@ -249,19 +288,23 @@ impl TrustZoneCtrlService for TrustZoneCtrlServiceManager {
 
         // Your existing logic to generate the HMAC using TrustZoneCtrl.
         // Call the generate_trustzone_hmac function using TrustZoneCtrl.
-        match self.trustzone_ctrl.generate_trustzone_hmac(
-            &secret_oid,
-            hmac_type.try_into().unwrap(),
-            &input_data,
-            &output_file,
-        ) {
+        let Ok(hmac_type) = u16::try_from(hmac_type) else {
+            return Err(Status::invalid_argument("Invalid hmac type"));
+        };
+        match self
+            .trustzone_ctrl
+            .run("generate_hmac", move |trustzone| {
+                trustzone.generate_trustzone_hmac(&secret_oid, hmac_type, &input_data, &output_file)
+            })
+            .await
+        {
             Ok(generated_hmac) => {
                 // Return the generated HMAC without an error message.
                 Ok(Response::new(GenerateHmacResponse { generated_hmac }))
             }
             Err(err) => {
                 // Return an empty response since error information is not needed.
-                Err(Status::from_error(err.into()))
+                Err(into_status(err))
             }
         }
     }
@@ -0,0 +1,327 @@
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tracing::{trace, warn};
+
+use crate::services::{CpuControl, CpuFrequency, Device, DisplayControl, LedColor, LedControl};
+
+pub const BRIGHTNESS: &str = "display.brightness";
+pub const RED_LED: &str = "led.red";
+pub const GREEN_LED: &str = "led.green";
+pub const BLUE_LED: &str = "led.blue";
+// the scaling governor as set, e.g. userspace or powersave
+pub const CPU_GOVERNOR: &str = "cpu.governor";
+// preferred frequency in kHz, one of the `CpuFrequency` steps
+pub const CPU_FREQUENCY: &str = "cpu.frequency";
+
+// the only governor that takes a frequency from `scaling_setspeed`
+pub(crate) const USERSPACE_GOVERNOR: &str = "userspace";
+
+const LED_ON: &str = "on";
+const LED_OFF: &str = "off";
+
+// key-value store of the device settings, a json object on disk. every
+// change is written to a temporary file that is renamed over the old one,
+// so a crash leaves either the old or the new settings
+#[derive(Debug, Clone)]
+pub struct SettingsStore {
+    path: PathBuf,
+    values: Arc<Mutex<BTreeMap<String, String>>>,
+}
+
+impl SettingsStore {
+    // a missing file is an empty store, an unreadable one is replaced by the
+    // next change
+    pub fn open(path: &Path) -> Result<Self> {
+        let values = match fs::read(path) {
+            Ok(contents) => serde_json::from_slice(&contents).unwrap_or_else(|e| {
+                warn!(
+                    task = "open",
+                    "ignoring invalid settings file {}: {}",
+                    path.display(),
+                    e
+                );
+                BTreeMap::new()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).context(format!("unable to read {}", path.display())),
+        };
+        Ok(SettingsStore {
+            path: path.to_path_buf(),
+            values: Arc::new(Mutex::new(values)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.values().get(key).cloned()
+    }
+
+    pub fn all(&self) -> BTreeMap<String, String> {
+        self.values().clone()
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<()> {
+        self.update(|values| {
+            values.insert(key.to_string(), value.to_string());
+        })
+    }
+
+    // forget the given keys, or every key when none are given. returns the
+    // keys that were set
+    pub fn reset(&self, keys: &[String]) -> Result<Vec<String>> {
+        self.update(|values| {
+            let removed = values
+                .keys()
+                .filter(|key| keys.is_empty() || keys.contains(key))
+                .cloned()
+                .collect::<Vec<_>>();
+            for key in &removed {
+                values.remove(key);
+            }
+            removed
+        })
+    }
+
+    // one transaction: `change` works on a copy that becomes visible only
+    // once it is on disk, a failed write leaves the settings as they were
+    pub fn update<R>(&self, change: impl FnOnce(&mut BTreeMap<String, String>) -> R) -> Result<R> {
+        let mut values = self.values();
+        let mut changed = values.clone();
+        let result = change(&mut changed);
+        if changed != *values {
+            self.write(&changed)?;
+            *values = changed;
+        }
+        Ok(result)
+    }
+
+    fn values(&self) -> MutexGuard<'_, BTreeMap<String, String>> {
+        self.values.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self, values: &BTreeMap<String, String>) -> Result<()> {
+        trace!(task = "write", "saving {}", self.path.display());
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .mode(0o640)
+            .open(&temporary)
+            .with_context(|| format!("unable to open {}", temporary.display()))?;
+        file.write_all(&serde_json::to_vec_pretty(values)?)
+            .with_context(|| format!("unable to write {}", temporary.display()))?;
+        file.sync_all()?;
+        fs::rename(&temporary, &self.path)
+            .with_context(|| format!("unable to replace {}", self.path.display()))?;
+        // the rename is only durable once the directory entry is
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .with_context(|| format!("unable to sync {}", dir.display()))
+    }
+
+    // the saved settings reapplied to the hardware, a setting that fails is
+    // logged and skipped. each returns the keys it applied
+    pub fn restore_display(&self, display: &impl DisplayControl) -> Vec<&'static str> {
+        self.restore(&[BRIGHTNESS], |_, value| {
+            display.set_display_brightness(value.parse().context("invalid brightness")?)
+        })
+    }
+
+    pub fn restore_led(&self, led: &impl LedControl) -> Vec<&'static str> {
+        self.restore(&[RED_LED, GREEN_LED, BLUE_LED], |key, value| {
+            let color = match key {
+                RED_LED => LedColor::Red,
+                GREEN_LED => LedColor::Green,
+                _ => LedColor::Blue,
+            };
+            match value {
+                LED_ON => led.set_led(color),
+                LED_OFF => led.clear_led(color),
+                value => bail!("invalid led state {}", value),
+            }
+        })
+    }
+
+    // the governor first, cpufreq only takes a frequency under userspace. a
+    // saved frequency is left alone when another governor was saved
+    pub fn restore_cpu(&self, cpu: &impl CpuControl) -> Vec<&'static str> {
+        let keys: &[&'static str] = match self.get(CPU_GOVERNOR).as_deref() {
+            None | Some(USERSPACE_GOVERNOR) => &[CPU_GOVERNOR, CPU_FREQUENCY],
+            Some(_) => &[CPU_GOVERNOR],
+        };
+        self.restore(keys, |key, value| match key {
+            CPU_GOVERNOR => cpu.set_cpu_governor(value),
+            _ => match cpu_frequency(value) {
+                Some(frequency) => cpu.set_cpu_frequency(frequency),
+                None => bail!("invalid cpu frequency {}", value),
+            },
+        })
+    }
+
+    fn restore(
+        &self,
+        keys: &[&'static str],
+        mut apply: impl FnMut(&str, &str) -> Result<()>,
+    ) -> Vec<&'static str> {
+        let mut applied = Vec::new();
+        for key in keys {
+            let value = match self.get(key) {
+                Some(value) => value,
+                None => continue,
+            };
+            match apply(key, &value) {
+                Ok(_) => applied.push(*key),
+                Err(e) => warn!(
+                    task = "restore",
+                    "unable to restore {} = {}: {:#}", key, value, e
+                ),
+            }
+        }
+        applied
+    }
+}
+
+// runs one of the `restore_*` calls on the device's blocking pool, a device
+// that does not answer is skipped
+pub async fn restore<T, F>(
+    device: &Device<T>,
+    store: &SettingsStore,
+    restore: F,
+) -> Vec<&'static str>
+where
+    T: Send + Sync + 'static,
+    F: FnOnce(&SettingsStore, &T) -> Vec<&'static str> + Send + 'static,
+{
+    let store = store.clone();
+    match device
+        .run("restore_settings", move |device| {
+            Ok(restore(&store, device))
+        })
+        .await
+    {
+        Ok(applied) => applied,
+        Err(e) => {
+            warn!(task = "restore", "{:#}", e);
+            Vec::new()
+        }
+    }
+}
+
+fn led_key(color: &LedColor) -> &'static str {
+    match color {
+        LedColor::Red => RED_LED,
+        LedColor::Green => GREEN_LED,
+        LedColor::Blue => BLUE_LED,
+    }
+}
+
+fn frequency_khz(frequency: &CpuFrequency) -> &'static str {
+    match frequency {
+        CpuFrequency::Freq1200000 => "1200000",
+        CpuFrequency::Freq1600000 => "1600000",
+        CpuFrequency::Freq1800000 => "1800000",
+    }
+}
+
+pub(crate) fn cpu_frequency(khz: &str) -> Option<CpuFrequency> {
+    match khz {
+        "1200000" => Some(CpuFrequency::Freq1200000),
+        "1600000" => Some(CpuFrequency::Freq1600000),
+        "1800000" => Some(CpuFrequency::Freq1800000),
+        _ => None,
+    }
+}
+
+// a controller whose state changes are written through to the store. a
+// change that can not be saved is only logged, the hardware already took it
+#[derive(Debug)]
+pub struct Persisted<T> {
+    inner: T,
+    store: Option<SettingsStore>,
+}
+
+impl<T> Persisted<T> {
+    // without a store nothing is saved
+    pub fn new(inner: T, store: Option<SettingsStore>) -> Self {
+        Persisted { inner, store }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn save(&self, key: &str, value: &str) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.set(key, value) {
+                warn!(task = "save", "unable to save {}: {:#}", key, e);
+            }
+        }
+    }
+}
+
+impl<T: DisplayControl> DisplayControl for Persisted<T> {
+    fn set_display_brightness(&self, brightness: u8) -> Result<()> {
+        self.inner.set_display_brightness(brightness)?;
+        self.save(BRIGHTNESS, &brightness.to_string());
+        Ok(())
+    }
+
+    fn get_display_brightness(&self) -> Result<u8> {
+        self.inner.get_display_brightness()
+    }
+}
+
+impl<T: LedControl> LedControl for Persisted<T> {
+    fn set_led(&self, color: LedColor) -> Result<()> {
+        let key = led_key(&color);
+        self.inner.set_led(color)?;
+        self.save(key, LED_ON);
+        Ok(())
+    }
+
+    fn clear_led(&self, color: LedColor) -> Result<()> {
+        let key = led_key(&color);
+        self.inner.clear_led(color)?;
+        self.save(key, LED_OFF);
+        Ok(())
+    }
+}
+
+impl<T: CpuControl> CpuControl for Persisted<T> {
+    fn set_cpu_governor(&self, governor: &str) -> Result<()> {
+        self.inner.set_cpu_governor(governor)?;
+        self.save(CPU_GOVERNOR, governor);
+        Ok(())
+    }
+
+    fn get_cpu_governor(&self) -> Result<String> {
+        self.inner.get_cpu_governor()
+    }
+
+    fn get_cpu_frequency(&self) -> Result<String> {
+        self.inner.get_cpu_frequency()
+    }
+
+    fn set_cpu_frequency(&self, frequency: CpuFrequency) -> Result<()> {
+        let khz = frequency_khz(&frequency);
+        self.inner.set_cpu_frequency(frequency)?;
+        self.save(CPU_FREQUENCY, khz);
+        Ok(())
+    }
+}
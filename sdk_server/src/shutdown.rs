@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+// broadcast once a shutdown signal arrives, long running tasks (streams,
+// background loops) wait on `triggered` to finish early
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    sender: watch::Sender<bool>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Shutdown::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(false);
+        Shutdown { sender }
+    }
+
+    pub fn trigger(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    // not tied to the lifetime of `self`, so it can be handed to a server
+    pub fn triggered(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut receiver = self.sender.subscribe();
+        async move {
+            // fails only once every sender is dropped, nothing left to wait for
+            let _ = receiver.wait_for(|triggered| *triggered).await;
+        }
+    }
+}
+
+// completes on the first SIGTERM or SIGINT
+pub async fn wait_for_signal() -> Result<()> {
+    let mut terminate = signal(SignalKind::terminate()).context("unable to handle SIGTERM")?;
+    let mut interrupt = signal(SignalKind::interrupt()).context("unable to handle SIGINT")?;
+    let name = tokio::select! {
+        _ = terminate.recv() => "SIGTERM",
+        _ = interrupt.recv() => "SIGINT",
+    };
+    info!(task = "shutdown", "{} received, shutting down", name);
+    Ok(())
+}
+
+// run `serve` until it returns on its own or, once `shutdown` is triggered,
+// until in-flight rpcs are drained or `drain_timeout` elapses
+pub async fn drain<F, E>(serve: F, shutdown: &Shutdown, drain_timeout: Duration) -> Result<()>
+where
+    F: Future<Output = Result<(), E>>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    tokio::pin!(serve);
+    tokio::select! {
+        result = &mut serve => return result.context("grpc server failed"),
+        _ = shutdown.triggered() => {}
+    }
+    match tokio::time::timeout(drain_timeout, serve).await {
+        Ok(result) => {
+            result.context("grpc server failed")?;
+            info!(task = "shutdown", "in-flight rpcs drained");
+        }
+        Err(_) => warn!(
+            task = "shutdown",
+            "drain timeout of {:?} elapsed, closing remaining connections", drain_timeout
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use sd_notify::NotifyState;
+use std::time::Duration;
+use tracing::{debug, info, trace, warn};
+
+// sd_notify is a no-op when the server is not started by systemd
+// (NOTIFY_SOCKET unset), failures are logged and otherwise ignored
+fn notify(state: NotifyState) {
+    if let Err(e) = sd_notify::notify(false, &[state]) {
+        warn!(task = "sd_notify", "unable to notify systemd: {}", e);
+    }
+}
+
+// the listener is bound and every service registered
+pub fn notify_ready() {
+    trace!(task = "sd_notify", "ready");
+    notify(NotifyState::Ready);
+}
+
+pub fn notify_stopping() {
+    trace!(task = "sd_notify", "stopping");
+    notify(NotifyState::Stopping);
+}
+
+// when the unit sets WatchdogSec, ping systemd at half the configured
+// interval for as long as the runtime keeps scheduling tasks
+pub fn spawn_watchdog() {
+    let mut usec = 0;
+    if !sd_notify::watchdog_enabled(false, &mut usec) {
+        debug!(task = "sd_notify", "watchdog not enabled");
+        return;
+    }
+    let interval = Duration::from_micros(usec) / 2;
+    info!(
+        task = "sd_notify",
+        "watchdog enabled, pinging every {:?}", interval
+    );
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            notify(NotifyState::Watchdog);
+        }
+    });
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use prometheus::core::Collector;
+use prometheus::proto::MetricFamily;
+use prometheus::{Gauge, Opts};
+use std::sync::{Mutex, PoisonError};
+use tracing::debug;
+
+use crate::services::{CpuControl, Device, DeviceMetricsInfo, PowerSupplyInfo};
+
+// device readings taken on every scrape. a source that is missing or fails
+// to read leaves its gauges out instead of exporting stale or zero values
+pub struct DeviceGauges<M, P, C> {
+    pub metrics: Option<Device<Mutex<M>>>,
+    pub battery: Option<Device<P>>,
+    pub cpu: Option<Device<C>>,
+}
+
+impl<M, P, C> DeviceGauges<M, P, C>
+where
+    M: DeviceMetricsInfo + Send + 'static,
+    P: PowerSupplyInfo + Send + Sync + 'static,
+    C: CpuControl + Send + Sync + 'static,
+{
+    pub async fn collect(&self) -> Vec<MetricFamily> {
+        let mut gauges = Vec::new();
+        if let Some(metrics) = &self.metrics {
+            let usage = metrics
+                .run("scrape_metrics", |metrics| {
+                    let mut metrics = metrics.lock().unwrap_or_else(PoisonError::into_inner);
+                    metrics.refresh();
+                    Ok((
+                        metrics.get_cpu_usage()?,
+                        metrics.get_memory_usage()?,
+                        metrics.get_disk_usage(),
+                    ))
+                })
+                .await;
+            match usage {
+                Ok((cpu, memory, disk)) => {
+                    gauges.push(gauge("mecha_cpu_usage_percent", "CPU usage", cpu.into()));
+                    gauges.push(gauge(
+                        "mecha_memory_used_bytes",
+                        "Memory in use",
+                        memory as f64,
+                    ));
+                    if let Ok(disk) = disk {
+                        gauges.push(gauge(
+                            "mecha_disk_used_bytes",
+                            "Space used on the primary disk",
+                            disk as f64,
+                        ));
+                    }
+                }
+                Err(e) => debug!(task = "scrape_metrics", "no system metrics: {:#}", e),
+            }
+        }
+        if let Some(battery) = &self.battery {
+            match battery
+                .run("scrape_battery", |battery| battery.info())
+                .await
+            {
+                Ok(info) => {
+                    gauges.push(gauge(
+                        "mecha_battery_capacity_percent",
+                        "Battery charge",
+                        info.capacity.into(),
+                    ));
+                    // the fuel gauge reports micro volts, micro amperes and tenths of a degree
+                    gauges.push(gauge(
+                        "mecha_battery_voltage_volts",
+                        "Battery voltage",
+                        f64::from(info.voltage_now) / 1e6,
+                    ));
+                    gauges.push(gauge(
+                        "mecha_battery_current_amperes",
+                        "Battery current, negative while discharging",
+                        f64::from(info.current_now) / 1e6,
+                    ));
+                    gauges.push(gauge(
+                        "mecha_battery_temperature_celsius",
+                        "Battery temperature",
+                        f64::from(info.temp) / 10.0,
+                    ));
+                }
+                Err(e) => debug!(task = "scrape_battery", "no battery metrics: {:#}", e),
+            }
+        }
+        if let Some(cpu) = &self.cpu {
+            // cpufreq reports kHz
+            let frequency = cpu
+                .run("scrape_cpu", |cpu| {
+                    let frequency = cpu.get_cpu_frequency()?;
+                    Ok(frequency.trim().parse::<f64>()?)
+                })
+                .await;
+            match frequency {
+                Ok(frequency) => gauges.push(gauge(
+                    "mecha_cpu_frequency_hertz",
+                    "Current CPU frequency",
+                    frequency * 1e3,
+                )),
+                Err(e) => debug!(task = "scrape_cpu", "no cpu frequency: {:#}", e),
+            }
+        }
+        gauges.into_iter().flatten().collect()
+    }
+}
+
+fn gauge(name: &str, help: &str, value: f64) -> Result<MetricFamily> {
+    let gauge = Gauge::with_opts(Opts::new(name, help))?;
+    gauge.set(value);
+    Ok(gauge.collect().remove(0))
+}
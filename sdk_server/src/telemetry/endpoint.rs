@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{header, Body, Method, Request, Response, Server, StatusCode};
+use prometheus::{Encoder, Registry, TextEncoder};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use tracing::{trace, warn};
+
+use super::DeviceGauges;
+use crate::services::{CpuControl, DeviceMetricsInfo, PowerSupplyInfo};
+use crate::shutdown::Shutdown;
+
+pub fn bind_metrics(addr: SocketAddr) -> Result<TcpListener> {
+    let listener =
+        TcpListener::bind(addr).with_context(|| format!("unable to listen on {}", addr))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+// plain http `GET /metrics` in the prometheus text format, the rpc metrics
+// in `registry` plus the device gauges read at scrape time
+pub fn serve_metrics<M, P, C>(
+    listener: TcpListener,
+    registry: Registry,
+    gauges: DeviceGauges<M, P, C>,
+    shutdown: &Shutdown,
+) -> Result<impl Future<Output = Result<()>>>
+where
+    M: DeviceMetricsInfo + Send + 'static,
+    P: PowerSupplyInfo + Send + Sync + 'static,
+    C: CpuControl + Send + Sync + 'static,
+{
+    let scrape = Arc::new((registry, gauges));
+    let make_service = make_service_fn(move |_| {
+        let scrape = scrape.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let scrape = scrape.clone();
+                async move { Ok::<_, Infallible>(respond(request, &scrape.0, &scrape.1).await) }
+            }))
+        }
+    });
+    let server = Server::from_tcp(listener)?
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown.triggered());
+    Ok(async move { server.await.context("metrics endpoint failed") })
+}
+
+async fn respond<M, P, C>(
+    request: Request<Body>,
+    registry: &Registry,
+    gauges: &DeviceGauges<M, P, C>,
+) -> Response<Body>
+where
+    M: DeviceMetricsInfo + Send + 'static,
+    P: PowerSupplyInfo + Send + Sync + 'static,
+    C: CpuControl + Send + Sync + 'static,
+{
+    if request.method() != Method::GET || request.uri().path() != "/metrics" {
+        return status(StatusCode::NOT_FOUND);
+    }
+    trace!(task = "scrape", "init");
+    let mut families = registry.gather();
+    families.extend(gauges.collect().await);
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    if let Err(e) = encoder.encode(&families, &mut body) {
+        warn!(task = "scrape", "unable to encode metrics: {}", e);
+        return status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    Response::builder()
+        .header(header::CONTENT_TYPE, encoder.format_type())
+        .body(Body::from(body))
+        .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = code;
+    response
+}
@@ -0,0 +1,78 @@
+use anyhow::{anyhow, Context, Result};
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+use opentelemetry::sdk::{trace, Resource};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+use crate::configs::{LogFormat, Logging, Otlp};
+
+// flushes the spans still queued for the collector when dropped
+#[derive(Debug)]
+pub struct LoggingGuard {
+    otlp: bool,
+}
+
+impl Drop for LoggingGuard {
+    fn drop(&mut self) {
+        if self.otlp {
+            global::shutdown_tracer_provider();
+        }
+    }
+}
+
+// install the global subscriber: the level filter, text, json or journald
+// output and, when configured, the OpenTelemetry exporter
+pub fn init_logging(logging: &Logging) -> Result<LoggingGuard> {
+    let filter = EnvFilter::try_new(&logging.level)
+        .with_context(|| format!("invalid log level {}", logging.level))?;
+    let text = (logging.format == LogFormat::Text).then(fmt::layer);
+    let json = (logging.format == LogFormat::Json).then(|| fmt::layer().json());
+    let journald = match logging.format {
+        LogFormat::Journald => {
+            Some(tracing_journald::layer().context("unable to connect to journald")?)
+        }
+        _ => None,
+    };
+    let otlp = match &logging.otlp {
+        Some(otlp) => Some(tracing_opentelemetry::layer().with_tracer(otlp_tracer(otlp)?)),
+        None => None,
+    };
+    let guard = LoggingGuard {
+        otlp: otlp.is_some(),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(text)
+        .with(json)
+        .with(journald)
+        .with(otlp)
+        .try_init()
+        .map_err(|e| anyhow!(e))
+        .context("setting default subscriber failed")?;
+    Ok(guard)
+}
+
+// batches spans to the collector over grpc, the caller's trace context is
+// read from w3c `traceparent` headers
+fn otlp_tracer(otlp: &Otlp) -> Result<trace::Tracer> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&otlp.endpoint),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                otlp.service_name.clone(),
+            )])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)
+        .with_context(|| format!("unable to export traces to {}", otlp.endpoint))
+}
@@ -0,0 +1,15 @@
+mod device;
+pub use device::DeviceGauges;
+
+mod endpoint;
+pub use endpoint::{bind_metrics, serve_metrics};
+
+mod logging;
+pub use logging::{init_logging, LoggingGuard};
+
+mod rpc;
+pub use rpc::{RpcMetrics, RpcMetricsLayer, RpcMetricsService};
+
+mod trace;
+pub(crate) use trace::peer;
+pub use trace::{RpcTraceLayer, RpcTraceService};
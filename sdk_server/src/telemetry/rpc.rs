@@ -0,0 +1,157 @@
+use anyhow::{Context as _, Result};
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+use prost_reflect::DescriptorPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::Code;
+use tower::Layer;
+
+use crate::middleware::take_ready;
+
+// label of paths that are not an rpc of a served package
+const UNKNOWN: &str = "unknown";
+
+// per service and method rpc counters and latency histograms
+#[derive(Debug, Clone)]
+pub struct RpcMetrics {
+    requests: IntCounterVec,
+    duration: HistogramVec,
+    // `/package.Service/Method` of every rpc in the descriptor sets
+    methods: Arc<HashSet<String>>,
+}
+
+impl RpcMetrics {
+    // the paths are taken from the client, only rpcs in `file_descriptor_sets`
+    // get their own labels so random paths can not grow the registry
+    pub fn new(registry: &Registry, file_descriptor_sets: &[&[u8]]) -> Result<Self> {
+        let mut methods = HashSet::new();
+        for file_descriptor_set in file_descriptor_sets {
+            let pool = DescriptorPool::decode(*file_descriptor_set)
+                .context("unable to decode the file descriptor set")?;
+            for service in pool.services() {
+                for rpc in service.methods() {
+                    methods.insert(format!("/{}/{}", service.full_name(), rpc.name()));
+                }
+            }
+        }
+
+        let requests = IntCounterVec::new(
+            Opts::new("mecha_grpc_requests_total", "RPCs handled by the server"),
+            &["service", "method", "code"],
+        )?;
+        let duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mecha_grpc_request_duration_seconds",
+                "Time until the response headers of an RPC",
+            ),
+            &["service", "method"],
+        )?;
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(duration.clone()))?;
+        Ok(RpcMetrics {
+            requests,
+            duration,
+            methods: Arc::new(methods),
+        })
+    }
+
+    // `path` is `/package.Service/Method`
+    pub fn observe(&self, path: &str, code: Code, elapsed: Duration) {
+        let (service, method) = if self.methods.contains(path) {
+            split_path(path)
+        } else {
+            (UNKNOWN, UNKNOWN)
+        };
+        self.requests
+            .with_label_values(&[service, method, &format!("{:?}", code)])
+            .inc();
+        self.duration
+            .with_label_values(&[service, method])
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+// tower layer recording every rpc, a layer without metrics records nothing
+#[derive(Clone, Default)]
+pub struct RpcMetricsLayer {
+    metrics: Option<Arc<RpcMetrics>>,
+}
+
+impl RpcMetricsLayer {
+    pub fn new(metrics: Option<RpcMetrics>) -> Self {
+        RpcMetricsLayer {
+            metrics: metrics.map(Arc::new),
+        }
+    }
+}
+
+impl<S> Layer<S> for RpcMetricsLayer {
+    type Service = RpcMetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcMetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcMetricsService<S> {
+    inner: S,
+    metrics: Option<Arc<RpcMetrics>>,
+}
+
+impl<S, B> Service<http::Request<B>> for RpcMetricsService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let mut inner = take_ready(&mut self.inner);
+        let Some(metrics) = self.metrics.clone() else {
+            return Box::pin(inner.call(request));
+        };
+
+        let path = request.uri().path().to_string();
+        let started = Instant::now();
+        let response = inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            metrics.observe(&path, code(&response), started.elapsed());
+            Ok(response)
+        })
+    }
+}
+
+// `/package.Service/Method` into its service and method
+pub(super) fn split_path(path: &str) -> (&str, &str) {
+    path.trim_start_matches('/')
+        .split_once('/')
+        .unwrap_or((path, ""))
+}
+
+// failed calls carry their status in the headers, successful ones only in
+// the trailers
+pub(super) fn code<B>(response: &http::Response<B>) -> Code {
+    response
+        .headers()
+        .get("grpc-status")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i32>().ok())
+        .map(Code::from)
+        .unwrap_or(Code::Ok)
+}
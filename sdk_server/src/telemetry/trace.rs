@@ -0,0 +1,118 @@
+use opentelemetry::global;
+use opentelemetry::propagation::Extractor;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::transport::server::{TcpConnectInfo, TlsConnectInfo, UdsConnectInfo};
+use tonic::Code;
+use tower::Layer;
+use tracing::field::Empty;
+use tracing::{info_span, Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use super::rpc::{code, split_path};
+use crate::middleware::take_ready;
+
+// tower layer opening an `rpc` span around every call. the span carries the
+// service, method, peer and final status, continues a w3c `traceparent` sent
+// by the caller and is the parent of the device calls the rpc makes
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RpcTraceLayer;
+
+impl<S> Layer<S> for RpcTraceLayer {
+    type Service = RpcTraceService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RpcTraceService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RpcTraceService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for RpcTraceService<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let mut inner = take_ready(&mut self.inner);
+
+        let span = span(&request);
+        let response = span.in_scope(|| inner.call(request));
+        Box::pin(
+            async move {
+                let response = response.await?;
+                let code = code(&response);
+                let span = Span::current();
+                span.record("status", format!("{:?}", code));
+                if code != Code::Ok {
+                    span.record("otel.status_code", "ERROR");
+                }
+                Ok(response)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+fn span<B>(request: &http::Request<B>) -> Span {
+    let path = request.uri().path();
+    let (service, method) = split_path(path);
+    let span = info_span!(
+        "rpc",
+        otel.name = path,
+        otel.kind = "server",
+        otel.status_code = Empty,
+        service = service,
+        method = method,
+        peer = %peer(request),
+        status = Empty,
+    );
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    span.set_parent(parent);
+    span
+}
+
+// address of the caller, `unix` for unix socket clients
+pub(crate) fn peer<B>(request: &http::Request<B>) -> String {
+    let extensions = request.extensions();
+    let addr = extensions
+        .get::<TcpConnectInfo>()
+        .or_else(|| {
+            extensions
+                .get::<TlsConnectInfo<TcpConnectInfo>>()
+                .map(|info| info.get_ref())
+        })
+        .and_then(|info| info.remote_addr());
+    match addr {
+        Some(addr) => addr.to_string(),
+        None if extensions.get::<UdsConnectInfo>().is_some() => "unix".to_string(),
+        None => "unknown".to_string(),
+    }
+}
+
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
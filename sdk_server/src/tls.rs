@@ -0,0 +1,40 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+use tracing::{info, trace};
+
+use crate::configs::Tls;
+
+// build the tonic tls config from the `server.tls` section, certificates and
+// keys are read once at startup
+pub fn server_tls_config(tls: &Tls) -> Result<ServerTlsConfig> {
+    trace!(task = "server_tls_config", "init");
+    let cert = fs::read(&tls.cert)
+        .with_context(|| format!("unable to read server certificate {}", tls.cert))?;
+    let key =
+        fs::read(&tls.key).with_context(|| format!("unable to read server key {}", tls.key))?;
+
+    let mut config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+
+    match &tls.client_ca {
+        Some(client_ca) => {
+            let ca = fs::read(client_ca)
+                .with_context(|| format!("unable to read client ca {}", client_ca))?;
+            config = config
+                .client_ca_root(Certificate::from_pem(ca))
+                .client_auth_optional(!tls.require_client_cert);
+            info!(
+                task = "server_tls_config",
+                "client certificates verified against {}, required: {}",
+                client_ca,
+                tls.require_client_cert
+            );
+        }
+        None if tls.require_client_cert => {
+            bail!("server.tls.require_client_cert is set but server.tls.client_ca is missing")
+        }
+        None => info!(task = "server_tls_config", "client certificates disabled"),
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,222 @@
+mod common;
+
+use common::{connect, FakeDisplay, FakeWifi, TempDir};
+use mecha_sdk_server::audit::{AuditEntry, AuditFilter, AuditLog};
+use mecha_sdk_server::configs::{Audit, Auth};
+use mecha_sdk_server::middleware::{AuditLayer, Auditor, AuthLayer, Authorizer};
+use mecha_sdk_server::services::audit_service::audit::audit_service_client::AuditServiceClient;
+use mecha_sdk_server::services::audit_service::QueryRequest;
+use mecha_sdk_server::services::display_manager_service::displaymanager::{
+    display_ctrl_service_client::DisplayCtrlServiceClient, GetBrightnessRequest,
+    SetBrightnessRequest,
+};
+use mecha_sdk_server::services::network_manager_service::networkmanager::{
+    network_manager_service_client::NetworkManagerServiceClient, WifiConnectRequest,
+};
+use mecha_sdk_server::services::{
+    AuditManager, AuditServiceServer, Device, DisplayCtrlManager, DisplayCtrlServiceServer,
+    NetworkManager, NetworkManagerServiceServer, FILE_DESCRIPTOR_SET,
+};
+use tonic::transport::Server;
+use tonic::Request;
+
+const AUTH: &str = r#"
+tokens:
+  - name: installer
+    token: installer-token
+    role: admin
+roles:
+  admin:
+    - "*"
+"#;
+
+fn audit(name: &str, max_size: u64) -> (TempDir, AuditLog) {
+    let dir = TempDir::new(&format!("audit-{}", name));
+    let log = AuditLog::new(&Audit {
+        path: dir.join("audit.log").to_string_lossy().to_string(),
+        max_size,
+        keep: 2,
+    });
+    (dir, log)
+}
+
+fn with_token<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request
+        .metadata_mut()
+        .insert("authorization", "Bearer installer-token".parse().unwrap());
+    request
+}
+
+fn entry(timestamp: u64, method: &str) -> AuditEntry {
+    AuditEntry {
+        timestamp,
+        caller: Some("installer".to_string()),
+        role: Some("admin".to_string()),
+        peer: "unix".to_string(),
+        method: method.to_string(),
+        request: serde_json::json!({}),
+        code: "Ok".to_string(),
+        message: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn mutating_rpcs_are_audited_with_secrets_redacted() {
+    let (_dir, log) = audit("rpc", 1024 * 1024);
+    let log = Device::from(log);
+    let auth: Auth = serde_yaml::from_str(AUTH).unwrap();
+    let channel = connect(
+        Server::builder()
+            .layer(AuthLayer::new(Some(Authorizer::new(&auth).unwrap())))
+            .layer(AuditLayer::new(Some(
+                Auditor::new(log.clone(), FILE_DESCRIPTOR_SET).unwrap(),
+            )))
+            .add_service(DisplayCtrlServiceServer::new(DisplayCtrlManager {
+                display_ctrl: FakeDisplay::default().into(),
+            }))
+            .add_service(NetworkManagerServiceServer::new(NetworkManager {
+                wifi: FakeWifi::default(),
+            }))
+            .add_service(AuditServiceServer::new(AuditManager { log: log.clone() })),
+    )
+    .await;
+    let mut display = DisplayCtrlServiceClient::new(channel.clone());
+    let mut network = NetworkManagerServiceClient::new(channel.clone());
+    let mut audit = AuditServiceClient::new(channel);
+
+    network
+        .connect_wireless_network(with_token(WifiConnectRequest {
+            ssid: "mecha".to_string(),
+            psk: "correct horse".to_string(),
+        }))
+        .await
+        .unwrap();
+    display
+        .set_brightness(with_token(SetBrightnessRequest { brightness: 250 }))
+        .await
+        .unwrap_err();
+    // reads are not audited
+    display
+        .get_brightness(with_token(GetBrightnessRequest {}))
+        .await
+        .unwrap();
+
+    let written = std::fs::read_to_string(log.get().path()).unwrap();
+    assert_eq!(written.lines().count(), 2);
+    assert!(!written.contains("correct horse"));
+
+    let records = audit
+        .query(with_token(QueryRequest::default()))
+        .await
+        .unwrap()
+        .into_inner()
+        .records;
+    assert_eq!(records.len(), 2);
+    let wifi = &records[0];
+    assert_eq!(
+        wifi.method,
+        "/networkmanager.NetworkManagerService/ConnectWirelessNetwork"
+    );
+    assert_eq!(
+        (wifi.caller.as_str(), wifi.role.as_str()),
+        ("installer", "admin")
+    );
+    assert_eq!(wifi.code, "Ok");
+    let request: serde_json::Value = serde_json::from_str(&wifi.request).unwrap();
+    assert_eq!(request["ssid"], "mecha");
+    assert_eq!(request["psk"], "[redacted]");
+
+    let brightness = &records[1];
+    assert_eq!(brightness.code, "Unknown");
+    assert!(!brightness.message.is_empty());
+
+    let filtered = audit
+        .query(with_token(QueryRequest {
+            method: "DisplayCtrlService/SetBrightness".to_string(),
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .records;
+    assert_eq!(filtered, vec![brightness.clone()]);
+}
+
+#[tokio::test]
+async fn oversized_requests_are_rejected_before_they_are_buffered() {
+    let (_dir, log) = audit("oversized", 1024 * 1024);
+    let log = Device::from(log);
+    let channel = connect(
+        Server::builder()
+            .layer(AuditLayer::new(Some(
+                Auditor::new(log.clone(), FILE_DESCRIPTOR_SET).unwrap(),
+            )))
+            .add_service(NetworkManagerServiceServer::new(NetworkManager {
+                wifi: FakeWifi::default(),
+            }))
+            .add_service(AuditServiceServer::new(AuditManager { log: log.clone() })),
+    )
+    .await;
+    let mut network = NetworkManagerServiceClient::new(channel.clone());
+    let mut audit = AuditServiceClient::new(channel);
+
+    let status = network
+        .connect_wireless_network(WifiConnectRequest {
+            ssid: "m".repeat(5 * 1024 * 1024),
+            psk: String::new(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+    let records = audit
+        .query(QueryRequest::default())
+        .await
+        .unwrap()
+        .into_inner()
+        .records;
+    assert!(records.is_empty());
+}
+
+#[test]
+fn the_log_rotates_and_queries_span_rotated_files() {
+    // room for two entries per file
+    let line = serde_json::to_vec(&entry(0, "/led_ctrl.LedCtrlService/SetLed"))
+        .unwrap()
+        .len() as u64;
+    let (dir, log) = audit("rotate", 2 * line + 10);
+    for timestamp in 1..=7 {
+        log.append(&entry(timestamp, "/led_ctrl.LedCtrlService/SetLed"))
+            .unwrap();
+    }
+
+    // 7 in the current file, 5-6 in .1, 3-4 in .2, 1-2 were dropped
+    let rotated = |index: usize| dir.join(format!("audit.log.{}", index));
+    assert!(rotated(2).exists());
+    assert!(!rotated(3).exists());
+    let timestamps = |filter: &AuditFilter, limit: usize| -> Vec<u64> {
+        log.query(filter, limit)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.timestamp)
+            .collect()
+    };
+    assert_eq!(
+        timestamps(&AuditFilter::default(), 100),
+        vec![3, 4, 5, 6, 7]
+    );
+    assert_eq!(timestamps(&AuditFilter::default(), 2), vec![6, 7]);
+    let filter = AuditFilter {
+        since: Some(4),
+        until: Some(6),
+        method: Some("SetLed".to_string()),
+        caller: Some("installer".to_string()),
+    };
+    assert_eq!(timestamps(&filter, 100), vec![4, 5, 6]);
+    let filter = AuditFilter {
+        method: Some("ClearLed".to_string()),
+        ..Default::default()
+    };
+    assert!(timestamps(&filter, 100).is_empty());
+}
@@ -0,0 +1,199 @@
+mod common;
+
+use common::{connect, FakeCpu, FakeMetrics};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use mecha_sdk_server::configs::Auth;
+use mecha_sdk_server::middleware::{AuthLayer, Authorizer};
+use mecha_sdk_server::services::{
+    cpu_ctrl_service::cpu_governor_ctrl::{
+        cpu_governor_ctrl_service_client::CpuGovernorCtrlServiceClient, CpuFrequencyRequest,
+        Empty as CpuEmpty,
+    },
+    metrics_service::metrics::{
+        metrics_service_client::MetricsServiceClient, Empty as MetricsEmpty,
+    },
+    CpuCtrlService, CpuGovernorCtrlServiceServer, DeviceMetricsService, MetricsServiceServer,
+};
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tonic::transport::{Channel, Server};
+use tonic::{Code, Request};
+use tonic_health::pb::{health_client::HealthClient, HealthCheckRequest};
+
+const AUTH: &str = r#"
+jwt_secret: test-secret
+tokens:
+  - name: dashboard
+    token: dashboard-token
+    role: viewer
+roles:
+  admin:
+    - "*"
+  viewer:
+    - MetricsService
+    - cpugovernorctrl.CPUGovernorCtrlService/GetCPUFrequency
+"#;
+
+#[derive(Serialize)]
+struct Claims {
+    sub: String,
+    role: String,
+    exp: u64,
+}
+
+fn jwt(role: &str, secret: &str, lifetime: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+    let claims = Claims {
+        sub: "companion-app".to_string(),
+        role: role.to_string(),
+        exp: (now + lifetime) as u64,
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+fn with_token<T>(message: T, token: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    request.metadata_mut().insert(
+        "authorization",
+        format!("Bearer {}", token).parse().unwrap(),
+    );
+    request
+}
+
+async fn channel() -> Channel {
+    let auth: Auth = serde_yaml::from_str(AUTH).unwrap();
+    connect(
+        Server::builder()
+            .layer(AuthLayer::new(Some(Authorizer::new(&auth).unwrap())))
+            .add_service(MetricsServiceServer::new(DeviceMetricsService {
+                metrics: FakeMetrics,
+            }))
+            .add_service(CpuGovernorCtrlServiceServer::new(CpuCtrlService {
+                cpu_ctrl_manager: FakeCpu::default().into(),
+            })),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn missing_token_is_unauthenticated() {
+    let mut client = MetricsServiceClient::new(channel().await);
+
+    let status = client.get_cpu_usage(MetricsEmpty {}).await.unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    let status = client
+        .get_cpu_usage(with_token(MetricsEmpty {}, "wrong-token"))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn static_token_follows_role_allow_list() {
+    let channel = channel().await;
+    let mut metrics = MetricsServiceClient::new(channel.clone());
+    let mut cpu = CpuGovernorCtrlServiceClient::new(channel);
+
+    metrics
+        .get_cpu_usage(with_token(MetricsEmpty {}, "dashboard-token"))
+        .await
+        .unwrap();
+    cpu.get_cpu_frequency(with_token(CpuEmpty {}, "dashboard-token"))
+        .await
+        .unwrap();
+
+    let status = cpu
+        .set_cpu_frequency(with_token(
+            CpuFrequencyRequest {
+                frequency: "1800".to_string(),
+            },
+            "dashboard-token",
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+}
+
+#[tokio::test]
+async fn jwt_role_is_honoured() {
+    let channel = channel().await;
+    let mut cpu = CpuGovernorCtrlServiceClient::new(channel);
+
+    cpu.set_cpu_frequency(with_token(
+        CpuFrequencyRequest {
+            frequency: "1800".to_string(),
+        },
+        &jwt("admin", "test-secret", 3600),
+    ))
+    .await
+    .unwrap();
+
+    let status = cpu
+        .set_cpu_frequency(with_token(
+            CpuFrequencyRequest {
+                frequency: "1800".to_string(),
+            },
+            &jwt("viewer", "test-secret", 3600),
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+
+    for token in [
+        jwt("admin", "other-secret", 3600),
+        jwt("admin", "test-secret", -3600),
+        jwt("root", "test-secret", 3600),
+    ] {
+        let status = cpu
+            .get_cpu_frequency(with_token(CpuEmpty {}, &token))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::Unauthenticated);
+    }
+}
+
+#[test]
+fn undefined_role_is_rejected() {
+    let auth: Auth = serde_yaml::from_str(
+        r#"
+tokens:
+  - name: dashboard
+    token: dashboard-token
+    role: viewer
+roles:
+  admin:
+    - "*"
+"#,
+    )
+    .unwrap();
+    assert!(Authorizer::new(&auth).is_err());
+}
+
+#[tokio::test]
+async fn health_is_public() {
+    let auth: Auth = serde_yaml::from_str(AUTH).unwrap();
+    let (_reporter, health_service) = tonic_health::server::health_reporter();
+    let channel = connect(
+        Server::builder()
+            .layer(AuthLayer::new(Some(Authorizer::new(&auth).unwrap())))
+            .add_service(health_service),
+    )
+    .await;
+    let mut client = HealthClient::new(channel);
+
+    client
+        .check(HealthCheckRequest {
+            service: String::new(),
+        })
+        .await
+        .unwrap();
+}
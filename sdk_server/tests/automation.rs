@@ -0,0 +1,367 @@
+mod common;
+
+use common::{
+    connect, FakeBattery, FakeCpu, FakeDisplay, FakeLed, FakeMetrics, FakeMotion, FakeWifi, TempDir,
+};
+use mecha_network_manager::wifi::NetworkResult;
+use mecha_sdk_server::automation::{
+    describe, run_automation, Automation, Outputs, Readings, Sensors,
+};
+use mecha_sdk_server::configs::{Actions, BaseConfig, Rule, Severity, WifiCondition};
+use mecha_sdk_server::services::automation_service::automation::{
+    automation_service_client::AutomationServiceClient, ListRulesRequest, RuleRequest,
+};
+use mecha_sdk_server::services::{AutomationManager, AutomationServiceServer, Device};
+use mecha_sdk_server::settings::{Persisted, SettingsStore};
+use std::fs;
+use std::sync::Mutex;
+use std::time::Duration;
+use tonic::{transport::Server, Code};
+
+fn rules(yaml: &str) -> Vec<Rule> {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+const LOW_BATTERY: &str = r#"
+- name: low_battery
+  when:
+    battery_below: 15
+    battery_status: Discharging
+  then:
+    set_leds: [red]
+  otherwise:
+    clear_leds: [red]
+- name: offline
+  enabled: false
+  when:
+    wifi: disconnected
+  then:
+    brightness: 40
+"#;
+
+fn battery(capacity: u8, status: &str) -> Readings {
+    Readings {
+        battery: Some(capacity),
+        battery_status: Some(status.to_string()),
+        ..Readings::default()
+    }
+}
+
+#[test]
+fn rules_fire_once_when_their_conditions_change() {
+    let automation = Automation::new(&rules(LOW_BATTERY));
+
+    assert!(automation.evaluate(battery(40, "Discharging")).is_empty());
+    let fired = automation.evaluate(battery(12, "Discharging"));
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].rule, "low_battery");
+    assert_eq!(fired[0].actions.set_leds.len(), 1);
+    // still low, nothing new to do
+    assert!(automation.evaluate(battery(11, "Discharging")).is_empty());
+
+    let fired = automation.evaluate(battery(11, "Charging"));
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].actions.clear_leds.len(), 1);
+
+    // a state that can not be read does not hold
+    assert!(automation.evaluate(Readings::default()).is_empty());
+
+    // disabled rules are skipped until enabled
+    let offline = Readings {
+        wifi: Some(WifiCondition::Disconnected),
+        ..Readings::default()
+    };
+    assert!(automation.evaluate(offline.clone()).is_empty());
+    assert!(automation.set_enabled("offline", true).unwrap().enabled);
+    let fired = automation.evaluate(offline);
+    assert_eq!(fired[0].rule, "offline");
+    assert_eq!(fired[0].actions.brightness, Some(40));
+
+    let err = automation.set_enabled("missing", true).unwrap_err();
+    assert!(err.to_string().contains("unknown rule missing"), "{}", err);
+}
+
+#[tokio::test]
+async fn scheduler_drives_the_controllers_without_saving_settings() {
+    let dir = TempDir::new("automation");
+    let store = SettingsStore::open(&dir.join("settings.json")).unwrap();
+
+    // the fake battery is at 76% and discharging, the sensor sees no motion
+    let automation = Automation::new(&rules(
+        r#"
+- name: battery_saver
+  when:
+    battery_below: 80
+    idle_for: 0
+    wifi: connected
+    cpu_usage_above: 10
+  then:
+    brightness: 40
+    set_leds: [red, blue]
+    cpu_frequency: 1200000
+- name: wake_on_motion
+  when:
+    motion: true
+  then:
+    set_leds: [green]
+"#,
+    ));
+    let wifi = FakeWifi::default();
+    wifi.networks.lock().unwrap().push(NetworkResult {
+        network_id: 0,
+        ssid: "mecha".to_string(),
+        flags: "[CURRENT]".to_string(),
+    });
+    let sensors = Sensors {
+        battery: Some(Device::from(FakeBattery::default())),
+        metrics: Some(Device::from(Mutex::new(FakeMetrics))),
+        motion: Some(Device::from(FakeMotion::default())),
+        wifi: Some(wifi),
+    };
+    let (display, led, cpu) = (
+        FakeDisplay::default(),
+        FakeLed::default(),
+        FakeCpu::default(),
+    );
+    let outputs = Outputs {
+        display: Some(Device::from(Persisted::new(
+            display.clone(),
+            Some(store.clone()),
+        ))),
+        led: Some(Device::from(Persisted::new(
+            led.clone(),
+            Some(store.clone()),
+        ))),
+        cpu: Some(Device::from(Persisted::new(
+            cpu.clone(),
+            Some(store.clone()),
+        ))),
+    };
+    tokio::spawn(run_automation(
+        automation.clone(),
+        sensors,
+        outputs,
+        Duration::from_millis(10),
+    ));
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while automation.rules()[0].fired == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let status = &automation.rules()[0];
+    assert!(status.active);
+    assert_eq!(status.last_error, None);
+    assert_eq!(*display.brightness.lock().unwrap(), 40);
+    assert_eq!(*led.lit.lock().unwrap(), [true, false, true]);
+    assert_eq!(*cpu.governor.lock().unwrap(), "userspace");
+    assert_eq!(*cpu.frequency.lock().unwrap(), "1200000");
+    assert!(store.all().is_empty());
+    assert_eq!(automation.rules()[1].fired, 0);
+
+    let dry_run = automation.dry_run("wake_on_motion").unwrap();
+    assert!(!dry_run.holds);
+    assert_eq!(dry_run.checks[0].reading, "false");
+}
+
+#[tokio::test]
+async fn governor_actions_switch_the_cpu_governor() {
+    let cpu = FakeCpu::default();
+    let outputs = Outputs::<FakeDisplay, FakeLed, _> {
+        display: None,
+        led: None,
+        cpu: Some(Device::from(Persisted::new(cpu.clone(), None))),
+    };
+    let actions = |yaml: &str| serde_yaml::from_str::<Actions>(yaml).unwrap();
+
+    outputs
+        .apply(&actions("governor: powersave"))
+        .await
+        .unwrap();
+    assert_eq!(*cpu.governor.lock().unwrap(), "powersave");
+    assert_eq!(*cpu.frequency.lock().unwrap(), "1200000");
+    assert_eq!(
+        describe(&actions("governor: powersave")),
+        vec!["governor powersave"]
+    );
+
+    // a frequency needs userspace, the governor is switched only when it is not set
+    outputs
+        .apply(&actions("cpu_frequency: 1600000"))
+        .await
+        .unwrap();
+    assert_eq!(*cpu.governor.lock().unwrap(), "userspace");
+    assert_eq!(*cpu.frequency.lock().unwrap(), "1600000");
+
+    let err = outputs
+        .apply(&actions("governor: turbo"))
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("invalid CPU governor turbo"),
+        "{:#}",
+        err
+    );
+    assert_eq!(*cpu.governor.lock().unwrap(), "userspace");
+}
+
+#[tokio::test]
+async fn rules_are_listed_toggled_and_dry_run_over_grpc() {
+    let automation = Automation::new(&rules(LOW_BATTERY));
+    let channel = connect(Server::builder().add_service(AutomationServiceServer::new(
+        AutomationManager {
+            automation: automation.clone(),
+        },
+    )))
+    .await;
+    let mut client = AutomationServiceClient::new(channel);
+
+    let status = client
+        .dry_run_rule(RuleRequest {
+            name: "low_battery".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+
+    automation.evaluate(battery(12, "Discharging"));
+    let rules = client
+        .list_rules(ListRulesRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .rules;
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0].name, "low_battery");
+    assert!(rules[0].enabled && rules[0].active);
+    assert_eq!(
+        rules[0].conditions,
+        vec!["battery_below 15", "battery_status Discharging"]
+    );
+    assert_eq!(rules[0].then, vec!["set_led red"]);
+    assert_eq!(rules[0].otherwise, vec!["clear_led red"]);
+    assert!(!rules[1].enabled);
+
+    let rule = client
+        .disable_rule(RuleRequest {
+            name: "low_battery".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!rule.enabled && !rule.active);
+    // disabled rules do not fire but can still be tried
+    assert!(automation.evaluate(battery(10, "Discharging")).is_empty());
+    let dry_run = client
+        .dry_run_rule(RuleRequest {
+            name: "low_battery".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(dry_run.holds);
+    assert_eq!(dry_run.checks[0].reading, "10");
+    assert_eq!(dry_run.actions, vec!["set_led red"]);
+    assert!(dry_run.readings_at > 0);
+
+    let status = client
+        .enable_rule(RuleRequest {
+            name: "missing".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+#[test]
+fn invalid_rules_are_config_errors() {
+    let dir = TempDir::new("automation-config");
+    let path = dir.join("Config.yaml");
+    fs::write(
+        dir.join("scaling_available_governors"),
+        "ondemand powersave userspace\n",
+    )
+    .unwrap();
+    fs::write(
+        &path,
+        format!(
+            r#"
+server:
+  port: 50052
+services:
+  motion_sensor:
+    enabled: false
+interfaces:
+  cpu:
+    device: {}
+automation:
+  interval: 0
+  rules:
+    - name: idle
+      when:
+        idle_for: 60
+      then:
+        brightness: 40
+    - name: idle
+      when: {{}}
+      then:
+        cpu_frequency: 1000000
+    - name: full
+      when:
+        battery_above: 120
+      then: {{}}
+    - name: turbo
+      when:
+        battery_status: Charging
+      then:
+        governor: turbo
+    - name: pinned
+      when:
+        battery_status: Charging
+      then:
+        governor: powersave
+        cpu_frequency: 1200000
+    - name: saver
+      when:
+        battery_status: Discharging
+      then:
+        governor: powersave
+      otherwise:
+        governor: ondemand
+"#,
+            dir.path().display()
+        ),
+    )
+    .unwrap();
+
+    let config = BaseConfig::load(&path).unwrap();
+    assert!(config.unknown_keys.is_empty(), "{:?}", config.unknown_keys);
+    let issues = config.check().issues;
+    let issue = |key: &str, severity: Severity| {
+        issues
+            .iter()
+            .any(|issue| issue.key == key && issue.severity == severity)
+    };
+    assert!(issue("automation.interval", Severity::Error));
+    assert!(issue("automation.rules.0.when", Severity::Warning));
+    assert!(issue("automation.rules.1", Severity::Error));
+    assert!(issue("automation.rules.1.when", Severity::Error));
+    assert!(issue(
+        "automation.rules.1.then.cpu_frequency",
+        Severity::Error
+    ));
+    assert!(issue(
+        "automation.rules.2.when.battery_above",
+        Severity::Error
+    ));
+    assert!(issue("automation.rules.2.then", Severity::Error));
+    assert!(!issue("automation.rules.0", Severity::Error));
+    // governors are checked against the ones the cpu offers
+    assert!(issue("automation.rules.3.then.governor", Severity::Error));
+    assert!(issue("automation.rules.4.then.governor", Severity::Error));
+    assert!(!issues
+        .iter()
+        .any(|issue| issue.key.starts_with("automation.rules.5")));
+}
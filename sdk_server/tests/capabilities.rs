@@ -0,0 +1,81 @@
+mod common;
+
+use common::{connect, TempDir};
+use mecha_sdk_server::configs::BaseConfig;
+use mecha_sdk_server::health::{HardwareProbe, Probe};
+use mecha_sdk_server::reload::Swappable;
+use mecha_sdk_server::services::capabilities_service::capabilities::device_capabilities_service_client::DeviceCapabilitiesServiceClient;
+use mecha_sdk_server::services::capabilities_service::{GetCapabilitiesRequest, PROTO_VERSION};
+use mecha_sdk_server::services::{CapabilitiesManager, Device, DeviceCapabilitiesServiceServer};
+use mecha_simulator::{SimulatedBoard, SysfsRoot};
+use std::path::PathBuf;
+use tonic::transport::Server;
+
+#[tokio::test]
+async fn capabilities_describe_the_board_and_follow_the_interfaces() {
+    let dir = TempDir::new("capabilities");
+    let root = SysfsRoot::new(dir.path());
+    SimulatedBoard::new(&root).generate().unwrap();
+    let mut config =
+        BaseConfig::load(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Config.yaml")).unwrap();
+    config.simulation.enabled = true;
+    config.simulation.root = root.path().display().to_string();
+    config.resolve_interfaces().unwrap();
+
+    let interfaces = Swappable::new(config.interfaces.clone());
+    let channel = connect(
+        Server::builder().add_service(DeviceCapabilitiesServiceServer::new(CapabilitiesManager {
+            board: config.name.clone(),
+            interfaces: Device::from(interfaces.clone()),
+            services: Swappable::new(vec![HardwareProbe {
+                service: "displaymanager.DisplayCtrlService",
+                probe: Probe::None,
+            }]),
+        })),
+    )
+    .await;
+    let mut client = DeviceCapabilitiesServiceClient::new(channel);
+
+    let capabilities = client
+        .get_capabilities(GetCapabilitiesRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(capabilities.board, "mecha-compute-g1");
+    assert_eq!(capabilities.proto_version, PROTO_VERSION);
+    assert_eq!(capabilities.sdk_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        capabilities.services,
+        vec!["displaymanager.DisplayCtrlService"]
+    );
+    let display = capabilities.display.unwrap();
+    assert!(display.available);
+    assert_eq!(display.max_brightness, 244);
+    assert_eq!(
+        capabilities.led.unwrap().colors,
+        vec!["red", "green", "blue"]
+    );
+    let motion_sensor = capabilities.motion_sensor.unwrap();
+    assert!(motion_sensor.available);
+    assert_eq!(motion_sensor.name, "bmi088_gyro");
+    let cpu = capabilities.cpu.unwrap();
+    assert!(cpu.governors.contains(&"userspace".to_string()));
+    assert_eq!(cpu.frequencies, vec![1200000, 1600000, 1800000]);
+    assert_eq!((cpu.min_frequency, cpu.max_frequency), (1200000, 1800000));
+    assert_eq!(capabilities.battery.unwrap().technology, "Li-ion");
+    let adc = capabilities.adc.unwrap();
+    assert_eq!(adc.channels, vec!["channel_1", "channel_2"]);
+    assert_eq!(adc.name, "ads1015");
+    assert!(capabilities.camera.unwrap().available);
+
+    // a reload pointing a led at a missing device drops it from the list
+    let mut reloaded = config.interfaces.clone();
+    reloaded.led.blue_led = root.path().join("missing").display().to_string();
+    interfaces.swap(reloaded);
+    let capabilities = client
+        .get_capabilities(GetCapabilitiesRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(capabilities.led.unwrap().colors, vec!["red", "green"]);
+}
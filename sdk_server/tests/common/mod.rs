@@ -0,0 +1,419 @@
+// in-memory fakes for every hardware trait plus a helper that serves a tonic
+// router over an in-process duplex channel, so the grpc services can be driven
+// end to end without sysfs, wpa_supplicant, bluez or the optiga tools.
+#![allow(dead_code)]
+
+use anyhow::{bail, Result};
+use mecha_battery_ctrl::PowerSupply as PowerSupplyData;
+use mecha_network_manager::wifi::{NetworkResult, ScanResult};
+use mecha_sdk_server::services::{
+    BluetoothControl, CpuControl, CpuFrequency, DeviceMetricsInfo, DisplayControl, KeySize,
+    KeyType, LedColor, LedControl, MotionSensorControl, PowerSupplyInfo, TrustZoneControl,
+    WifiControl,
+};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tonic::body::BoxBody;
+use tonic::codegen::{http, Service, StdError};
+use tonic::transport::server::{Router, Routes};
+use tonic::transport::{Body, Channel, Endpoint, Uri};
+use tower::{service_fn, Layer};
+
+pub async fn connect<L>(router: Router<L>) -> Channel
+where
+    L: Layer<Routes> + Send + 'static,
+    L::Service: Service<http::Request<Body>, Response = http::Response<BoxBody>, Error = StdError>
+        + Clone
+        + Send
+        + 'static,
+    <L::Service as Service<http::Request<Body>>>::Future: Send + 'static,
+{
+    let (client, server) = tokio::io::duplex(4096);
+    tokio::spawn(async move {
+        router
+            .serve_with_incoming(tokio_stream::once(Ok::<_, std::io::Error>(server)))
+            .await
+    });
+
+    // the connector is called once, hand out the client half of the duplex
+    let mut client = Some(client);
+    Endpoint::try_from("http://[::]:50051")
+        .unwrap()
+        .connect_with_connector(service_fn(move |_: Uri| {
+            let client = client.take();
+            async move { client.ok_or_else(|| std::io::Error::other("client already taken")) }
+        }))
+        .await
+        .unwrap()
+}
+
+// an empty directory per test and process under the system temp dir, removed
+// with everything in it when dropped
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("mecha-{}-{}", name, std::process::id()));
+        // left over by a run that panicked before the drop
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FakeDisplay {
+    pub brightness: Arc<Mutex<u8>>,
+}
+
+impl DisplayControl for FakeDisplay {
+    fn set_display_brightness(&self, brightness: u8) -> Result<()> {
+        if brightness > 244 {
+            bail!("invalid brightness value");
+        }
+        *self.brightness.lock().unwrap() = brightness;
+        Ok(())
+    }
+
+    fn get_display_brightness(&self) -> Result<u8> {
+        Ok(*self.brightness.lock().unwrap())
+    }
+}
+
+// lit state of the red, green and blue leds
+#[derive(Clone, Default)]
+pub struct FakeLed {
+    pub lit: Arc<Mutex<[bool; 3]>>,
+}
+
+impl FakeLed {
+    fn index(color: LedColor) -> usize {
+        match color {
+            LedColor::Red => 0,
+            LedColor::Green => 1,
+            LedColor::Blue => 2,
+        }
+    }
+}
+
+impl LedControl for FakeLed {
+    fn set_led(&self, color: LedColor) -> Result<()> {
+        self.lit.lock().unwrap()[Self::index(color)] = true;
+        Ok(())
+    }
+
+    fn clear_led(&self, color: LedColor) -> Result<()> {
+        self.lit.lock().unwrap()[Self::index(color)] = false;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FakeMotion {
+    pub value: Arc<Mutex<(f64, f64, f64)>>,
+}
+
+impl MotionSensorControl for FakeMotion {
+    fn read_motion_sensor_value(&self) -> Result<(f64, f64, f64)> {
+        Ok(*self.value.lock().unwrap())
+    }
+
+    fn detect_motion_sensor_event(&self) -> Result<bool> {
+        let (x, y, z) = *self.value.lock().unwrap();
+        Ok(x != 0.0 || y != 0.0 || z != 0.0)
+    }
+}
+
+// scaling_available_governors of the fake cpu
+pub const CPU_GOVERNORS: [&str; 4] = ["ondemand", "performance", "powersave", "userspace"];
+
+#[derive(Clone)]
+pub struct FakeCpu {
+    pub governor: Arc<Mutex<String>>,
+    pub frequency: Arc<Mutex<String>>,
+}
+
+impl Default for FakeCpu {
+    fn default() -> Self {
+        FakeCpu {
+            governor: Arc::new(Mutex::new("ondemand".to_string())),
+            frequency: Arc::new(Mutex::new("1200000".to_string())),
+        }
+    }
+}
+
+impl CpuControl for FakeCpu {
+    fn set_cpu_governor(&self, governor: &str) -> Result<()> {
+        if !CPU_GOVERNORS.contains(&governor) {
+            bail!("invalid CPU governor {}", governor);
+        }
+        *self.governor.lock().unwrap() = governor.to_string();
+        Ok(())
+    }
+
+    fn get_cpu_governor(&self) -> Result<String> {
+        Ok(self.governor.lock().unwrap().clone())
+    }
+
+    fn get_cpu_frequency(&self) -> Result<String> {
+        Ok(self.frequency.lock().unwrap().clone())
+    }
+
+    fn set_cpu_frequency(&self, frequency: CpuFrequency) -> Result<()> {
+        let value = match frequency {
+            CpuFrequency::Freq1200000 => "1200000",
+            CpuFrequency::Freq1600000 => "1600000",
+            CpuFrequency::Freq1800000 => "1800000",
+        };
+        *self.frequency.lock().unwrap() = value.to_string();
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FakeMetrics;
+
+impl DeviceMetricsInfo for FakeMetrics {
+    fn get_cpu_usage(&self) -> Result<f32> {
+        Ok(12.5)
+    }
+
+    fn get_memory_usage(&self) -> Result<u64> {
+        Ok(512)
+    }
+
+    fn get_disk_usage(&self) -> Result<u64> {
+        Ok(2048)
+    }
+}
+
+#[derive(Clone)]
+pub struct FakeBattery {
+    pub device: String,
+}
+
+impl Default for FakeBattery {
+    fn default() -> Self {
+        FakeBattery {
+            device: "/sys/class/power_supply/bq27441-0/uevent".to_string(),
+        }
+    }
+}
+
+impl PowerSupplyInfo for FakeBattery {
+    fn info(&self) -> Result<PowerSupplyData> {
+        Ok(PowerSupplyData {
+            name: "bq27441-0".to_string(),
+            r#type: "Battery".to_string(),
+            status: "Discharging".to_string(),
+            present: true,
+            voltage_now: 3912000,
+            current_now: -245000,
+            capacity: 76,
+            capacity_level: "Normal".to_string(),
+            temp: 287,
+            technology: "Li-ion".to_string(),
+            charge_full: 2830000,
+            charge_now: 2150000,
+            charge_full_design: 3000000,
+            manufacturer: "Texas Instruments".to_string(),
+        })
+    }
+
+    fn set_device(&mut self, device: &str) -> Result<()> {
+        self.device = device.to_string();
+        Ok(())
+    }
+
+    fn get_device(&self) -> Result<String> {
+        Ok(self.device.clone())
+    }
+
+    fn get_current(&self) -> Result<i64> {
+        Ok(-245000)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FakeWifi {
+    pub networks: Arc<Mutex<Vec<NetworkResult>>>,
+}
+
+impl FakeWifi {
+    fn scan_result(name: &str) -> ScanResult {
+        ScanResult {
+            mac: "00:11:22:33:44:55".to_string(),
+            frequency: "2412".to_string(),
+            signal: -42,
+            flags: "[WPA2-PSK-CCMP][ESS]".to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl WifiControl for FakeWifi {
+    fn wifi_status(&self) -> bool {
+        true
+    }
+
+    async fn scan_wireless_network(&self) -> Result<Vec<ScanResult>> {
+        Ok(vec![
+            Self::scan_result("mecha"),
+            Self::scan_result("mecha-guest"),
+        ])
+    }
+
+    async fn get_known_wifi_list(&self) -> Result<Vec<NetworkResult>> {
+        Ok(self.networks.lock().unwrap().clone())
+    }
+
+    async fn current_wifi_network(&self) -> Result<ScanResult> {
+        match self.networks.lock().unwrap().first() {
+            Some(network) => Ok(Self::scan_result(&network.ssid)),
+            None => bail!("not connected"),
+        }
+    }
+
+    async fn connect_wireless_network(&self, ssid: &str, psk: &str) -> Result<()> {
+        if psk.len() < 8 {
+            bail!("invalid psk");
+        }
+        let mut networks = self.networks.lock().unwrap();
+        let network_id = networks.len();
+        networks.push(NetworkResult {
+            network_id,
+            ssid: ssid.to_string(),
+            flags: "[CURRENT]".to_string(),
+        });
+        Ok(())
+    }
+
+    async fn remove_wireless_network(&self, network_id: usize) -> Result<()> {
+        let mut networks = self.networks.lock().unwrap();
+        match networks.iter().position(|n| n.network_id == network_id) {
+            Some(index) => {
+                networks.remove(index);
+                Ok(())
+            }
+            None => bail!("unknown network id {}", network_id),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FakeBluetooth {
+    pub powered: Arc<Mutex<bool>>,
+}
+
+#[tonic::async_trait]
+impl BluetoothControl for FakeBluetooth {
+    async fn bluetooth_status(&self) -> Result<bool> {
+        Ok(*self.powered.lock().unwrap())
+    }
+
+    async fn enable_bluetooth(&self) -> Result<()> {
+        *self.powered.lock().unwrap() = true;
+        Ok(())
+    }
+
+    async fn disable_bluetooth(&self) -> Result<()> {
+        *self.powered.lock().unwrap() = false;
+        Ok(())
+    }
+}
+
+// answers with deterministic strings built from the arguments, an empty oid
+// is treated as a missing slot
+#[derive(Clone, Default)]
+pub struct FakeTrustZone;
+
+impl TrustZoneControl for FakeTrustZone {
+    fn read_trustzone_cert(&self, _output_file: &str, region: &str) -> Result<String> {
+        Ok(format!("cert:{}", region))
+    }
+
+    fn write_trustzone_cert(&self, _cert_file: &str, oid: &str) -> Result<()> {
+        if oid.is_empty() {
+            bail!("missing oid");
+        }
+        Ok(())
+    }
+
+    fn remove_trustzone_cert(&self, oid: &str) -> Result<()> {
+        if oid.is_empty() {
+            bail!("missing oid");
+        }
+        Ok(())
+    }
+
+    fn generate_trustzone_key(
+        &self,
+        oid: &str,
+        key_type: KeyType,
+        key_size: KeySize,
+        _output_file: &str,
+    ) -> Result<String> {
+        Ok(format!("pubkey:{}:{:?}:{:?}", oid, key_type, key_size))
+    }
+
+    fn sign_trustzone_data(
+        &self,
+        key_oid: &str,
+        input_file: &str,
+        _output_file: &str,
+        _hash_before_sign: bool,
+    ) -> Result<String> {
+        Ok(format!("signed:{}:{}", key_oid, input_file))
+    }
+
+    fn verify_trustzone_data(
+        &self,
+        _pubkey_file: &str,
+        _input_file: &str,
+        signature_file: &str,
+        _hash_before_verify: bool,
+    ) -> Result<String> {
+        if signature_file.is_empty() {
+            bail!("missing signature");
+        }
+        Ok("verified".to_string())
+    }
+
+    fn derive_trustzone_key(
+        &self,
+        secret_oid: &str,
+        hkdf_type: u16,
+        _info_file: &str,
+        _salt_file: &str,
+        _output_file: &str,
+    ) -> Result<String> {
+        Ok(format!("derived:{}:{}", secret_oid, hkdf_type))
+    }
+
+    fn generate_trustzone_hmac(
+        &self,
+        secret_oid: &str,
+        hmac_type: u16,
+        input_data: &str,
+        _output_file: &str,
+    ) -> Result<String> {
+        Ok(format!("hmac:{}:{}:{}", secret_oid, hmac_type, input_data))
+    }
+}
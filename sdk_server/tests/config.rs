@@ -0,0 +1,349 @@
+mod common;
+
+use common::TempDir;
+use mecha_sdk_server::configs::{BaseConfig, BoardProfiles, LogFormat, Severity};
+use mecha_simulator::{SimulatedBoard, SysfsRoot};
+use std::path::PathBuf;
+
+// the directory holds the config until it is dropped
+fn write_config(name: &str, yaml: &str) -> (TempDir, PathBuf) {
+    let dir = TempDir::new(&format!("config-{}", name));
+    let path = dir.join("Config.yaml");
+    std::fs::write(&path, yaml).unwrap();
+    (dir, path)
+}
+
+fn issues(config: &BaseConfig) -> Vec<(Severity, String)> {
+    config
+        .check()
+        .issues
+        .into_iter()
+        .map(|issue| (issue.severity, issue.key))
+        .collect()
+}
+
+#[test]
+fn shipped_config_is_valid_against_the_simulated_board() {
+    let dir = TempDir::new("config-sim");
+    let root = SysfsRoot::new(dir.path());
+    SimulatedBoard::new(&root).generate().unwrap();
+
+    let mut config =
+        BaseConfig::load(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Config.yaml")).unwrap();
+    config.simulation.enabled = true;
+    config.simulation.root = root.path().display().to_string();
+    config.resolve_interfaces().unwrap();
+    assert_eq!(config.name, "mecha-compute-g1");
+
+    // the audio sample is not part of the board tree
+    assert_eq!(
+        issues(&config),
+        vec![(Severity::Warning, "interfaces.audio.audio_file".to_string())]
+    );
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn unknown_keys_and_missing_interfaces_are_errors() {
+    let (_dir, path) = write_config(
+        "unknown",
+        r#"
+name: mecha-compute-g1
+server:
+  port: 0
+  colour: blue
+interfaces:
+  display:
+    device: /nonexistent/brightness
+  led:
+    red_led: /nonexistent/red
+    green_led: /nonexistent/green
+    blue_led: /nonexistent/blue
+"#,
+    );
+    let config = BaseConfig::load(&path).unwrap();
+    let issues = issues(&config);
+
+    for key in [
+        "server.colour",
+        "server.port",
+        "interfaces.motion_sensor.x_axis",
+        "interfaces.battery.device",
+    ] {
+        assert!(
+            issues.contains(&(Severity::Error, key.to_string())),
+            "{} not reported as error in {:?}",
+            key,
+            issues
+        );
+    }
+    assert!(issues.contains(&(Severity::Warning, "interfaces.display.device".to_string())));
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn parse_errors_name_the_key() {
+    let (_dir, path) = write_config("parse", "server:\n  port: abc\ninterfaces: {}\n");
+    let err = BaseConfig::load(&path).unwrap_err();
+    assert!(format!("{:#}", err).contains("server.port"));
+}
+
+#[test]
+fn disabled_services_do_not_require_interfaces() {
+    let (_dir, path) = write_config(
+        "services",
+        r#"
+server:
+  port: 50052
+services:
+  display:
+    enabled: false
+  motion_sensor:
+    enabled: false
+  battery:
+    enabled: false
+  bluetooth:
+    probe: false
+interfaces:
+  led:
+    red_led: /nonexistent/red
+    green_led: /nonexistent/green
+    blue_led: /nonexistent/blue
+"#,
+    );
+    let config = BaseConfig::load(&path).unwrap();
+    assert!(!config.services.display.enabled);
+    assert!(config.services.bluetooth.enabled && !config.services.bluetooth.probe);
+    assert!(config.services.trustzone.enabled && config.services.trustzone.probe);
+
+    let issues = issues(&config);
+    assert!(
+        issues
+            .iter()
+            .all(|(severity, _)| *severity == Severity::Warning),
+        "unexpected errors in {:?}",
+        issues
+    );
+    assert!(config.validate().is_ok());
+}
+
+#[test]
+fn gateway_origins_must_be_header_values() {
+    let (_dir, path) = write_config(
+        "gateway",
+        r#"
+server:
+  port: 50052
+  gateway:
+    listen: 127.0.0.1:0
+    cors:
+      allowed_origins: ["https://dashboard.example.com", "bad\norigin"]
+interfaces: {}
+"#,
+    );
+    let config = BaseConfig::load(&path).unwrap();
+    assert_eq!(config.server.gateway.as_ref().unwrap().cors.max_age, 3600);
+    let issues = issues(&config);
+    for key in ["server.gateway.listen", "server.gateway.cors.allowed_origins"] {
+        assert!(
+            issues.contains(&(Severity::Error, key.to_string())),
+            "{} not reported as error in {:?}",
+            key,
+            issues
+        );
+    }
+}
+
+#[test]
+fn logging_defaults_and_bad_filters() {
+    let (_dir, path) = write_config(
+        "logging-default",
+        "server:\n  port: 50052\ninterfaces: {}\n",
+    );
+    let config = BaseConfig::load(&path).unwrap();
+    assert_eq!(config.logging.level, "info");
+    assert_eq!(config.logging.format, LogFormat::Text);
+    assert!(config.logging.otlp.is_none());
+
+    let (_dir, path) = write_config(
+        "logging",
+        r#"
+server:
+  port: 50052
+logging:
+  level: info,mecha_display_ctrl=[
+  format: json
+  otlp:
+    endpoint: "http://collector:4317 "
+interfaces: {}
+"#,
+    );
+    let config = BaseConfig::load(&path).unwrap();
+    assert_eq!(config.logging.format, LogFormat::Json);
+    let otlp = config.logging.otlp.as_ref().unwrap();
+    assert_eq!(otlp.service_name, "mecha_sdk_server");
+    let issues = issues(&config);
+    for key in ["logging.level", "logging.otlp.endpoint"] {
+        assert!(
+            issues.contains(&(Severity::Error, key.to_string())),
+            "{} not reported as error in {:?}",
+            key,
+            issues
+        );
+    }
+}
+
+#[test]
+fn audit_needs_an_existing_directory() {
+    let (_dir, path) = write_config(
+        "audit",
+        r#"
+server:
+  port: 50052
+audit:
+  path: /nonexistent/mecha/audit.log
+  keep: 0
+interfaces: {}
+"#,
+    );
+    let config = BaseConfig::load(&path).unwrap();
+    assert_eq!(config.audit.as_ref().unwrap().max_size, 10 * 1024 * 1024);
+    let issues = issues(&config);
+    for key in ["audit.path", "audit.keep"] {
+        assert!(
+            issues.contains(&(Severity::Error, key.to_string())),
+            "{} not reported as error in {:?}",
+            key,
+            issues
+        );
+    }
+}
+
+#[test]
+fn bundled_profiles_cover_the_required_interfaces() {
+    let profiles = BoardProfiles::bundled();
+    assert_eq!(
+        profiles.names(),
+        vec!["mecha-compute-g1", "mecha-compute-g2"]
+    );
+    for name in profiles.names() {
+        // paths or selectors, resolved once the board is known
+        let interfaces = profiles.get(name).unwrap().interfaces(&Default::default());
+        for (section, entry) in [
+            ("display", "device"),
+            ("led", "red_led"),
+            ("motion_sensor", "x_axis"),
+            ("battery", "device"),
+        ] {
+            let value = &interfaces[section][entry];
+            assert!(
+                value
+                    .as_str()
+                    .map_or(value.is_mapping(), |path| path.starts_with("/sys/")),
+                "{}: {}.{} = {:?}",
+                name,
+                section,
+                entry,
+                value
+            );
+        }
+    }
+    let g2 = profiles.get("mecha-compute-g2").unwrap();
+    assert_eq!(
+        profiles
+            .detect(&["mecha,compute-g2".to_string(), "fsl,imx8mp".to_string()])
+            .map(|profile| profile.name.as_str()),
+        Some(g2.name.as_str())
+    );
+    assert!(profiles.detect(&["fsl,imx8mm".to_string()]).is_none());
+}
+
+#[test]
+fn user_profiles_and_config_keys_override_bundled_profiles() {
+    let root = TempDir::new("profiles");
+    let profiles_dir = root.join("profiles");
+    std::fs::create_dir_all(&profiles_dir).unwrap();
+    std::fs::write(
+        profiles_dir.join("mecha-compute-g1.yaml"),
+        r#"
+name: mecha-compute-g1
+compatible:
+  - mecha,compute-g1
+interfaces:
+  battery:
+    device: /sys/class/power_supply/bq27441-1/uevent
+  led:
+    red_led: /sys/class/leds/red:status/brightness
+"#,
+    )
+    .unwrap();
+    let compatible = root.join("proc/device-tree/compatible");
+    std::fs::create_dir_all(compatible.parent().unwrap()).unwrap();
+    std::fs::write(&compatible, b"mecha,compute-g1\0fsl,imx8mm\0").unwrap();
+
+    let (_dir, path) = write_config(
+        "profiles",
+        &format!(
+            r#"
+name: auto
+profiles_dir: {}
+server:
+  port: 50052
+interfaces:
+  led:
+    green_led: /sys/class/leds/green:status/brightness
+"#,
+            profiles_dir.display()
+        ),
+    );
+    let mut config = BaseConfig::load(&path).unwrap();
+    config.apply_profile(&SysfsRoot::new(root.path())).unwrap();
+    assert_eq!(config.name, "mecha-compute-g1");
+    // the user profile replaces the bundled one, the config file wins over both
+    let interfaces = &config.interfaces;
+    assert_eq!(
+        interfaces.battery.device,
+        "/sys/class/power_supply/bq27441-1/uevent"
+    );
+    assert_eq!(
+        interfaces.led.red_led,
+        "/sys/class/leds/red:status/brightness"
+    );
+    assert_eq!(
+        interfaces.led.green_led,
+        "/sys/class/leds/green:status/brightness"
+    );
+    assert!(interfaces.display.device.is_empty());
+
+    // another board has no profile and keeps the interfaces of the file
+    std::fs::write(&compatible, b"fsl,imx8mp-evk\0fsl,imx8mp\0").unwrap();
+    let mut config = BaseConfig::load(&path).unwrap();
+    config.apply_profile(&SysfsRoot::new(root.path())).unwrap();
+    assert_eq!(config.name, "auto");
+    assert_eq!(
+        config.interfaces.led.green_led,
+        "/sys/class/leds/green:status/brightness"
+    );
+    assert_ne!(
+        config.interfaces.battery.device,
+        "/sys/class/power_supply/bq27441-1/uevent"
+    );
+
+    // an unknown name keeps the interfaces of the file
+    let (_custom, custom) = write_config(
+        "profiles-custom",
+        r#"
+name: custom-board
+server:
+  port: 50052
+interfaces:
+  display:
+    device: /sys/class/backlight/custom/brightness
+"#,
+    );
+    let mut config = BaseConfig::load(&custom).unwrap();
+    config.apply_profile(&SysfsRoot::default()).unwrap();
+    assert_eq!(config.name, "custom-board");
+    assert!(config.interfaces.battery.device.is_empty());
+    assert!(issues(&config).contains(&(Severity::Warning, "name".to_string())));
+}
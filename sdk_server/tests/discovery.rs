@@ -0,0 +1,169 @@
+mod common;
+
+use common::TempDir;
+use mecha_sdk_server::configs::{BaseConfig, Discovery, Selector, Severity};
+use mecha_simulator::{SimulatedBoard, SysfsRoot};
+use std::fs;
+
+fn simulated_board(dir: &TempDir) -> SysfsRoot {
+    let root = SysfsRoot::new(dir.path());
+    SimulatedBoard::new(&root).generate().unwrap();
+    root
+}
+
+fn resolve(discovery: &Discovery, selector: &str) -> anyhow::Result<String> {
+    discovery.resolve(&serde_yaml::from_str::<Selector>(selector).unwrap())
+}
+
+#[test]
+fn selectors_follow_the_devices_not_their_numbering() {
+    let dir = TempDir::new("discovery");
+    let root = simulated_board(&dir);
+    let discovery = Discovery::new(&root);
+
+    let gyro = "{iio: {name: bmi088_gyro}, attribute: in_anglvel_x_raw}";
+    assert_eq!(
+        resolve(&discovery, gyro).unwrap(),
+        "/sys/bus/iio/devices/iio:device1/in_anglvel_x_raw"
+    );
+    assert_eq!(
+        resolve(&discovery, "{backlight: backlight}").unwrap(),
+        "/sys/class/backlight/backlight/brightness"
+    );
+    assert_eq!(
+        resolve(&discovery, "{led: green-led, attribute: trigger}").unwrap(),
+        "/sys/class/leds/green-led/trigger"
+    );
+    assert_eq!(
+        resolve(&discovery, "{power_supply: {type: Battery}}").unwrap(),
+        "/sys/class/power_supply/bq27441-0/uevent"
+    );
+    assert_eq!(
+        resolve(&discovery, "{cpufreq: 0}").unwrap(),
+        "/sys/devices/system/cpu/cpu0/cpufreq"
+    );
+
+    // the kernel probed the devices in the other order
+    let iio = root.path().join("sys/bus/iio/devices");
+    fs::rename(iio.join("iio:device0"), iio.join("iio:device2")).unwrap();
+    fs::rename(iio.join("iio:device1"), iio.join("iio:device0")).unwrap();
+    assert_eq!(
+        resolve(&discovery, gyro).unwrap(),
+        "/sys/bus/iio/devices/iio:device0/in_anglvel_x_raw"
+    );
+
+    // policies win over the per cpu directory
+    let policy = root.path().join("sys/devices/system/cpu/cpufreq/policy0");
+    fs::create_dir_all(&policy).unwrap();
+    fs::write(policy.join("affected_cpus"), "0 1 2 3\n").unwrap();
+    assert_eq!(
+        resolve(&discovery, "{cpufreq: 2}").unwrap(),
+        "/sys/devices/system/cpu/cpufreq/policy0"
+    );
+    assert!(resolve(&discovery, "{cpufreq: 4}").is_err());
+
+    let err = resolve(&discovery, "{iio: {name: bmi088_gyro}}").unwrap_err();
+    assert!(err.to_string().contains("needs an attribute"), "{}", err);
+    let err = resolve(&discovery, "{led: red-led, backlight: backlight}").unwrap_err();
+    assert!(err.to_string().contains("exactly one"), "{}", err);
+}
+
+#[test]
+fn config_selectors_resolve_at_startup_and_misses_are_warnings() {
+    let dir = TempDir::new("discovery-config");
+    let root = simulated_board(&dir);
+    let path = dir.join("Config.yaml");
+    fs::write(
+        &path,
+        format!(
+            r#"
+server:
+  port: 50052
+simulation:
+  enabled: true
+  root: {}
+interfaces:
+  display:
+    device: {{backlight: backlight}}
+  led:
+    red_led: {{led: red-led}}
+    green_led: {{led: green-led}}
+    blue_led: {{led: blue-led}}
+  motion_sensor:
+    x_axis: {{iio: {{name: bmi160}}, attribute: in_anglvel_x_raw}}
+    y_axis: {{iio: {{name: bmi088_gyro}}, attribute: in_anglvel_y_raw}}
+    z_axis: {{iio: {{name: bmi088_gyro}}, attribute: in_anglvel_z_raw}}
+  cpu:
+    device: {{cpufreq: 4}}
+  battery:
+    device: {{power_supply: bq27441-0}}
+    current: {{power_supply: bq27441-0, attribute: current_now}}
+"#,
+            root.path().display()
+        ),
+    )
+    .unwrap();
+
+    let mut config = BaseConfig::load(&path).unwrap();
+    assert!(config.unknown_keys.is_empty(), "{:?}", config.unknown_keys);
+    assert!(config.interfaces.display.device.is_empty());
+    config.resolve_interfaces().unwrap();
+
+    let interfaces = &config.interfaces;
+    assert_eq!(
+        interfaces.display.device,
+        root.rebase("/sys/class/backlight/backlight/brightness")
+    );
+    assert_eq!(
+        interfaces.motion_sensor.y_axis,
+        root.rebase("/sys/bus/iio/devices/iio:device1/in_anglvel_y_raw")
+    );
+    assert_eq!(
+        interfaces.battery.current,
+        root.rebase("/sys/class/power_supply/bq27441-0/current_now")
+    );
+    assert!(interfaces.motion_sensor.x_axis.is_empty());
+
+    let unresolved = config
+        .unresolved
+        .iter()
+        .map(|(key, _)| key.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        unresolved,
+        vec!["interfaces.motion_sensor.x_axis", "interfaces.cpu.device"]
+    );
+    // a missing device warns like a missing path
+    let issues = config.check().issues;
+    for key in unresolved {
+        assert!(
+            issues
+                .iter()
+                .any(|issue| issue.key == key && issue.severity == Severity::Warning),
+            "{} not reported as warning in {:?}",
+            key,
+            issues
+        );
+        assert!(!issues
+            .iter()
+            .any(|issue| issue.key == key && issue.severity == Severity::Error));
+    }
+
+    // a malformed selector is a config error, not a missing device
+    let yaml = fs::read_to_string(&path).unwrap();
+    fs::write(
+        &path,
+        yaml.replace(
+            "attribute: in_anglvel_z_raw",
+            "attribute: in_anglvel_z_raw, colour: blue",
+        ),
+    )
+    .unwrap();
+    let mut config = BaseConfig::load(&path).unwrap();
+    let err = config.resolve_interfaces().unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("invalid selector interfaces.motion_sensor.z_axis"),
+        "{:#}",
+        err
+    );
+}
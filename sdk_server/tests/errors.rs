@@ -0,0 +1,148 @@
+mod common;
+
+use anyhow::Context;
+use common::{connect, TempDir};
+use mecha_display_ctrl::{DisplayError, DisplayErrorCodes};
+use mecha_errors::error_info;
+use mecha_motion_sensor::{MotionSensor, MotionSensorControl};
+use mecha_sdk_server::services::{
+    display_manager_service::displaymanager::{
+        display_ctrl_service_client::DisplayCtrlServiceClient, SetBrightnessRequest,
+    },
+    into_status, Battery, CpuControl, CpuCtrl, DisplayCtrl, DisplayCtrlManager,
+    DisplayCtrlServiceServer, LedColor, LedControl, LedCtrl, PowerSupplyInfo,
+};
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use tonic::{transport::Server, Code};
+
+async fn display_client(path: &str) -> DisplayCtrlServiceClient<tonic::transport::Channel> {
+    let channel = connect(Server::builder().add_service(DisplayCtrlServiceServer::new(
+        DisplayCtrlManager {
+            display_ctrl: DisplayCtrl::new(path).into(),
+        },
+    )))
+    .await;
+    DisplayCtrlServiceClient::new(channel)
+}
+
+#[tokio::test]
+async fn device_errors_map_to_grpc_codes_with_error_info() {
+    let dir = TempDir::new("brightness");
+    let path = dir.join("brightness");
+    let mut client = display_client(path.to_str().unwrap()).await;
+
+    let status = client
+        .set_brightness(SetBrightnessRequest { brightness: 250 })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(status.message(), "invalid brightness value");
+    let info = error_info(&status).unwrap();
+    assert_eq!(info.domain, "mecha_display_ctrl");
+    assert_eq!(info.reason, "InvalidBrightnessValueError");
+
+    client
+        .set_brightness(SetBrightnessRequest { brightness: 100 })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn missing_sysfs_attributes_are_unavailable() {
+    let mut client = display_client("/nonexistent/backlight/brightness").await;
+
+    let status = client
+        .set_brightness(SetBrightnessRequest { brightness: 100 })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(
+        error_info(&status).unwrap().reason,
+        "InvalidBrightnessPathError"
+    );
+}
+
+#[test]
+fn permission_errors_override_the_domain_code() {
+    let err = Err::<(), _>(io::Error::from(io::ErrorKind::PermissionDenied))
+        .context(DisplayError::new(
+            DisplayErrorCodes::InvalidBrightnessPathError,
+            "failed to open brightness file".to_string(),
+        ))
+        .unwrap_err();
+    let status = into_status(err);
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(error_info(&status).unwrap().domain, "mecha_display_ctrl");
+}
+
+#[test]
+fn unreadable_sysfs_attributes_are_permission_denied() {
+    let dir = TempDir::new("denied");
+    let attribute = |name: &str| {
+        let path = dir.join(name);
+        fs::write(&path, "0\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+        path.to_str().unwrap().to_string()
+    };
+    let governor = attribute("scaling_governor");
+    attribute("scaling_available_governors");
+    let brightness = attribute("brightness");
+    let uevent = attribute("uevent");
+    let axis = attribute("in_accel_x_raw");
+
+    // root ignores file modes, nothing to check
+    if fs::read(&governor).is_ok() {
+        return;
+    }
+
+    let cpu = CpuCtrl::with_path(dir.path().to_str().unwrap());
+    let errors = [
+        cpu.get_cpu_governor().unwrap_err(),
+        cpu.set_cpu_governor("userspace").unwrap_err(),
+        LedCtrl::new(&brightness, &brightness, &brightness)
+            .set_led(LedColor::Red)
+            .unwrap_err(),
+        Battery {
+            path: uevent,
+            currnet_now: String::new(),
+        }
+        .info()
+        .unwrap_err(),
+        MotionSensor::new(&axis, &axis, &axis)
+            .read_motion_sensor_value()
+            .unwrap_err(),
+    ];
+    for err in errors {
+        let status = into_status(err);
+        assert_eq!(status.code(), Code::PermissionDenied, "{:?}", status);
+        assert!(error_info(&status).is_some());
+    }
+}
+
+#[test]
+fn governors_the_cpu_does_not_offer_are_invalid_arguments() {
+    let dir = TempDir::new("governors");
+    fs::write(
+        dir.join("scaling_available_governors"),
+        "ondemand userspace\n",
+    )
+    .unwrap();
+    let cpu = CpuCtrl::with_path(dir.path().to_str().unwrap());
+
+    let status = into_status(cpu.set_cpu_governor("powersave").unwrap_err());
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(error_info(&status).unwrap().reason, "InvalidCpuGovernor");
+    assert!(!dir.join("scaling_governor").exists());
+
+    cpu.set_cpu_governor("ondemand").unwrap();
+    assert_eq!(cpu.get_cpu_governor().unwrap(), "ondemand");
+}
+
+#[test]
+fn plain_errors_stay_unknown() {
+    let status = into_status(anyhow::anyhow!("something broke"));
+    assert_eq!(status.code(), Code::Unknown);
+    assert!(error_info(&status).is_none());
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use common::{connect, FakeBluetooth, FakeDisplay, FakeMotion};
+use mecha_network_manager::wifi::Broadcast;
+use mecha_sdk_server::events::{
+    forward_wifi, watch_bluetooth, watch_brightness, watch_motion, EventBus,
+};
+use mecha_sdk_server::services::event_service::events::event_service_client::EventServiceClient;
+use mecha_sdk_server::services::event_service::{
+    BatteryStatusChanged, BrightnessChanged, Event, Payload, SubscribeRequest, WifiState,
+    WifiStateChanged,
+};
+use mecha_sdk_server::services::{Device, EventManager, EventServiceServer};
+use mecha_sdk_server::shutdown::Shutdown;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tonic::transport::{Channel, Server};
+use tonic::Code;
+
+const INTERVAL: Duration = Duration::from_millis(10);
+
+async fn client(events: &EventManager) -> EventServiceClient<Channel> {
+    let router = Server::builder().add_service(EventServiceServer::new(events.clone()));
+    EventServiceClient::new(connect(router).await)
+}
+
+fn topics(topics: &[&str]) -> SubscribeRequest {
+    SubscribeRequest {
+        topics: topics.iter().map(|topic| topic.to_string()).collect(),
+    }
+}
+
+async fn next(receiver: &mut broadcast::Receiver<Event>) -> Payload {
+    tokio::time::timeout(Duration::from_secs(2), receiver.recv())
+        .await
+        .expect("no event published")
+        .unwrap()
+        .payload
+        .unwrap()
+}
+
+#[tokio::test]
+async fn subscribers_only_receive_their_topics() {
+    let events = EventManager::default();
+    let mut client = client(&events).await;
+    let mut stream = client
+        .subscribe(topics(&["display"]))
+        .await
+        .unwrap()
+        .into_inner();
+
+    events.bus.publish(Payload::Battery(BatteryStatusChanged {
+        status: "Charging".to_string(),
+        capacity: 50,
+    }));
+    events
+        .bus
+        .publish(Payload::Brightness(BrightnessChanged { brightness: 80 }));
+
+    let event = stream.message().await.unwrap().unwrap();
+    assert_eq!(event.topic, "display");
+    assert!(event.timestamp > 0);
+    assert_eq!(
+        event.payload,
+        Some(Payload::Brightness(BrightnessChanged { brightness: 80 }))
+    );
+}
+
+#[tokio::test]
+async fn unknown_topics_are_rejected() {
+    let events = EventManager::default();
+    let status = client(&events)
+        .await
+        .subscribe(topics(&["display", "temperature"]))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert!(status.message().contains("temperature"));
+}
+
+#[tokio::test]
+async fn streams_end_on_shutdown() {
+    let events = EventManager {
+        bus: EventBus::default(),
+        shutdown: Shutdown::new(),
+    };
+    let mut stream = client(&events)
+        .await
+        .subscribe(topics(&[]))
+        .await
+        .unwrap()
+        .into_inner();
+
+    events.shutdown.trigger();
+    let end = tokio::time::timeout(Duration::from_secs(2), stream.message())
+        .await
+        .expect("stream still open");
+    assert!(end.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn watchers_publish_state_changes() {
+    let bus = EventBus::default();
+    let mut receiver = bus.subscribe();
+    let display = FakeDisplay::default();
+    let motion = FakeMotion::default();
+    let bluetooth = FakeBluetooth::default();
+    tokio::spawn(watch_brightness(
+        Device::from(display.clone()),
+        bus.clone(),
+        INTERVAL,
+    ));
+    tokio::spawn(watch_motion(
+        Device::from(motion.clone()),
+        bus.clone(),
+        INTERVAL,
+    ));
+    tokio::spawn(watch_bluetooth(bluetooth.clone(), bus.clone(), INTERVAL));
+    // the first reads only set the baseline
+    tokio::time::sleep(INTERVAL * 5).await;
+
+    *display.brightness.lock().unwrap() = 120;
+    assert_eq!(
+        next(&mut receiver).await,
+        Payload::Brightness(BrightnessChanged { brightness: 120 })
+    );
+
+    *motion.value.lock().unwrap() = (1.0, 2.0, 3.0);
+    let Payload::Motion(motion_detected) = next(&mut receiver).await else {
+        panic!("expected a motion event");
+    };
+    assert_eq!(motion_detected.z_value, 3.0);
+
+    *bluetooth.powered.lock().unwrap() = true;
+    let Payload::Bluetooth(power) = next(&mut receiver).await else {
+        panic!("expected a bluetooth event");
+    };
+    assert!(power.enabled);
+
+    // ongoing motion is reported once
+    tokio::time::sleep(INTERVAL * 5).await;
+    assert!(receiver.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn wifi_broadcasts_are_forwarded() {
+    let bus = EventBus::default();
+    let mut receiver = bus.subscribe();
+    let (sender, broadcasts) = broadcast::channel(8);
+    let forwarding = tokio::spawn(forward_wifi(broadcasts, bus.clone()));
+
+    sender.send(Broadcast::Ready).unwrap();
+    sender.send(Broadcast::Connected).unwrap();
+    sender.send(Broadcast::WrongPsk).unwrap();
+    drop(sender);
+    forwarding.await.unwrap();
+
+    for state in [WifiState::Connected, WifiState::WrongPsk] {
+        assert_eq!(
+            next(&mut receiver).await,
+            Payload::Wifi(WifiStateChanged {
+                state: state.into()
+            })
+        );
+    }
+}
@@ -0,0 +1,348 @@
+mod common;
+
+use common::{FakeDisplay, FakeLed, FakeTrustZone};
+use mecha_sdk_server::configs::Cors;
+use mecha_sdk_server::gateway::{cors_layer, RestLayer, RestRoutes};
+use mecha_sdk_server::middleware::AuthLayer;
+use mecha_sdk_server::services::{
+    DisplayCtrlManager, DisplayCtrlServiceServer, LedCtrlManager, LedCtrlServiceServer,
+    TrustZoneCtrlServiceManager, TrustZoneCtrlServiceServer, FILE_DESCRIPTOR_SET,
+};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::codegen::http::{HeaderMap, Request, Response};
+use tonic::transport::{Body, Server};
+use tonic_web::GrpcWebLayer;
+use tower::{service_fn, Layer, ServiceBuilder, ServiceExt};
+
+struct Reply {
+    status: u16,
+    headers: String,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+async fn gateway(display: FakeDisplay, led: FakeLed) -> SocketAddr {
+    let cors = Cors {
+        allowed_origins: vec!["https://dashboard.example.com".to_string()],
+        max_age: 600,
+    };
+    let layers = ServiceBuilder::new()
+        .layer(cors_layer(&cors).unwrap())
+        .layer(RestLayer::new(
+            RestRoutes::new(FILE_DESCRIPTOR_SET).unwrap(),
+        ))
+        .layer(GrpcWebLayer::new())
+        .layer(AuthLayer::default())
+        .into_inner();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let serve = Server::builder()
+        .accept_http1(true)
+        .layer(layers)
+        .add_service(DisplayCtrlServiceServer::new(DisplayCtrlManager {
+            display_ctrl: display.into(),
+        }))
+        .add_service(LedCtrlServiceServer::new(LedCtrlManager {
+            led_ctrl: led.into(),
+        }))
+        .add_service(TrustZoneCtrlServiceServer::new(
+            TrustZoneCtrlServiceManager {
+                trustzone_ctrl: FakeTrustZone.into(),
+            },
+        ))
+        .serve_with_incoming(TcpListenerStream::new(listener));
+    tokio::spawn(serve);
+    addr
+}
+
+// sends a rest request to a grpc service that answers every call with
+// `frame`, returns the http status and the headers the grpc call carried
+async fn transcode(request: Request<Body>, frame: &'static [u8]) -> (u16, HeaderMap) {
+    let called = Arc::new(Mutex::new(HeaderMap::new()));
+    let grpc = {
+        let called = called.clone();
+        service_fn(move |request: Request<Body>| {
+            *called.lock().unwrap() = request.headers().clone();
+            async move { Ok::<_, Infallible>(Response::new(tonic::body::boxed(Body::from(frame)))) }
+        })
+    };
+    let response = RestLayer::new(RestRoutes::new(FILE_DESCRIPTOR_SET).unwrap())
+        .layer(grpc)
+        .oneshot(request)
+        .await
+        .unwrap();
+    let headers = called.lock().unwrap().clone();
+    (response.status().as_u16(), headers)
+}
+
+// one http/1.1 request per connection
+async fn send(addr: SocketAddr, head: &str, body: &[u8]) -> Reply {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "{}\r\nhost: localhost\r\nconnection: close\r\ncontent-length: {}\r\n\r\n",
+        head,
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    stream.write_all(body).await.unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.unwrap();
+
+    let split = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .unwrap();
+    let headers = String::from_utf8(response[..split].to_vec())
+        .unwrap()
+        .to_lowercase();
+    let mut body = response[split + 4..].to_vec();
+    if headers.contains("transfer-encoding: chunked") {
+        body = dechunk(&body);
+    }
+    Reply {
+        status: headers[9..12].parse().unwrap(),
+        headers,
+        body,
+    }
+}
+
+fn dechunk(mut chunked: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    loop {
+        let line = chunked.windows(2).position(|w| w == b"\r\n").unwrap();
+        let size = std::str::from_utf8(&chunked[..line]).unwrap();
+        let size = usize::from_str_radix(size.trim(), 16).unwrap();
+        if size == 0 {
+            return body;
+        }
+        body.extend_from_slice(&chunked[line + 2..line + 2 + size]);
+        chunked = &chunked[line + 4 + size..];
+    }
+}
+
+#[tokio::test]
+async fn rest_routes_are_read_from_the_proto_options() {
+    let routes = RestRoutes::new(FILE_DESCRIPTOR_SET).unwrap();
+    let route = routes
+        .routes()
+        .iter()
+        .find(|route| route.path == "/v1/led/{color}" && route.method == "POST")
+        .unwrap();
+    assert_eq!(route.grpc_path(), "/led_ctrl.LedCtrlService/SetLed");
+    // every trustzone rpc has a rest mapping
+    let trustzone = routes
+        .routes()
+        .iter()
+        .filter(|route| route.grpc_path().starts_with("/trustzonectrl."))
+        .count();
+    assert_eq!(trustzone, 8);
+}
+
+#[tokio::test]
+async fn rest_requests_are_transcoded_to_grpc() {
+    let display = FakeDisplay::default();
+    let led = FakeLed::default();
+    let addr = gateway(display.clone(), led.clone()).await;
+
+    let reply = send(
+        addr,
+        "POST /v1/display/brightness HTTP/1.1\r\ncontent-type: application/json",
+        br#"{"brightness": 80}"#,
+    )
+    .await;
+    assert_eq!(reply.status, 200);
+    assert_eq!(*display.brightness.lock().unwrap(), 80);
+
+    let reply = send(addr, "GET /v1/display/brightness HTTP/1.1", b"").await;
+    assert_eq!(reply.status, 200);
+    assert!(reply.headers.contains("content-type: application/json"));
+    assert_eq!(reply.json(), serde_json::json!({"brightness": 80}));
+
+    // path parameters match enum names case-insensitively
+    let reply = send(addr, "POST /v1/led/green HTTP/1.1", b"").await;
+    assert_eq!(reply.status, 200);
+    assert_eq!(*led.lit.lock().unwrap(), [false, true, false]);
+    let reply = send(addr, "DELETE /v1/led/Green HTTP/1.1", b"").await;
+    assert_eq!(reply.status, 200);
+    assert_eq!(*led.lit.lock().unwrap(), [false, false, false]);
+}
+
+#[tokio::test]
+async fn get_requests_take_fields_from_the_query_string() {
+    let addr = gateway(FakeDisplay::default(), FakeLed::default()).await;
+
+    let reply = send(
+        addr,
+        "GET /v1/trustzone/certificate?region=0xE0E0&output_file=%2Ftmp%2Fcert+1.pem HTTP/1.1",
+        b"",
+    )
+    .await;
+    assert_eq!(reply.status, 200);
+    assert_eq!(
+        reply.json(),
+        serde_json::json!({"certificate": "cert:0xE0E0"})
+    );
+
+    let reply = send(
+        addr,
+        "POST /v1/trustzone/key HTTP/1.1\r\ncontent-type: application/json",
+        br#"{"oid": "0xE0F1", "key_type": "SIGN", "key_size": "ECC384"}"#,
+    )
+    .await;
+    assert_eq!(reply.status, 200);
+    assert_eq!(reply.json()["public_key"], "pubkey:0xE0F1:Sign:ECC384");
+
+    let reply = send(
+        addr,
+        "DELETE /v1/trustzone/certificate/0xE0E1 HTTP/1.1",
+        b"",
+    )
+    .await;
+    assert_eq!(reply.status, 200);
+    assert_eq!(reply.json(), serde_json::json!({"success": true}));
+
+    let reply = send(
+        addr,
+        "GET /v1/trustzone/certificate?color=red HTTP/1.1",
+        b"",
+    )
+    .await;
+    assert_eq!(reply.status, 400);
+    assert!(reply.json()["message"]
+        .as_str()
+        .unwrap()
+        .contains("unknown field color"));
+}
+
+#[tokio::test]
+async fn rest_errors_map_to_http_statuses() {
+    let addr = gateway(FakeDisplay::default(), FakeLed::default()).await;
+
+    let reply = send(addr, "GET /v1/nothing HTTP/1.1", b"").await;
+    assert_eq!(reply.status, 404);
+    assert_eq!(reply.json()["code"], 5);
+
+    let reply = send(addr, "POST /v1/led/purple HTTP/1.1", b"").await;
+    assert_eq!(reply.status, 400);
+
+    let reply = send(
+        addr,
+        "POST /v1/display/brightness HTTP/1.1",
+        b"{\"brightness\":",
+    )
+    .await;
+    assert_eq!(reply.status, 400);
+    assert!(reply.json()["message"]
+        .as_str()
+        .unwrap()
+        .contains("invalid json body"));
+
+    // the fake display rejects values above 244
+    let reply = send(
+        addr,
+        "POST /v1/display/brightness HTTP/1.1",
+        br#"{"brightness": 250}"#,
+    )
+    .await;
+    assert_eq!(reply.status, 500);
+    assert_eq!(reply.json()["message"], "invalid brightness value");
+}
+
+#[tokio::test]
+async fn cors_preflight_allows_configured_origins() {
+    let addr = gateway(FakeDisplay::default(), FakeLed::default()).await;
+
+    let reply = send(
+        addr,
+        "OPTIONS /v1/display/brightness HTTP/1.1\r\norigin: https://dashboard.example.com\r\naccess-control-request-method: POST",
+        b"",
+    )
+    .await;
+    assert_eq!(reply.status, 200);
+    assert!(reply
+        .headers
+        .contains("access-control-allow-origin: https://dashboard.example.com"));
+    assert!(reply.headers.contains("access-control-max-age: 600"));
+
+    let reply = send(
+        addr,
+        "OPTIONS /v1/display/brightness HTTP/1.1\r\norigin: https://evil.example.com\r\naccess-control-request-method: POST",
+        b"",
+    )
+    .await;
+    assert!(!reply.headers.contains("access-control-allow-origin"));
+}
+
+#[tokio::test]
+async fn grpc_web_requests_pass_through() {
+    let display = FakeDisplay::default();
+    *display.brightness.lock().unwrap() = 42;
+    let addr = gateway(display, FakeLed::default()).await;
+
+    // an empty GetBrightnessRequest in a grpc-web frame
+    let reply = send(
+        addr,
+        "POST /displaymanager.DisplayCtrlService/GetBrightness HTTP/1.1\r\ncontent-type: application/grpc-web+proto",
+        &[0, 0, 0, 0, 0],
+    )
+    .await;
+    assert_eq!(reply.status, 200);
+    assert!(reply
+        .headers
+        .contains("content-type: application/grpc-web+proto"));
+    // data frame holding `brightness: 42`, followed by the trailer frame
+    assert_eq!(&reply.body[..7], &[0, 0, 0, 0, 2, 8, 42]);
+    assert_eq!(reply.body[7], 0x80);
+}
+
+#[tokio::test]
+async fn grpc_replies_must_hold_one_uncompressed_message() {
+    let brightness = || {
+        Request::get("/v1/display/brightness")
+            .body(Body::empty())
+            .unwrap()
+    };
+    // `brightness: 42`
+    let (status, _) = transcode(brightness(), &[0, 0, 0, 0, 2, 8, 42]).await;
+    assert_eq!(status, 200);
+
+    // compressed flag set
+    let (status, _) = transcode(brightness(), &[1, 0, 0, 0, 2, 8, 42]).await;
+    assert_eq!(status, 500);
+    // a second message
+    let (status, _) = transcode(brightness(), &[0, 0, 0, 0, 2, 8, 42, 0, 0, 0, 0, 0]).await;
+    assert_eq!(status, 500);
+    // trailing bytes and a truncated message
+    let (status, _) = transcode(brightness(), &[0, 0, 0, 0, 2, 8, 42, 7]).await;
+    assert_eq!(status, 500);
+    let (status, _) = transcode(brightness(), &[0, 0, 0, 0, 2, 8]).await;
+    assert_eq!(status, 500);
+}
+
+#[tokio::test]
+async fn auth_and_trace_headers_reach_the_grpc_service() {
+    let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    let request = Request::get("/v1/display/brightness")
+        .header("authorization", "Bearer installer-token")
+        .header("traceparent", traceparent)
+        .header("tracestate", "mecha=1")
+        .header("cookie", "session=1")
+        .body(Body::empty())
+        .unwrap();
+    let (status, headers) = transcode(request, &[0, 0, 0, 0, 2, 8, 42]).await;
+    assert_eq!(status, 200);
+    assert_eq!(headers["authorization"], "Bearer installer-token");
+    assert_eq!(headers["traceparent"], traceparent);
+    assert_eq!(headers["tracestate"], "mecha=1");
+    assert!(headers.get("cookie").is_none());
+}
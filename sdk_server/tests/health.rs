@@ -0,0 +1,199 @@
+mod common;
+
+use common::{connect, FakeDisplay, FakeMetrics, FakeTrustZone, TempDir};
+use mecha_sdk_server::configs::ServiceToggle;
+use mecha_sdk_server::health::{report_health, HardwareProbe, Probe, ServiceGate};
+use mecha_sdk_server::reload::Swappable;
+use mecha_sdk_server::services::{
+    DeviceMetricsService, DisplayCtrlManager, DisplayCtrlServiceServer, MetricsServiceServer,
+    TrustZoneCtrlServiceManager, TrustZoneCtrlServiceServer, FILE_DESCRIPTOR_SET,
+};
+use std::time::Duration;
+use tonic::transport::Server;
+use tonic_health::pb::{
+    health_check_response::ServingStatus, health_client::HealthClient, HealthCheckRequest,
+};
+use tonic_reflection::pb::{
+    server_reflection_client::ServerReflectionClient, server_reflection_request::MessageRequest,
+    server_reflection_response::MessageResponse, ServerReflectionRequest,
+};
+
+#[tokio::test]
+async fn health_follows_hardware_probes() {
+    let root = TempDir::new("health");
+    let brightness = root.join("brightness");
+    std::fs::write(&brightness, "120\n").unwrap();
+
+    let probes = vec![
+        HardwareProbe {
+            service: "displaymanager.DisplayCtrlService",
+            probe: Probe::Paths(vec![brightness.display().to_string()]),
+        },
+        HardwareProbe {
+            service: "motionsensor.MotionSensorService",
+            probe: Probe::Paths(vec![root.join("missing").display().to_string()]),
+        },
+        HardwareProbe {
+            service: "metrics.MetricsService",
+            probe: Probe::None,
+        },
+    ];
+
+    let (reporter, health_service) = tonic_health::server::health_reporter();
+    tokio::spawn(report_health(
+        reporter,
+        Swappable::new(probes),
+        Duration::from_millis(20),
+    ));
+    let mut client =
+        HealthClient::new(connect(Server::builder().add_service(health_service)).await);
+
+    let status = |service: &'static str| {
+        let mut client = client.clone();
+        async move {
+            client
+                .check(HealthCheckRequest {
+                    service: service.to_string(),
+                })
+                .await
+                .unwrap()
+                .into_inner()
+                .status()
+        }
+    };
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(status("").await, ServingStatus::Serving);
+    assert_eq!(
+        status("displaymanager.DisplayCtrlService").await,
+        ServingStatus::Serving
+    );
+    assert_eq!(
+        status("motionsensor.MotionSensorService").await,
+        ServingStatus::NotServing
+    );
+    assert_eq!(
+        status("metrics.MetricsService").await,
+        ServingStatus::Serving
+    );
+
+    // the backlight disappears, the next probe round reports it
+    std::fs::remove_file(&brightness).unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(
+        status("displaymanager.DisplayCtrlService").await,
+        ServingStatus::NotServing
+    );
+
+    let unknown = client
+        .check(HealthCheckRequest {
+            service: "unknown.Service".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(unknown.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn service_gate_skips_disabled_and_unreachable_services() {
+    let dir = TempDir::new("gate");
+    let missing = dir.join("missing");
+    let probes = vec![
+        HardwareProbe {
+            service: "displaymanager.DisplayCtrlService",
+            probe: Probe::Paths(vec![missing.display().to_string()]),
+        },
+        HardwareProbe {
+            service: "trustzonectrl.TrustZoneCtrlService",
+            probe: Probe::Paths(vec![missing.display().to_string()]),
+        },
+        HardwareProbe {
+            service: "metrics.MetricsService",
+            probe: Probe::None,
+        },
+    ];
+    let enabled = ServiceToggle::default();
+    let mut gate = ServiceGate::new(probes);
+
+    let display = DisplayCtrlServiceServer::new(DisplayCtrlManager {
+        display_ctrl: FakeDisplay::default().into(),
+    });
+    assert!(gate.admit(&enabled, display).await.is_none());
+
+    // probing turned off, registered even without the chip
+    let trustzone = TrustZoneCtrlServiceServer::new(TrustZoneCtrlServiceManager {
+        trustzone_ctrl: FakeTrustZone.into(),
+    });
+    let unprobed = ServiceToggle {
+        enabled: true,
+        probe: false,
+    };
+    assert!(gate.admit(&unprobed, trustzone).await.is_some());
+
+    let metrics = MetricsServiceServer::new(DeviceMetricsService {
+        metrics: FakeMetrics,
+    });
+    let disabled = ServiceToggle {
+        enabled: false,
+        probe: true,
+    };
+    assert!(gate.admit(&disabled, metrics).await.is_none());
+
+    let services = gate
+        .into_probes()
+        .into_iter()
+        .map(|probe| probe.service)
+        .collect::<Vec<_>>();
+    assert_eq!(services, vec!["trustzonectrl.TrustZoneCtrlService"]);
+}
+
+#[tokio::test]
+async fn reflection_lists_every_package() {
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .build()
+        .unwrap();
+    let mut client = ServerReflectionClient::new(
+        connect(Server::builder().add_service(reflection_service)).await,
+    );
+
+    let request = ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(MessageRequest::ListServices(String::new())),
+    };
+    let mut responses = client
+        .server_reflection_info(tokio_stream::once(request))
+        .await
+        .unwrap()
+        .into_inner();
+    let response = responses.message().await.unwrap().unwrap();
+
+    let services = match response.message_response {
+        Some(MessageResponse::ListServicesResponse(list)) => list
+            .service
+            .into_iter()
+            .map(|service| service.name)
+            .collect::<Vec<_>>(),
+        other => panic!("unexpected reflection response {:?}", other),
+    };
+    for service in [
+        "networkmanager.NetworkManagerService",
+        "displaymanager.DisplayCtrlService",
+        "motionsensor.MotionSensorService",
+        "led_ctrl.LedCtrlService",
+        "deviceinfo.DeviceInfoService",
+        "metrics.MetricsService",
+        "cpugovernorctrl.CPUGovernorCtrlService",
+        "trustzonectrl.TrustZoneCtrlService",
+        "battery.PowerSupplyService",
+        "bluetooth.BluetoothService",
+        "grpc.reflection.v1alpha.ServerReflection",
+    ] {
+        assert!(
+            services.iter().any(|name| name == service),
+            "{} missing from {:?}",
+            service,
+            services
+        );
+    }
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use common::{FakeDisplay, TempDir};
+use mecha_sdk_server::configs::{GrpcConfig, Listener};
+use mecha_sdk_server::listener::{bind_unix, resolve, Endpoint, UnixSocket};
+use mecha_sdk_server::services::display_manager_service::displaymanager::{
+    display_ctrl_service_client::DisplayCtrlServiceClient, GetBrightnessRequest,
+};
+use mecha_sdk_server::services::{DisplayCtrlManager, DisplayCtrlServiceServer};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use tokio::net::UnixStream;
+use tokio_stream::wrappers::UnixListenerStream;
+use tonic::transport::{Endpoint as ChannelEndpoint, Server, Uri};
+use tower::service_fn;
+
+#[test]
+fn bind_and_port_are_the_default_listener() {
+    let config: GrpcConfig = serde_yaml::from_str("bind: 127.0.0.1\nport: 50052\n").unwrap();
+    assert_eq!(
+        config.listeners(),
+        vec![Listener {
+            tcp: Some("127.0.0.1:50052".parse().unwrap()),
+            ..Listener::default()
+        }]
+    );
+
+    let config: GrpcConfig = serde_yaml::from_str(
+        "listeners:\n  - unix: /run/mecha/grpc.sock\n    owner: \"0\"\n    group: root\n    mode: \"0660\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        resolve(&config.listeners()[0]).unwrap(),
+        Endpoint::Unix(UnixSocket {
+            path: "/run/mecha/grpc.sock".into(),
+            owner: Some(0),
+            group: Some(0),
+            mode: Some(0o660),
+        })
+    );
+}
+
+#[test]
+fn invalid_listeners_are_rejected() {
+    let cases = [
+        "tcp: 127.0.0.1:1\nunix: /tmp/a.sock\n",
+        "mode: \"0660\"\n",
+        "tcp: 127.0.0.1:1\nmode: \"0660\"\n",
+        "unix: /tmp/a.sock\nmode: \"0999\"\n",
+        "unix: /tmp/a.sock\nowner: no-such-user-mecha\n",
+    ];
+    for case in cases {
+        let listener: Listener = serde_yaml::from_str(case).unwrap();
+        assert!(resolve(&listener).is_err(), "{:?} accepted", case);
+    }
+}
+
+#[tokio::test]
+async fn services_are_served_on_a_unix_socket() {
+    let dir = TempDir::new("listener");
+    let path = dir.join("run").join("grpc.sock");
+    let socket = UnixSocket {
+        path: path.clone(),
+        owner: None,
+        group: None,
+        mode: Some(0o600),
+    };
+    // a stale socket file from a previous run is replaced
+    drop(bind_unix(&socket).unwrap());
+    let listener = bind_unix(&socket).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    // nothing is left of the private directory it was bound in
+    assert_eq!(std::fs::read_dir(dir.join("run")).unwrap().count(), 1);
+
+    tokio::spawn(
+        Server::builder()
+            .add_service(DisplayCtrlServiceServer::new(DisplayCtrlManager {
+                display_ctrl: FakeDisplay::default().into(),
+            }))
+            .serve_with_incoming(UnixListenerStream::new(listener)),
+    );
+    // while served, the socket is not taken over by a second server
+    assert!(bind_unix(&socket).is_err());
+
+    let channel = ChannelEndpoint::try_from("http://[::]:50051")
+        .unwrap()
+        .connect_with_connector(service_fn(move |_: Uri| UnixStream::connect(path.clone())))
+        .await
+        .unwrap();
+    let brightness = DisplayCtrlServiceClient::new(channel)
+        .get_brightness(GetBrightnessRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .brightness;
+    assert_eq!(brightness, 0);
+}
+
+#[tokio::test]
+async fn a_socket_without_its_owner_applied_is_removed() {
+    let dir = TempDir::new("listener-owner");
+    let uid = std::fs::metadata("/proc/self").unwrap().uid();
+    let socket = UnixSocket {
+        path: dir.join("grpc.sock"),
+        // only root may hand a socket to another user
+        owner: Some(if uid == 0 { 1 } else { 0 }),
+        group: None,
+        mode: Some(0o600),
+    };
+    let bound = bind_unix(&socket);
+    if uid == 0 {
+        bound.unwrap();
+        assert!(socket.path.exists());
+    } else {
+        assert!(bound.is_err());
+        assert!(!socket.path.exists());
+    }
+    assert_eq!(
+        std::fs::read_dir(dir.path()).unwrap().count(),
+        usize::from(uid == 0)
+    );
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use anyhow::Result;
+use common::{connect, FakeLed, TempDir};
+use mecha_sdk_server::services::{
+    display_manager_service::displaymanager::{
+        display_ctrl_service_client::DisplayCtrlServiceClient, SetBrightnessRequest,
+    },
+    led_manager::ledmanager::{led_ctrl_service_client::LedCtrlServiceClient, LedColor},
+    trustzone_ctrl_service::trustzone::{
+        trust_zone_ctrl_service_client::TrustZoneCtrlServiceClient, RemoveCertificateRequest,
+    },
+    BlockingPool, Device, DisplayControl, DisplayCtrlManager, DisplayCtrlServiceServer,
+    LedCtrlManager, LedCtrlServiceServer, TrustZoneCtrl, TrustZoneCtrlServiceManager,
+    TrustZoneCtrlServiceServer,
+};
+use std::os::unix::fs::PermissionsExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tonic::transport::{Channel, Server};
+use tonic::Code;
+
+// a backlight whose sysfs writes block the calling thread for `delay`
+#[derive(Clone, Default)]
+struct SlowDisplay {
+    delay: Duration,
+    active: Arc<AtomicUsize>,
+    max_active: Arc<AtomicUsize>,
+}
+
+impl DisplayControl for SlowDisplay {
+    fn set_display_brightness(&self, _brightness: u8) -> Result<()> {
+        let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_active.fetch_max(active, Ordering::SeqCst);
+        std::thread::sleep(self.delay);
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn get_display_brightness(&self) -> Result<u8> {
+        Ok(0)
+    }
+}
+
+async fn serve(display: SlowDisplay, pool: &BlockingPool) -> Channel {
+    connect(
+        Server::builder()
+            .add_service(DisplayCtrlServiceServer::new(DisplayCtrlManager {
+                display_ctrl: Device::new(display, pool),
+            }))
+            .add_service(LedCtrlServiceServer::new(LedCtrlManager {
+                led_ctrl: Device::new(FakeLed::default(), pool),
+            })),
+    )
+    .await
+}
+
+// a single async worker, it would sit in the display writes if those ran
+// inside the handlers
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn a_slow_device_does_not_block_the_others() {
+    let display = SlowDisplay {
+        delay: Duration::from_millis(200),
+        ..SlowDisplay::default()
+    };
+    let channel = serve(
+        display.clone(),
+        &BlockingPool::new(4, Duration::from_secs(10)),
+    )
+    .await;
+
+    let writes: Vec<_> = (0..8)
+        .map(|_| {
+            let mut client = DisplayCtrlServiceClient::new(channel.clone());
+            tokio::spawn(async move {
+                client
+                    .set_brightness(SetBrightnessRequest { brightness: 100 })
+                    .await
+            })
+        })
+        .collect();
+    // keep the leds busy for as long as the backlight is
+    let mut led = LedCtrlServiceClient::new(channel.clone());
+    let mut slowest = Duration::ZERO;
+    let mut calls = 0;
+    while writes.iter().any(|write| !write.is_finished()) {
+        let started = Instant::now();
+        led.set_led(LedColor { color: 0 }).await.unwrap();
+        slowest = slowest.max(started.elapsed());
+        calls += 1;
+    }
+    assert!(calls > 10);
+    assert!(
+        slowest < Duration::from_millis(150),
+        "led call took {:?} behind the display writes",
+        slowest
+    );
+
+    for write in writes {
+        write.await.unwrap().unwrap();
+    }
+    // the writes to the one backlight never overlapped
+    assert_eq!(display.max_active.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn stuck_device_calls_time_out() {
+    let display = SlowDisplay {
+        delay: Duration::from_millis(500),
+        ..SlowDisplay::default()
+    };
+    let channel = serve(display, &BlockingPool::new(4, Duration::from_millis(100))).await;
+    let mut client = DisplayCtrlServiceClient::new(channel);
+
+    let started = Instant::now();
+    let status = client
+        .set_brightness(SetBrightnessRequest { brightness: 100 })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+    assert!(started.elapsed() < Duration::from_millis(400));
+}
+
+#[tokio::test]
+async fn a_hung_trustm_tool_releases_the_device() {
+    let dir = TempDir::new("trustm");
+    let tool = dir.join("trustm_cert");
+    std::fs::write(&tool, "#!/bin/sh\nsleep 30\n").unwrap();
+    std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let trustzone = TrustZoneCtrl {
+        path: dir.path().to_string_lossy().to_string(),
+        timeout: Some(Duration::from_millis(200)),
+    };
+    let channel = connect(
+        Server::builder().add_service(TrustZoneCtrlServiceServer::new(
+            TrustZoneCtrlServiceManager {
+                trustzone_ctrl: Device::new(
+                    trustzone,
+                    &BlockingPool::new(4, Duration::from_millis(100)),
+                ),
+            },
+        )),
+    )
+    .await;
+    let mut client = TrustZoneCtrlServiceClient::new(channel);
+    let remove = || RemoveCertificateRequest {
+        oid: "0xE0E1".to_string(),
+    };
+
+    let status = client.remove_certificate(remove()).await.unwrap_err();
+    assert_eq!(status.code(), Code::DeadlineExceeded);
+
+    // the tool was killed after 200ms, so the next call gets the device
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    std::fs::write(&tool, "#!/bin/sh\nexit 0\n").unwrap();
+    let removed = client.remove_certificate(remove()).await.unwrap();
+    assert!(removed.into_inner().success);
+}
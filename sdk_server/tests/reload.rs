@@ -0,0 +1,166 @@
+mod common;
+
+use common::TempDir;
+use mecha_sdk_server::cli::Cli;
+use mecha_sdk_server::health::{HardwareProbe, Probe};
+use mecha_sdk_server::reload::{diff, ConfigReloader, Controllers, Swappable};
+use mecha_sdk_server::services::{DisplayControl, LedColor, LedControl};
+use std::fs;
+use std::path::Path;
+
+fn board(name: &str) -> TempDir {
+    let root = TempDir::new(&format!("reload-{}", name));
+    for file in [
+        "brightness",
+        "red",
+        "green",
+        "blue",
+        "red2",
+        "x",
+        "y",
+        "z",
+        "uevent",
+        "current",
+    ] {
+        fs::write(root.join(file), "0\n").unwrap();
+    }
+    root
+}
+
+fn yaml(root: &Path, red: &str, extra: &str) -> String {
+    let path = |file: &str| root.join(file).display().to_string();
+    format!(
+        r#"
+server:
+  port: 50052
+{extra}
+services:
+  cpu_governor:
+    enabled: false
+interfaces:
+  display:
+    device: {}
+  led:
+    red_led: {}
+    green_led: {}
+    blue_led: {}
+  motion_sensor:
+    x_axis: {}
+    y_axis: {}
+    z_axis: {}
+  battery:
+    device: {}
+    current: {}
+"#,
+        path("brightness"),
+        path(red),
+        path("green"),
+        path("blue"),
+        path("x"),
+        path("y"),
+        path("z"),
+        path("uevent"),
+        path("current"),
+    )
+}
+
+fn cli(path: &Path) -> Cli {
+    use clap::Parser;
+    Cli::parse_from(["mecha_sdk_server", "--config", path.to_str().unwrap()])
+}
+
+#[test]
+fn reload_swaps_changed_controllers() {
+    let root = board("swap");
+    let path = root.join("Config.yaml");
+    fs::write(&path, yaml(root.path(), "red", "")).unwrap();
+    let cli = cli(&path);
+
+    let config = cli.load_config().unwrap();
+    let controllers = Controllers::new(&config.interfaces);
+    let probes = Swappable::new(vec![HardwareProbe {
+        service: "led_ctrl.LedCtrlService",
+        probe: Probe::None,
+    }]);
+    let led = controllers.led.clone();
+    let display = controllers.display.clone();
+    let mut reloader =
+        ConfigReloader::new(|| cli.load_config(), config, controllers, probes.clone());
+
+    // nothing changed on disk
+    assert!(reloader.reload().unwrap().is_empty());
+
+    fs::write(&path, yaml(root.path(), "red2", "")).unwrap();
+    let changes = reloader.reload().unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].key, "interfaces.led.red_led");
+    assert!(changes[0].is_reloadable());
+
+    // the running service handle writes to the new path
+    led.set_led(LedColor::Red).unwrap();
+    assert_eq!(fs::read_to_string(root.join("red2")).unwrap().trim(), "1");
+    assert_eq!(fs::read_to_string(root.join("red")).unwrap().trim(), "0");
+    display.set_display_brightness(42).unwrap();
+    assert_eq!(display.get_display_brightness().unwrap(), 42);
+
+    // the led probe now checks the new paths
+    match &probes.get()[0].probe {
+        Probe::Paths(paths) => assert!(paths.contains(&root.join("red2").display().to_string())),
+        other => panic!("unexpected probe {:?}", other),
+    }
+}
+
+#[test]
+fn invalid_reload_keeps_the_running_config() {
+    let root = board("invalid");
+    let path = root.join("Config.yaml");
+    fs::write(&path, yaml(root.path(), "red", "")).unwrap();
+    let cli = cli(&path);
+
+    let config = cli.load_config().unwrap();
+    let controllers = Controllers::new(&config.interfaces);
+    let led = controllers.led.clone();
+    let mut reloader = ConfigReloader::new(
+        || cli.load_config(),
+        config,
+        controllers,
+        Swappable::new(Vec::new()),
+    );
+
+    fs::write(&path, yaml(root.path(), "red2", "colour: blue")).unwrap();
+    assert!(reloader.reload().is_err());
+    fs::write(&path, "server: [").unwrap();
+    assert!(reloader.reload().is_err());
+
+    led.set_led(LedColor::Red).unwrap();
+    assert_eq!(fs::read_to_string(root.join("red")).unwrap().trim(), "1");
+    assert!(reloader.config().interfaces.led.red_led.ends_with("/red"));
+}
+
+#[test]
+fn diff_redacts_auth_and_flags_restart_only_keys() {
+    let root = board("diff");
+    let path = root.join("Config.yaml");
+    fs::write(&path, yaml(root.path(), "red", "")).unwrap();
+    let old = cli(&path).load_config().unwrap();
+    fs::write(
+        &path,
+        yaml(
+            root.path(),
+            "red",
+            "  health_interval: 5\nauth:\n  jwt_secret: hunter2\n  roles: {}",
+        ),
+    )
+    .unwrap();
+    let new = cli(&path).load_config().unwrap();
+
+    let changes = diff(&old, &new);
+    let keys = changes
+        .iter()
+        .map(|change| change.key.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec!["server.health_interval", "auth"]);
+    assert!(changes.iter().all(|change| !change.is_reloadable()));
+    assert_eq!(changes[0].to_string(), "server.health_interval: 30 -> 5");
+    assert!(!changes[1].to_string().contains("hunter2"));
+}
@@ -0,0 +1,493 @@
+mod common;
+
+use common::{
+    connect, FakeBattery, FakeBluetooth, FakeCpu, FakeDisplay, FakeLed, FakeMetrics, FakeMotion,
+    FakeTrustZone, FakeWifi,
+};
+use mecha_sdk_server::services::{
+    battery_ctrl_service::power_supply::{
+        power_supply_service_client::PowerSupplyServiceClient, Empty as BatteryEmpty,
+    },
+    bluetooth_manager::bluetooth::{
+        bluetooth_service_client::BluetoothServiceClient, Empty as BluetoothEmpty,
+    },
+    cpu_ctrl_service::cpu_governor_ctrl::{
+        cpu_governor_ctrl_service_client::CpuGovernorCtrlServiceClient, CpuFrequencyRequest,
+        Empty as CpuEmpty, GovernorRequest,
+    },
+    device_info_service::deviceinfo::{
+        device_info_service_client::DeviceInfoServiceClient, Empty as DeviceInfoEmpty,
+    },
+    display_manager_service::displaymanager::{
+        display_ctrl_service_client::DisplayCtrlServiceClient, GetBrightnessRequest,
+        SetBrightnessRequest,
+    },
+    led_manager::ledmanager::{led_ctrl_service_client::LedCtrlServiceClient, LedColor},
+    metrics_service::metrics::{
+        metrics_service_client::MetricsServiceClient, Empty as MetricsEmpty,
+    },
+    motion_sensor_service::motionsensor::{
+        motion_sensor_service_client::MotionSensorServiceClient, Empty as MotionEmpty,
+    },
+    network_manager_service::networkmanager::{
+        network_manager_service_client::NetworkManagerServiceClient, Empty as NetworkEmpty,
+        RemoveNetworkRequest, WifiConnectRequest,
+    },
+    trustzone_ctrl_service::trustzone::{
+        trust_zone_ctrl_service_client::TrustZoneCtrlServiceClient, GenerateKeyRequest,
+        ReadCertificationRequest, SignDataRequest, VerifyDataRequest, WriteCertificateRequest,
+    },
+    Bluetooth, BluetoothServiceServer, CpuCtrlService, CpuGovernorCtrlServiceServer,
+    DeviceInfoCtrl, DeviceInfoServiceServer, DeviceMetricsService, DisplayCtrlManager,
+    DisplayCtrlServiceServer, LedCtrlManager, LedCtrlServiceServer, MetricsServiceServer,
+    MotionSensorManager, MotionSensorServiceServer, NetworkManager, NetworkManagerServiceServer,
+    PowerSupply, PowerSupplyServiceServer, TrustZoneCtrlServiceManager, TrustZoneCtrlServiceServer,
+};
+use tonic::{transport::Server, Code};
+
+#[tokio::test]
+async fn display_brightness_round_trip() {
+    let display = FakeDisplay::default();
+    let channel = connect(Server::builder().add_service(DisplayCtrlServiceServer::new(
+        DisplayCtrlManager {
+            display_ctrl: display.clone().into(),
+        },
+    )))
+    .await;
+    let mut client = DisplayCtrlServiceClient::new(channel);
+
+    client
+        .set_brightness(SetBrightnessRequest { brightness: 200 })
+        .await
+        .unwrap();
+    assert_eq!(*display.brightness.lock().unwrap(), 200);
+
+    let response = client
+        .get_brightness(GetBrightnessRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.brightness, 200);
+}
+
+#[tokio::test]
+async fn display_rejects_out_of_range_brightness() {
+    let display = FakeDisplay::default();
+    let channel = connect(Server::builder().add_service(DisplayCtrlServiceServer::new(
+        DisplayCtrlManager {
+            display_ctrl: display.clone().into(),
+        },
+    )))
+    .await;
+    let mut client = DisplayCtrlServiceClient::new(channel);
+
+    let status = client
+        .set_brightness(SetBrightnessRequest { brightness: 250 })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unknown);
+    assert_eq!(*display.brightness.lock().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn led_set_and_clear() {
+    let led = FakeLed::default();
+    let channel = connect(Server::builder().add_service(LedCtrlServiceServer::new(
+        LedCtrlManager {
+            led_ctrl: led.clone().into(),
+        },
+    )))
+    .await;
+    let mut client = LedCtrlServiceClient::new(channel);
+
+    client.set_led(LedColor { color: 0 }).await.unwrap();
+    client.set_led(LedColor { color: 2 }).await.unwrap();
+    assert_eq!(*led.lit.lock().unwrap(), [true, false, true]);
+
+    client.clear_led(LedColor { color: 0 }).await.unwrap();
+    assert_eq!(*led.lit.lock().unwrap(), [false, false, true]);
+
+    let status = client.set_led(LedColor { color: 7 }).await.unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn motion_sensor_read_and_detect() {
+    let motion = FakeMotion::default();
+    let channel = connect(
+        Server::builder().add_service(MotionSensorServiceServer::new(MotionSensorManager {
+            motion_sensor: motion.clone().into(),
+        })),
+    )
+    .await;
+    let mut client = MotionSensorServiceClient::new(channel);
+
+    let detected = client
+        .detect_motion(MotionEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!detected.is_motion_detected);
+
+    *motion.value.lock().unwrap() = (0.5, -1.0, 9.8);
+    let value = client
+        .read_value(MotionEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        (value.x_value, value.y_value, value.z_value),
+        (0.5, -1.0, 9.8)
+    );
+
+    let detected = client
+        .detect_motion(MotionEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(detected.is_motion_detected);
+}
+
+#[tokio::test]
+async fn battery_info_device_and_current() {
+    let channel = connect(Server::builder().add_service(PowerSupplyServiceServer::new(
+        PowerSupply {
+            power_supply: FakeBattery::default().into(),
+        },
+    )))
+    .await;
+    let mut client = PowerSupplyServiceClient::new(channel);
+
+    let info = client
+        .get_power_supply_info(BatteryEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(info.name, "bq27441-0");
+    assert_eq!(info.capacity, "76");
+    assert_eq!(info.voltage_now, 3912000);
+    assert!(info.present);
+
+    let device = client
+        .get_device(BatteryEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(
+        device.device_path,
+        "/sys/class/power_supply/bq27441-0/uevent"
+    );
+
+    let current = client
+        .get_current(BatteryEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(current.current_value, -245000);
+}
+
+#[tokio::test]
+async fn cpu_governor_and_frequency() {
+    let cpu = FakeCpu::default();
+    let channel = connect(
+        Server::builder().add_service(CpuGovernorCtrlServiceServer::new(CpuCtrlService {
+            cpu_ctrl_manager: cpu.clone().into(),
+        })),
+    )
+    .await;
+    let mut client = CpuGovernorCtrlServiceClient::new(channel);
+
+    client
+        .set_governor(GovernorRequest {
+            governor: "userspace".to_string(),
+        })
+        .await
+        .unwrap();
+    let governor = client.get_governor(CpuEmpty {}).await.unwrap().into_inner();
+    assert_eq!(governor.result, "userspace");
+    let status = client
+        .set_governor(GovernorRequest {
+            governor: "turbo".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert!(status.message().contains("invalid CPU governor turbo"));
+    assert_eq!(*cpu.governor.lock().unwrap(), "userspace");
+    // an empty governor selects userspace as it always did
+    *cpu.governor.lock().unwrap() = "powersave".to_string();
+    client
+        .set_governor(GovernorRequest {
+            governor: String::new(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(*cpu.governor.lock().unwrap(), "userspace");
+
+    client
+        .set_cpu_frequency(CpuFrequencyRequest {
+            frequency: "1600".to_string(),
+        })
+        .await
+        .unwrap();
+    let frequency = client
+        .get_cpu_frequency(CpuEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(frequency.result, "1600000");
+
+    let status = client
+        .set_cpu_frequency(CpuFrequencyRequest {
+            frequency: "999".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+    assert_eq!(*cpu.frequency.lock().unwrap(), "1600000");
+}
+
+#[tokio::test]
+async fn metrics_usage() {
+    let channel = connect(Server::builder().add_service(MetricsServiceServer::new(
+        DeviceMetricsService {
+            metrics: FakeMetrics,
+        },
+    )))
+    .await;
+    let mut client = MetricsServiceClient::new(channel);
+
+    let cpu = client
+        .get_cpu_usage(MetricsEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(cpu.cpu_usage, 12.5);
+    let memory = client
+        .get_memory_usage(MetricsEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(memory.memory_usage, 512);
+    let disk = client
+        .get_disk_usage(MetricsEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(disk.disk_usage, 2048);
+}
+
+#[tokio::test]
+async fn wifi_scan_connect_and_remove() {
+    let wifi = FakeWifi::default();
+    let channel = connect(
+        Server::builder().add_service(NetworkManagerServiceServer::new(NetworkManager {
+            wifi: wifi.clone(),
+        })),
+    )
+    .await;
+    let mut client = NetworkManagerServiceClient::new(channel);
+
+    let status = client
+        .get_wifi_status(NetworkEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(status.wifi_on);
+
+    let scan = client
+        .scan_wireless_network(NetworkEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(scan.results.len(), 2);
+    assert_eq!(scan.results[0].signal, -42);
+
+    // not connected yet
+    let status = client
+        .get_current_network(NetworkEmpty {})
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unknown);
+
+    let connected = client
+        .connect_wireless_network(WifiConnectRequest {
+            ssid: "mecha".to_string(),
+            psk: "short".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!connected.success);
+    assert_eq!(connected.message, "WiFi connection failed");
+
+    let connected = client
+        .connect_wireless_network(WifiConnectRequest {
+            ssid: "mecha".to_string(),
+            psk: "mecha-password".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(connected.success);
+
+    let known = client
+        .scan_known_wireless_network(NetworkEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(known.results.len(), 1);
+    assert_eq!(known.results[0].ssid, "mecha");
+
+    let current = client
+        .get_current_network(NetworkEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(current.name, "mecha");
+
+    let removed = client
+        .disconnect_wireless_network(RemoveNetworkRequest { network_id: 3 })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!removed.success);
+
+    let removed = client
+        .disconnect_wireless_network(RemoveNetworkRequest {
+            network_id: known.results[0].network_id,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(removed.success);
+    assert!(wifi.networks.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn bluetooth_enable_and_disable() {
+    let bluetooth = FakeBluetooth::default();
+    let channel = connect(
+        Server::builder().add_service(BluetoothServiceServer::new(Bluetooth {
+            bluetooth: bluetooth.clone(),
+        })),
+    )
+    .await;
+    let mut client = BluetoothServiceClient::new(channel);
+
+    let status = client
+        .get_bluetooth_status(BluetoothEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!status.enabled);
+
+    client.enable_bluetooth(BluetoothEmpty {}).await.unwrap();
+    assert!(*bluetooth.powered.lock().unwrap());
+    let status = client
+        .get_bluetooth_status(BluetoothEmpty {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(status.enabled);
+
+    client.disable_bluetooth(BluetoothEmpty {}).await.unwrap();
+    assert!(!*bluetooth.powered.lock().unwrap());
+}
+
+#[tokio::test]
+async fn trustzone_requests() {
+    let channel = connect(
+        Server::builder().add_service(TrustZoneCtrlServiceServer::new(
+            TrustZoneCtrlServiceManager {
+                trustzone_ctrl: FakeTrustZone.into(),
+            },
+        )),
+    )
+    .await;
+    let mut client = TrustZoneCtrlServiceClient::new(channel);
+
+    let cert = client
+        .read_certification(ReadCertificationRequest {
+            output_file: "/tmp/cert.pem".to_string(),
+            region: "0xe0e0".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(cert.certificate, "cert:0xe0e0");
+
+    let written = client
+        .write_certificate(WriteCertificateRequest {
+            cert_file: "/tmp/cert.pem".to_string(),
+            oid: String::new(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!written.success);
+
+    let key = client
+        .generate_key(GenerateKeyRequest {
+            oid: "0xe0f1".to_string(),
+            key_type: 4,
+            key_size: 1,
+            output_file: "/tmp/pub.pem".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(key.public_key, "pubkey:0xe0f1:Sign:ECC384");
+
+    let status = client
+        .generate_key(GenerateKeyRequest {
+            oid: "0xe0f1".to_string(),
+            key_type: 9,
+            key_size: 1,
+            output_file: "/tmp/pub.pem".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let signed = client
+        .sign_data(SignDataRequest {
+            key_oid: "0xe0f1".to_string(),
+            input_file: "data.bin".to_string(),
+            output_file: "data.sig".to_string(),
+            hash_before_sign: true,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(signed.signed_data, "signed:0xe0f1:data.bin");
+
+    let verified = client
+        .verify_data(VerifyDataRequest {
+            pubkey_file: "pub.pem".to_string(),
+            input_file: "data.bin".to_string(),
+            signature_file: "data.sig".to_string(),
+            hash_before_verify: true,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(verified.verification_result, "Verification Success.");
+}
+
+#[tokio::test]
+async fn device_info_memory() {
+    let channel = connect(
+        Server::builder().add_service(DeviceInfoServiceServer::new(DeviceInfoCtrl::default())),
+    )
+    .await;
+    let mut client = DeviceInfoServiceClient::new(channel);
+
+    let memory = client
+        .get_memory_info(DeviceInfoEmpty {})
+        .await
+        .unwrap()
+        .into_inner()
+        .memory_info
+        .unwrap();
+    assert!(memory.total_memory > 0);
+}
@@ -0,0 +1,192 @@
+mod common;
+
+use common::{connect, FakeCpu, FakeDisplay, FakeLed, TempDir};
+use mecha_sdk_server::services::{
+    display_manager_service::displaymanager::{
+        display_ctrl_service_client::DisplayCtrlServiceClient, SetBrightnessRequest,
+    },
+    led_manager::ledmanager::{led_ctrl_service_client::LedCtrlServiceClient, LedColor},
+    settings_service::settings::{
+        settings_service_client::SettingsServiceClient, GetSettingsRequest, ResetSettingsRequest,
+    },
+    CpuControl, CpuFrequency, Device, DisplayCtrlManager, DisplayCtrlServiceServer, LedCtrlManager,
+    LedCtrlServiceServer, SettingsManager, SettingsServiceServer,
+};
+use mecha_sdk_server::settings::{restore, Persisted, SettingsStore};
+use std::fs;
+use tonic::transport::Server;
+
+#[test]
+fn store_survives_a_reopen_and_resets_keys() {
+    let dir = TempDir::new("settings-store");
+    let path = dir.join("settings.json");
+    let store = SettingsStore::open(&path).unwrap();
+    assert!(store.all().is_empty());
+
+    store.set("display.brightness", "120").unwrap();
+    store.set("led.red", "on").unwrap();
+    store.set("led.blue", "off").unwrap();
+    assert!(!path.with_extension("json.tmp").exists());
+
+    let store = SettingsStore::open(&path).unwrap();
+    assert_eq!(store.get("display.brightness").as_deref(), Some("120"));
+    assert_eq!(store.all().len(), 3);
+
+    let removed = store
+        .reset(&["led.red".to_string(), "cpu.governor".to_string()])
+        .unwrap();
+    assert_eq!(removed, vec!["led.red"]);
+    assert_eq!(store.get("led.red"), None);
+    assert_eq!(
+        store.reset(&[]).unwrap(),
+        vec!["display.brightness", "led.blue"]
+    );
+    assert!(SettingsStore::open(&path).unwrap().all().is_empty());
+
+    // a corrupted file starts over instead of failing startup
+    fs::write(&path, "{ not json").unwrap();
+    let store = SettingsStore::open(&path).unwrap();
+    assert!(store.all().is_empty());
+    store.set("led.green", "on").unwrap();
+    assert_eq!(
+        SettingsStore::open(&path)
+            .unwrap()
+            .get("led.green")
+            .as_deref(),
+        Some("on")
+    );
+}
+
+#[test]
+fn failed_write_leaves_the_settings_unchanged() {
+    let dir = TempDir::new("settings-unwritable");
+    let path = dir.join("settings.json");
+    let store = SettingsStore::open(&path).unwrap();
+    store.set("display.brightness", "80").unwrap();
+
+    fs::remove_dir_all(dir.path()).unwrap();
+    assert!(store.set("display.brightness", "90").is_err());
+    assert_eq!(store.get("display.brightness").as_deref(), Some("80"));
+}
+
+#[tokio::test]
+async fn changes_through_the_services_are_saved_and_restored() {
+    let dir = TempDir::new("settings-services");
+    let path = dir.join("settings.json");
+    let store = SettingsStore::open(&path).unwrap();
+    let display = FakeDisplay::default();
+    let led = FakeLed::default();
+
+    let channel = connect(
+        Server::builder()
+            .add_service(DisplayCtrlServiceServer::new(DisplayCtrlManager {
+                display_ctrl: Persisted::new(display.clone(), Some(store.clone())).into(),
+            }))
+            .add_service(LedCtrlServiceServer::new(LedCtrlManager {
+                led_ctrl: Persisted::new(led.clone(), Some(store.clone())).into(),
+            }))
+            .add_service(SettingsServiceServer::new(SettingsManager {
+                store: store.clone().into(),
+            })),
+    )
+    .await;
+
+    DisplayCtrlServiceClient::new(channel.clone())
+        .set_brightness(SetBrightnessRequest { brightness: 150 })
+        .await
+        .unwrap();
+    // rejected by the hardware, so not saved
+    assert!(DisplayCtrlServiceClient::new(channel.clone())
+        .set_brightness(SetBrightnessRequest { brightness: 250 })
+        .await
+        .is_err());
+    let mut leds = LedCtrlServiceClient::new(channel.clone());
+    leds.set_led(LedColor { color: 1 }).await.unwrap();
+    leds.clear_led(LedColor { color: 2 }).await.unwrap();
+
+    let mut settings = SettingsServiceClient::new(channel);
+    let saved = settings
+        .get_settings(GetSettingsRequest {
+            prefix: "led.".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .settings
+        .into_iter()
+        .map(|setting| (setting.key, setting.value))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        saved,
+        vec![
+            ("led.blue".to_string(), "off".to_string()),
+            ("led.green".to_string(), "on".to_string()),
+        ]
+    );
+
+    // a fresh board picks the saved state up again
+    let store = SettingsStore::open(&path).unwrap();
+    let display = Device::from(Persisted::new(FakeDisplay::default(), None));
+    let led = Device::from(Persisted::new(FakeLed::default(), None));
+    assert_eq!(
+        restore(&display, &store, |store, display| store
+            .restore_display(display.inner()))
+        .await,
+        vec!["display.brightness"]
+    );
+    assert_eq!(
+        restore(&led, &store, |store, led| store.restore_led(led.inner())).await,
+        vec!["led.green", "led.blue"]
+    );
+    assert_eq!(*display.get().inner().brightness.lock().unwrap(), 150);
+    assert_eq!(*led.get().inner().lit.lock().unwrap(), [false, true, false]);
+
+    let reset = settings
+        .reset_settings(ResetSettingsRequest {
+            keys: vec!["display.brightness".to_string()],
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(reset.keys, vec!["display.brightness"]);
+    assert_eq!(
+        SettingsStore::open(&path)
+            .unwrap()
+            .get("display.brightness"),
+        None
+    );
+}
+
+#[test]
+fn cpu_restores_the_governor_before_the_frequency() {
+    let dir = TempDir::new("settings-cpu");
+    let path = dir.join("settings.json");
+    let store = SettingsStore::open(&path).unwrap();
+    let cpu = Persisted::new(FakeCpu::default(), Some(store.clone()));
+    cpu.set_cpu_governor("userspace").unwrap();
+    cpu.set_cpu_frequency(CpuFrequency::Freq1600000).unwrap();
+    // rejected by the controller, so not saved
+    assert!(cpu.set_cpu_governor("turbo").is_err());
+    assert_eq!(store.get("cpu.governor").as_deref(), Some("userspace"));
+    assert_eq!(store.get("cpu.frequency").as_deref(), Some("1600000"));
+
+    let fresh = FakeCpu::default();
+    assert_eq!(
+        store.restore_cpu(&fresh),
+        vec!["cpu.governor", "cpu.frequency"]
+    );
+    assert_eq!(*fresh.governor.lock().unwrap(), "userspace");
+    assert_eq!(*fresh.frequency.lock().unwrap(), "1600000");
+
+    // an unknown step is skipped, the rest still applies
+    store.set("cpu.frequency", "2000000").unwrap();
+    assert_eq!(store.restore_cpu(&FakeCpu::default()), vec!["cpu.governor"]);
+
+    // the saved governor is applied as is, a frequency only under userspace
+    cpu.set_cpu_governor("powersave").unwrap();
+    assert_eq!(store.get("cpu.governor").as_deref(), Some("powersave"));
+    let fresh = FakeCpu::default();
+    assert_eq!(store.restore_cpu(&fresh), vec!["cpu.governor"]);
+    assert_eq!(*fresh.governor.lock().unwrap(), "powersave");
+    assert_eq!(*fresh.frequency.lock().unwrap(), "1200000");
+}
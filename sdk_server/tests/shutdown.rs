@@ -0,0 +1,82 @@
+mod common;
+
+use common::{FakeDisplay, TempDir};
+use mecha_sdk_server::services::display_manager_service::displaymanager::{
+    display_ctrl_service_client::DisplayCtrlServiceClient, GetBrightnessRequest,
+};
+use mecha_sdk_server::services::{DisplayCtrlManager, DisplayCtrlServiceServer};
+use mecha_sdk_server::shutdown::{drain, Shutdown};
+use mecha_sdk_server::systemd;
+use std::os::unix::net::UnixDatagram;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+
+#[tokio::test]
+async fn drain_gives_up_after_the_timeout() {
+    let shutdown = Shutdown::new();
+    shutdown.trigger();
+    assert!(shutdown.is_triggered());
+
+    let started = Instant::now();
+    let serve = std::future::pending::<Result<(), std::io::Error>>();
+    drain(serve, &shutdown, Duration::from_millis(50))
+        .await
+        .unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn server_stops_once_shutdown_is_triggered() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shutdown = Shutdown::new();
+    let serve = Server::builder()
+        .add_service(DisplayCtrlServiceServer::new(DisplayCtrlManager {
+            display_ctrl: FakeDisplay::default().into(),
+        }))
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown.triggered());
+    let trigger = shutdown.clone();
+    let handle = tokio::spawn(async move { drain(serve, &trigger, Duration::from_secs(5)).await });
+
+    let mut display = DisplayCtrlServiceClient::connect(format!("http://{}", addr))
+        .await
+        .unwrap();
+    display
+        .get_brightness(GetBrightnessRequest {})
+        .await
+        .unwrap();
+    assert!(!handle.is_finished());
+
+    shutdown.trigger();
+    tokio::time::timeout(Duration::from_secs(2), handle)
+        .await
+        .expect("server did not stop")
+        .unwrap()
+        .unwrap();
+
+    // the listener is closed
+    assert!(display
+        .get_brightness(GetBrightnessRequest {})
+        .await
+        .is_err());
+}
+
+#[test]
+fn ready_is_sent_to_the_notify_socket() {
+    let dir = TempDir::new("notify");
+    let path = dir.join("notify.sock");
+    let socket = UnixDatagram::bind(&path).unwrap();
+    std::env::set_var("NOTIFY_SOCKET", &path);
+
+    systemd::notify_ready();
+    systemd::notify_stopping();
+    std::env::remove_var("NOTIFY_SOCKET");
+
+    let mut buf = [0; 64];
+    let n = socket.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"READY=1\n");
+    let n = socket.recv(&mut buf).unwrap();
+    assert_eq!(&buf[..n], b"STOPPING=1\n");
+}
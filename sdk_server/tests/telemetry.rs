@@ -0,0 +1,171 @@
+mod common;
+
+use common::{connect, FakeBattery, FakeCpu, FakeDisplay, FakeMetrics};
+use mecha_sdk_server::services::display_manager_service::displaymanager::{
+    display_ctrl_service_client::DisplayCtrlServiceClient, SetBrightnessRequest,
+};
+use mecha_sdk_server::services::{
+    Device, DisplayCtrlManager, DisplayCtrlServiceServer, FILE_DESCRIPTOR_SET,
+};
+use mecha_sdk_server::shutdown::Shutdown;
+use mecha_sdk_server::telemetry::{
+    bind_metrics, serve_metrics, DeviceGauges, RpcMetrics, RpcMetricsLayer,
+};
+use prometheus::{Encoder, Registry, TextEncoder};
+use std::sync::Mutex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tonic::transport::Server;
+
+fn gauges() -> DeviceGauges<FakeMetrics, FakeBattery, FakeCpu> {
+    DeviceGauges {
+        metrics: Some(Device::from(Mutex::new(FakeMetrics))),
+        battery: Some(FakeBattery::default().into()),
+        cpu: Some(FakeCpu::default().into()),
+    }
+}
+
+fn text(registry: &Registry) -> String {
+    let mut body = Vec::new();
+    TextEncoder::new()
+        .encode(&registry.gather(), &mut body)
+        .unwrap();
+    String::from_utf8(body).unwrap()
+}
+
+#[tokio::test]
+async fn rpcs_are_counted_per_method_and_code() {
+    let registry = Registry::new();
+    let layer = RpcMetricsLayer::new(Some(
+        RpcMetrics::new(&registry, &[FILE_DESCRIPTOR_SET]).unwrap(),
+    ));
+    let router = Server::builder()
+        .layer(layer)
+        .add_service(DisplayCtrlServiceServer::new(DisplayCtrlManager {
+            display_ctrl: FakeDisplay::default().into(),
+        }));
+    let mut display = DisplayCtrlServiceClient::new(connect(router).await);
+
+    display
+        .set_brightness(SetBrightnessRequest { brightness: 80 })
+        .await
+        .unwrap();
+    display
+        .set_brightness(SetBrightnessRequest { brightness: 80 })
+        .await
+        .unwrap();
+    display
+        .set_brightness(SetBrightnessRequest { brightness: 250 })
+        .await
+        .unwrap_err();
+
+    let text = text(&registry);
+    let labels = r#"method="SetBrightness",service="displaymanager.DisplayCtrlService""#;
+    assert!(text.contains(&format!(
+        r#"mecha_grpc_requests_total{{code="Ok",{}}} 2"#,
+        labels
+    )));
+    assert!(text.contains(&format!(
+        r#"mecha_grpc_requests_total{{code="Unknown",{}}} 1"#,
+        labels
+    )));
+    assert!(text.contains(&format!(
+        "mecha_grpc_request_duration_seconds_count{{{}}} 3",
+        labels
+    )));
+}
+
+#[tokio::test]
+async fn unknown_paths_share_one_label() {
+    let registry = Registry::new();
+    let metrics = RpcMetrics::new(&registry, &[FILE_DESCRIPTOR_SET]).unwrap();
+    for path in ["/a.B/C", "/displaymanager.DisplayCtrlService/Nope", "/x"] {
+        metrics.observe(path, tonic::Code::Unimplemented, Default::default());
+    }
+
+    let text = text(&registry);
+    assert!(text.contains(
+        r#"mecha_grpc_requests_total{code="Unimplemented",method="unknown",service="unknown"} 3"#
+    ));
+    assert!(!text.contains("Nope"));
+    assert!(!text.contains(r#"service="a.B""#));
+}
+
+#[tokio::test]
+async fn device_gauges_are_converted_to_base_units() {
+    let families = gauges().collect().await;
+    let value = |name: &str| {
+        let family = families
+            .iter()
+            .find(|family| family.get_name() == name)
+            .unwrap_or_else(|| panic!("{} is missing", name));
+        family.get_metric()[0].get_gauge().get_value()
+    };
+
+    assert_eq!(value("mecha_cpu_usage_percent"), 12.5);
+    assert_eq!(value("mecha_memory_used_bytes"), 512.0);
+    assert_eq!(value("mecha_disk_used_bytes"), 2048.0);
+    assert_eq!(value("mecha_battery_capacity_percent"), 76.0);
+    assert_eq!(value("mecha_battery_voltage_volts"), 3.912);
+    assert_eq!(value("mecha_battery_current_amperes"), -0.245);
+    assert_eq!(value("mecha_battery_temperature_celsius"), 28.7);
+    assert_eq!(value("mecha_cpu_frequency_hertz"), 1.2e9);
+}
+
+#[tokio::test]
+async fn unreadable_sources_are_left_out() {
+    let gauges: DeviceGauges<FakeMetrics, _, _> = DeviceGauges {
+        metrics: None,
+        battery: Some(FakeBattery::default().into()),
+        cpu: Some(Device::from(FakeCpu::default())),
+    };
+    *gauges.cpu.as_ref().unwrap().get().frequency.lock().unwrap() = "unknown".to_string();
+
+    let names: Vec<_> = gauges
+        .collect()
+        .await
+        .iter()
+        .map(|family| family.get_name().to_string())
+        .collect();
+    assert!(names.contains(&"mecha_battery_capacity_percent".to_string()));
+    assert!(!names.iter().any(|name| name.starts_with("mecha_cpu")));
+}
+
+#[tokio::test]
+async fn metrics_are_served_over_http() {
+    let registry = Registry::new();
+    RpcMetrics::new(&registry, &[FILE_DESCRIPTOR_SET])
+        .unwrap()
+        .observe(
+            "/led_ctrl.LedCtrlService/SetLed",
+            tonic::Code::Ok,
+            Default::default(),
+        );
+    let listener = bind_metrics("127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shutdown = Shutdown::new();
+    let serve = serve_metrics(listener, registry, gauges(), &shutdown).unwrap();
+    let server = tokio::spawn(serve);
+
+    let get = |path: &'static str| async move {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            path
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    };
+
+    let response = get("/metrics").await;
+    assert!(response.starts_with("HTTP/1.1 200"));
+    assert!(response.contains("content-type: text/plain; version=0.0.4"));
+    assert!(response.contains(r#"method="SetLed""#));
+    assert!(response.contains("mecha_battery_capacity_percent 76"));
+    assert!(get("/other").await.starts_with("HTTP/1.1 404"));
+
+    shutdown.trigger();
+    server.await.unwrap().unwrap();
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{connect, FakeDisplay};
+use mecha_sdk_server::services::display_manager_service::displaymanager::{
+    display_ctrl_service_client::DisplayCtrlServiceClient, SetBrightnessRequest,
+};
+use mecha_sdk_server::services::{DisplayCtrlManager, DisplayCtrlServiceServer};
+use mecha_sdk_server::telemetry::RpcTraceLayer;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use tonic::transport::Server;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+#[derive(Debug, Clone, Default)]
+struct Captured {
+    name: &'static str,
+    parent: Option<&'static str>,
+    fields: HashMap<String, String>,
+}
+
+// keeps the name, parent and fields of every span, by span id
+#[derive(Clone, Default)]
+struct Capture {
+    spans: Arc<Mutex<HashMap<u64, Captured>>>,
+}
+
+impl Capture {
+    fn named(&self, name: &str) -> Vec<Captured> {
+        let spans = self.spans.lock().unwrap();
+        let mut spans: Vec<_> = spans.iter().filter(|(_, span)| span.name == name).collect();
+        spans.sort_by_key(|(id, _)| **id);
+        spans.into_iter().map(|(_, span)| span.clone()).collect()
+    }
+}
+
+struct Fields<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl<S> Layer<S> for Capture
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut span = Captured {
+            name: attrs.metadata().name(),
+            parent: ctx
+                .span(id)
+                .and_then(|span| span.parent())
+                .map(|parent| parent.name()),
+            ..Default::default()
+        };
+        attrs.record(&mut Fields(&mut span.fields));
+        self.spans.lock().unwrap().insert(id.into_u64(), span);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        if let Some(span) = self.spans.lock().unwrap().get_mut(&id.into_u64()) {
+            values.record(&mut Fields(&mut span.fields));
+        }
+    }
+}
+
+// the default subscriber is per thread, the current thread runtime keeps
+// the server on the test's thread
+#[tokio::test]
+async fn rpc_spans_carry_method_status_and_device_calls() {
+    let capture = Capture::default();
+    let _default =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(capture.clone()));
+    let router = Server::builder()
+        .layer(RpcTraceLayer)
+        .add_service(DisplayCtrlServiceServer::new(DisplayCtrlManager {
+            display_ctrl: FakeDisplay::default().into(),
+        }));
+    let mut display = DisplayCtrlServiceClient::new(connect(router).await);
+
+    display
+        .set_brightness(SetBrightnessRequest { brightness: 80 })
+        .await
+        .unwrap();
+    display
+        .set_brightness(SetBrightnessRequest { brightness: 250 })
+        .await
+        .unwrap_err();
+
+    let rpcs = capture.named("rpc");
+    assert_eq!(rpcs.len(), 2);
+    for (rpc, status) in rpcs.iter().zip(["Ok", "Unknown"]) {
+        assert_eq!(rpc.fields["service"], "displaymanager.DisplayCtrlService");
+        assert_eq!(rpc.fields["method"], "SetBrightness");
+        assert_eq!(rpc.fields["status"], status);
+        // the in-memory transport has no socket address
+        assert_eq!(rpc.fields["peer"], "unknown");
+    }
+    assert_eq!(rpcs[1].fields["otel.status_code"], "ERROR");
+    assert!(!rpcs[0].fields.contains_key("otel.status_code"));
+
+    let devices = capture.named("device");
+    assert!(!devices.is_empty());
+    assert!(devices.iter().all(|device| device.parent == Some("rpc")));
+}
@@ -0,0 +1,13 @@
+use mecha_simulator::{SimulatedBoard, SysfsRoot};
+
+// usage: mecha_sim_gen [ROOT]
+fn main() -> anyhow::Result<()> {
+    let root = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "./mecha-sim".to_string());
+
+    let board = SimulatedBoard::new(&SysfsRoot::new(&root));
+    let created = board.generate()?;
+    println!("simulated board tree ready at {} ({} files created)", root, created);
+    Ok(())
+}
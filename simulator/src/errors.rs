@@ -0,0 +1,34 @@
+#[derive(Debug)]
+pub enum SimulatorErrorCodes {
+    InvalidRootPath,
+    UnableToCreateDirectory,
+    UnableToWriteFile,
+}
+
+impl std::fmt::Display for SimulatorErrorCodes {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            SimulatorErrorCodes::InvalidRootPath => write!(f, "InvalidRootPath"),
+            SimulatorErrorCodes::UnableToCreateDirectory => write!(f, "UnableToCreateDirectory"),
+            SimulatorErrorCodes::UnableToWriteFile => write!(f, "UnableToWriteFile"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SimulatorError {
+    pub code: SimulatorErrorCodes,
+    pub message: String,
+}
+
+impl std::fmt::Display for SimulatorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "(code: {:?}, message: {})", self.code, self.message)
+    }
+}
+
+impl SimulatorError {
+    pub fn new(code: SimulatorErrorCodes, message: String) -> Self {
+        SimulatorError { code, message }
+    }
+}
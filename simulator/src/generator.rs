@@ -0,0 +1,202 @@
+use crate::{SimulatorError, SimulatorErrorCodes, SysfsRoot};
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::Path;
+use tracing::{error as trace_error, info, trace};
+
+// sysfs/devfs layout of a mecha-compute-g1 board, paths are relative to the root
+const BOARD_FILES: &[(&str, &str)] = &[
+    // backlight
+    ("sys/class/backlight/backlight/brightness", "120\n"),
+    ("sys/class/backlight/backlight/actual_brightness", "120\n"),
+    ("sys/class/backlight/backlight/max_brightness", "244\n"),
+    ("sys/class/backlight/backlight/bl_power", "0\n"),
+    // leds
+    ("sys/class/leds/red-led/brightness", "0\n"),
+    ("sys/class/leds/red-led/max_brightness", "1\n"),
+    ("sys/class/leds/red-led/trigger", "[none] timer heartbeat\n"),
+    ("sys/class/leds/green-led/brightness", "0\n"),
+    ("sys/class/leds/green-led/max_brightness", "1\n"),
+    ("sys/class/leds/green-led/trigger", "[none] timer heartbeat\n"),
+    ("sys/class/leds/blue-led/brightness", "0\n"),
+    ("sys/class/leds/blue-led/max_brightness", "1\n"),
+    ("sys/class/leds/blue-led/trigger", "[none] timer heartbeat\n"),
+    // iio adc
+    ("sys/bus/iio/devices/iio:device0/name", "ads1015\n"),
+    ("sys/bus/iio/devices/iio:device0/in_voltage0_raw", "1024\n"),
+    ("sys/bus/iio/devices/iio:device0/in_voltage1_raw", "512\n"),
+    ("sys/bus/iio/devices/iio:device0/in_voltage0_sampling_frequency", "1600\n"),
+    // iio gyroscope
+    ("sys/bus/iio/devices/iio:device1/name", "bmi088_gyro\n"),
+    ("sys/bus/iio/devices/iio:device1/in_anglvel_x_raw", "0\n"),
+    ("sys/bus/iio/devices/iio:device1/in_anglvel_y_raw", "0\n"),
+    ("sys/bus/iio/devices/iio:device1/in_anglvel_z_raw", "0\n"),
+    ("sys/bus/iio/devices/iio:device1/in_anglvel_scale", "0.001065264\n"),
+    // fuel gauge
+    (
+        "sys/class/power_supply/bq27441-0/uevent",
+        "POWER_SUPPLY_NAME=bq27441-0\n\
+         POWER_SUPPLY_TYPE=Battery\n\
+         POWER_SUPPLY_STATUS=Discharging\n\
+         POWER_SUPPLY_PRESENT=1\n\
+         POWER_SUPPLY_VOLTAGE_NOW=3912000\n\
+         POWER_SUPPLY_CURRENT_NOW=-245000\n\
+         POWER_SUPPLY_CAPACITY=76\n\
+         POWER_SUPPLY_CAPACITY_LEVEL=Normal\n\
+         POWER_SUPPLY_TEMP=287\n\
+         POWER_SUPPLY_TECHNOLOGY=Li-ion\n\
+         POWER_SUPPLY_CHARGE_FULL=2830000\n\
+         POWER_SUPPLY_CHARGE_NOW=2150000\n\
+         POWER_SUPPLY_CHARGE_FULL_DESIGN=3000000\n\
+         POWER_SUPPLY_MANUFACTURER=Texas Instruments\n",
+    ),
+    ("sys/class/power_supply/bq27441-0/type", "Battery\n"),
+    ("sys/class/power_supply/bq27441-0/status", "Discharging\n"),
+    ("sys/class/power_supply/bq27441-0/capacity", "76\n"),
+    ("sys/class/power_supply/bq27441-0/voltage_now", "3912000\n"),
+    ("sys/class/power_supply/bq27441-0/current_now", "-245000\n"),
+    ("sys/class/power_supply/bq27441-0/temp", "287\n"),
+    // cpufreq
+    ("sys/devices/system/cpu/cpu0/cpufreq/scaling_governor", "userspace\n"),
+    (
+        "sys/devices/system/cpu/cpu0/cpufreq/scaling_available_governors",
+        "conservative ondemand userspace powersave performance schedutil\n",
+    ),
+    ("sys/devices/system/cpu/cpu0/cpufreq/scaling_cur_freq", "1200000\n"),
+    ("sys/devices/system/cpu/cpu0/cpufreq/scaling_setspeed", "1200000\n"),
+    (
+        "sys/devices/system/cpu/cpu0/cpufreq/scaling_available_frequencies",
+        "1200000 1600000 1800000\n",
+    ),
+    ("sys/devices/system/cpu/cpu0/cpufreq/cpuinfo_min_freq", "1200000\n"),
+    ("sys/devices/system/cpu/cpu0/cpufreq/cpuinfo_max_freq", "1800000\n"),
+    ("sys/devices/system/cpu/cpu0/cpufreq/affected_cpus", "0 1 2 3\n"),
+    // camera
+    ("dev/video0", ""),
+    // board identification, read by `name: auto`
+    ("proc/device-tree/compatible", "mecha,compute-g1\0fsl,imx8mm\0"),
+];
+
+#[derive(Debug)]
+pub struct SimulatedBoard {
+    root: SysfsRoot,
+}
+
+impl SimulatedBoard {
+    pub fn new(root: &SysfsRoot) -> Self {
+        trace!(task = "new", "init");
+        SimulatedBoard { root: root.clone() }
+    }
+
+    pub fn root(&self) -> &SysfsRoot {
+        &self.root
+    }
+
+    // populate the fake tree, files that already exist keep their current value
+    // so state written by a previous run survives a restart. returns the number
+    // of files created.
+    pub fn generate(&self) -> Result<usize> {
+        trace!(task = "generate", "init");
+        if self.root.is_host() {
+            trace_error!(task = "generate", "refusing to generate into /");
+            bail!(SimulatorError::new(
+                SimulatorErrorCodes::InvalidRootPath,
+                "simulation root must not be /".to_string(),
+            ));
+        }
+
+        let mut created = 0;
+        for (relative, contents) in BOARD_FILES {
+            let path = self.root.path().join(relative);
+            if path.exists() {
+                continue;
+            }
+            self.write_file(&path, contents)?;
+            created += 1;
+        }
+
+        info!(
+            task = "generate",
+            "created {} files under {}",
+            created,
+            self.root.path().display()
+        );
+        Ok(created)
+    }
+
+    fn write_file(&self, path: &Path, contents: &str) -> Result<()> {
+        trace!(task = "write_file", "init");
+        if let Some(parent) = path.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                trace_error!(
+                    task = "write_file",
+                    "unable to create {}: {}",
+                    parent.display(),
+                    e
+                );
+                bail!(SimulatorError::new(
+                    SimulatorErrorCodes::UnableToCreateDirectory,
+                    format!("unable to create {}: {}", parent.display(), e),
+                ));
+            }
+        }
+
+        if let Err(e) = fs::write(path, contents) {
+            trace_error!(
+                task = "write_file",
+                "unable to write {}: {}",
+                path.display(),
+                e
+            );
+            bail!(SimulatorError::new(
+                SimulatorErrorCodes::UnableToWriteFile,
+                format!("unable to write {}: {}", path.display(), e),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SimulatedBoard, BOARD_FILES};
+    use crate::{SimulatorError, SimulatorErrorCodes, SysfsRoot};
+    use std::fs;
+
+    #[test]
+    fn refuses_to_generate_into_the_host_root() {
+        let err = SimulatedBoard::new(&SysfsRoot::default())
+            .generate()
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<SimulatorError>(),
+            Some(SimulatorError {
+                code: SimulatorErrorCodes::InvalidRootPath,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn generate_creates_the_board_and_keeps_existing_values() {
+        let dir = std::env::temp_dir().join(format!("mecha-sim-generate-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let root = SysfsRoot::new(&dir);
+        let board = SimulatedBoard::new(&root);
+
+        assert_eq!(board.generate().unwrap(), BOARD_FILES.len());
+        let brightness = root.rebase("/sys/class/backlight/backlight/brightness");
+        assert_eq!(fs::read_to_string(&brightness).unwrap(), "120\n");
+        assert!(fs::metadata(root.rebase("/dev/video0")).unwrap().is_file());
+
+        // state written by a previous run survives, missing files come back
+        fs::write(&brightness, "42\n").unwrap();
+        let capacity = root.rebase("/sys/class/power_supply/bq27441-0/capacity");
+        fs::remove_file(&capacity).unwrap();
+        assert_eq!(board.generate().unwrap(), 1);
+        assert_eq!(fs::read_to_string(&brightness).unwrap(), "42\n");
+        assert_eq!(fs::read_to_string(&capacity).unwrap(), "76\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,9 @@
+#![deny(clippy::all)]
+mod sysfs_root;
+pub use sysfs_root::SysfsRoot;
+
+mod generator;
+pub use generator::SimulatedBoard;
+
+mod errors;
+pub use errors::{SimulatorError, SimulatorErrorCodes};
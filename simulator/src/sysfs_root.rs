@@ -0,0 +1,71 @@
+use std::path::{Path, PathBuf};
+
+/// Directory that absolute sysfs/devfs paths are resolved against.
+///
+/// On a board this is `/`, so paths are used as-is. In simulation mode it
+/// points at a generated tree and `/sys/class/...` becomes
+/// `<root>/sys/class/...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SysfsRoot {
+    root: PathBuf,
+}
+
+impl Default for SysfsRoot {
+    fn default() -> Self {
+        SysfsRoot {
+            root: PathBuf::from("/"),
+        }
+    }
+}
+
+impl SysfsRoot {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        SysfsRoot {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.root == Path::new("/")
+    }
+
+    // relative paths (e.g. the audio sample file) are left untouched
+    pub fn rebase(&self, path: &str) -> String {
+        match Path::new(path).strip_prefix("/") {
+            Ok(relative) if !self.is_host() => self.root.join(relative).to_string_lossy().into_owned(),
+            _ => path.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SysfsRoot;
+
+    #[test]
+    fn host_root_leaves_paths_alone() {
+        let root = SysfsRoot::default();
+        assert!(root.is_host());
+        assert!(SysfsRoot::new("/").is_host());
+        assert_eq!(
+            root.rebase("/sys/class/leds/red-led/brightness"),
+            "/sys/class/leds/red-led/brightness"
+        );
+    }
+
+    #[test]
+    fn simulated_root_rebases_absolute_paths() {
+        let root = SysfsRoot::new("/tmp/mecha-sim");
+        assert!(!root.is_host());
+        assert_eq!(
+            root.rebase("/sys/class/backlight/backlight/brightness"),
+            "/tmp/mecha-sim/sys/class/backlight/backlight/brightness"
+        );
+        assert_eq!(root.rebase("/dev/video0"), "/tmp/mecha-sim/dev/video0");
+        assert_eq!(root.rebase("sample.wav"), "sample.wav");
+    }
+}
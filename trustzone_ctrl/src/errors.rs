@@ -1,3 +1,4 @@
+use mecha_errors::{DomainError, ErrorCode, ErrorKind};
 #[derive(Debug)]
 pub enum TrustZoneCtrlErrorCodes {
     // define your error codes here
@@ -87,3 +88,39 @@ impl TrustZoneCtrlError {
         TrustZoneCtrlError { code, message }
     }
 }
+
+impl ErrorCode for TrustZoneCtrlErrorCodes {
+    fn domain(&self) -> &'static str {
+        "mecha_trustzone_ctrl"
+    }
+
+    fn kind(&self) -> ErrorKind {
+        match *self {
+            TrustZoneCtrlErrorCodes::FileReadError => ErrorKind::Unavailable,
+            TrustZoneCtrlErrorCodes::UnableToVerifyToken
+            | TrustZoneCtrlErrorCodes::UnableToVerifyTrustZoneData => ErrorKind::InvalidArgument,
+            TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert
+            | TrustZoneCtrlErrorCodes::UnableToWriteTrustZoneCert
+            | TrustZoneCtrlErrorCodes::UnableToRemoveTrustZoneCert
+            | TrustZoneCtrlErrorCodes::UnableToGenrateToken
+            | TrustZoneCtrlErrorCodes::UnableToSignTrust
+            | TrustZoneCtrlErrorCodes::UnableToSignTrustZoneData
+            | TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneKey
+            | TrustZoneCtrlErrorCodes::UnableToReadTrustDeviceKey
+            | TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneHMAC
+            | TrustZoneCtrlErrorCodes::UnableToDeriveTrustZoneKey
+            | TrustZoneCtrlErrorCodes::UnableToDecryptTrust
+            | TrustZoneCtrlErrorCodes::UnableToEncryptTrust => ErrorKind::Internal,
+        }
+    }
+}
+
+impl DomainError for TrustZoneCtrlError {
+    fn code(&self) -> &dyn ErrorCode {
+        &self.code
+    }
+
+    fn message(&self) -> &str {
+        &self.message
+    }
+}
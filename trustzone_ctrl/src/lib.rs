@@ -1,7 +1,7 @@
 #![deny(clippy::all)]
 
 mod trust_ctrl;
-pub use trust_ctrl::{TrustZoneCtrl,KeySize,KeyType};
+pub use trust_ctrl::{KeySize, KeyType, TrustZoneControl, TrustZoneCtrl};
 
 mod errors;
 pub use errors::{TrustZoneCtrlError, TrustZoneCtrlErrorCodes};
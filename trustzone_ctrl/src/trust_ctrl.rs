@@ -1,13 +1,25 @@
-use std::{fs, process::Command};
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+use std::{fs, thread};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use tracing::{error as trace_error, info, trace};
 
 use crate::{TrustZoneCtrlError, TrustZoneCtrlErrorCodes};
 
+// how long a trustm tool may run before it is killed
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(5);
+// how often a running tool is checked for having exited
+const TOOL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 #[derive(Debug, Default)]
 pub struct TrustZoneCtrl {
+    // directory holding the trustm tools
     pub path: String,
+    // tools still running after this long are killed, none waits forever
+    pub timeout: Option<Duration>,
 }
 
 // Key type enum
@@ -32,41 +44,195 @@ pub enum KeySize {
     BRAINPOOL512,
 }
 
+pub trait TrustZoneControl {
+    fn read_trustzone_cert(&self, output_file: &str, region: &str) -> Result<String>;
+    fn write_trustzone_cert(&self, cert_file: &str, oid: &str) -> Result<()>;
+    fn remove_trustzone_cert(&self, oid: &str) -> Result<()>;
+    fn generate_trustzone_key(
+        &self,
+        oid: &str,
+        key_type: KeyType,
+        key_size: KeySize,
+        output_file: &str,
+    ) -> Result<String>;
+    fn sign_trustzone_data(
+        &self,
+        key_oid: &str,
+        input_file: &str,
+        output_file: &str,
+        hash_before_sign: bool,
+    ) -> Result<String>;
+    fn verify_trustzone_data(
+        &self,
+        pubkey_file: &str,
+        input_file: &str,
+        signature_file: &str,
+        hash_before_verify: bool,
+    ) -> Result<String>;
+    fn derive_trustzone_key(
+        &self,
+        secret_oid: &str,
+        hkdf_type: u16,
+        info_file: &str,
+        salt_file: &str,
+        output_file: &str,
+    ) -> Result<String>;
+    fn generate_trustzone_hmac(
+        &self,
+        secret_oid: &str,
+        hmac_type: u16,
+        input_data: &str,
+        output_file: &str,
+    ) -> Result<String>;
+}
+
 impl TrustZoneCtrl {
     pub fn new() -> Self {
         TrustZoneCtrl {
             path: String::from("/MECHA_TEST/optiga_trust_m"),
+            timeout: Some(DEFAULT_TOOL_TIMEOUT),
         }
     }
 
-    fn read_value_from_file(&self, path: &str) -> Result<String, TrustZoneCtrlError> {
+    // run the trustm tool `args[0]` with the remaining arguments. a hung
+    // secure element would block the caller, and the device, for good, so
+    // the tool is killed once it runs past the timeout
+    fn run_tool(&self, args: &[&str]) -> io::Result<Output> {
+        let mut child = Command::new(Path::new(&self.path).join(args[0]))
+            .args(&args[1..])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // drained while waiting, a tool blocked on a full pipe never exits
+        let stdout = drain(child.stdout.take());
+        let stderr = drain(child.stderr.take());
+
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("{} did not exit within {:?}", args[0], self.timeout),
+                ));
+            }
+            thread::sleep(TOOL_POLL_INTERVAL);
+        };
+        Ok(Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    }
+
+    fn read_value_from_file(&self, path: &str) -> Result<String> {
         fs::read_to_string(path).map_err(|e| {
-            TrustZoneCtrlError::new(TrustZoneCtrlErrorCodes::FileReadError, e.to_string())
+            let message = e.to_string();
+            anyhow!(e).context(TrustZoneCtrlError::new(
+                TrustZoneCtrlErrorCodes::FileReadError,
+                message,
+            ))
         })
     }
 
+    //encrypt_trustzone_data that accept path of file using trust ic and return it that will be type of string or error using match and anyhow error
+    pub fn encrypt_trustzone_data(&self, _data: &str) -> Result<String> {
+        trace!(task = "encrypt_trustzone_data", "init");
+        //read x,y,z values from the motion sensor or error using match and anyhow error
+        let encrypted_data = match self.read_value_from_file("/dev/trustzone_encrypt") {
+            Ok(x) => {
+                info!(task = "encrypt_trustzone_data", "encrypted_data: {}", x);
+                x
+            }
+            Err(e) => {
+                trace_error!(
+                    task = "encrypt_trustzone_data",
+                    "unable to read encrypted_data: {}",
+                    e
+                );
+                let message = format!("unable to read encrypted_data: {}", e);
+                return Err(e).context(TrustZoneCtrlError::new(
+                    TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
+                    message,
+                ));
+            }
+        };
+        Ok(encrypted_data)
+    }
+
+    //decrypt_trustzone_data that accept path of file using trust ic and return it that will be type of string or error using match and anyhow error
+    pub fn decrypt_trustzone_data(&self, _data: &str) -> Result<String> {
+        trace!(task = "decrypt_trustzone_data", "init");
+        //read x,y,z values from the motion sensor or error using match and anyhow error
+        let decrypted_data = match self.read_value_from_file("/dev/trustzone_decrypt") {
+            Ok(x) => {
+                info!(task = "decrypt_trustzone_data", "decrypted_data: {}", x);
+                x
+            }
+            Err(e) => {
+                trace_error!(
+                    task = "decrypt_trustzone_data",
+                    "unable to read decrypted_data: {}",
+                    e
+                );
+                let message = format!("unable to read decrypted_data: {}", e);
+                return Err(e).context(TrustZoneCtrlError::new(
+                    TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
+                    message,
+                ));
+            }
+        };
+        Ok(decrypted_data)
+    }
+
+    //encrypt_trustzone_data that accept path of file using trust ic and return it that will be type of string or error using match and anyhow error
+    pub fn encrypt_trustzone_data_with_key(&self, _data: &str, _key: &str) -> Result<String> {
+        trace!(task = "encrypt_trustzone_data_with_key", "init");
+        //read x,y,z values from the motion sensor or error using match and anyhow error
+        let encrypted_data = match self.read_value_from_file("/dev/trustzone_encrypt") {
+            Ok(x) => {
+                info!(
+                    task = "encrypt_trustzone_data_with_key",
+                    "encrypted_data: {}", x
+                );
+                x
+            }
+            Err(e) => {
+                trace_error!(
+                    task = "encrypt_trustzone_data_with_key",
+                    "unable to read encrypted_data: {}",
+                    e
+                );
+                let message = format!("unable to read encrypted_data: {}", e);
+                return Err(e).context(TrustZoneCtrlError::new(
+                    TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
+                    message,
+                ));
+            }
+        };
+        Ok(encrypted_data)
+    }
+}
+
+impl TrustZoneControl for TrustZoneCtrl {
     //write a function to write a file to the trustzone ic and return ok or error
 
     //read_trustzone_cert we need to read the cert from the trustzone ic and return it that will be type of
-    pub fn read_trustzone_cert(&self, output_file: &str, region: &str) -> Result<String> {
+    fn read_trustzone_cert(&self, output_file: &str, region: &str) -> Result<String> {
         trace!(task = "read_trustzone_cert", "init");
 
-        let command_args = [
-            "/MECHA_TEST/optiga_trust_m/trustm_cert",
-            "-r",
-            region,
-            "-o",
-            output_file,
-        ];
-        let command_output = Command::new(&command_args[0])
-            .args(&command_args[1..])
-            .output()
-            .map_err(|e| {
-                TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
-                    format!("Error executing trustm_cert: {}", e),
-                )
-            });
+        let command_args = ["trustm_cert", "-r", region, "-o", output_file];
+        let command_output = self.run_tool(&command_args).map_err(|e| {
+            let message = format!("Error executing trustm_cert: {}", e);
+            anyhow!(e).context(TrustZoneCtrlError::new(
+                TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
+                message,
+            ))
+        });
 
         match command_output {
             Ok(output) => {
@@ -81,10 +247,11 @@ impl TrustZoneCtrl {
                     }
                     Err(e) => {
                         trace_error!(task = "read_trustzone_cert", "unable to read cert: {}", e);
-                        bail!(TrustZoneCtrlError::new(
+                        let message = format!("unable to read cert: {}", e);
+                        return Err(e).context(TrustZoneCtrlError::new(
                             TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
-                            format!("unable to read cert: {}", e),
-                        ))
+                            message,
+                        ));
                     }
                 };
 
@@ -92,35 +259,28 @@ impl TrustZoneCtrl {
             }
             Err(e) => {
                 trace_error!(task = "read_trustzone_cert", "unable to read cert: {}", e);
-                bail!(TrustZoneCtrlError::new(
+                let message = format!("unable to read cert: {}", e);
+                Err(e).context(TrustZoneCtrlError::new(
                     TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
-                    format!("unable to read cert: {}", e),
+                    message,
                 ))
             }
         }
     }
 
     //write_trustzone_cert we need to write the cert to the trustzone ic and return ok or error using match and anyhow error
-    pub fn write_trustzone_cert(&self, cert_file: &str, oid: &str) -> Result<()> {
+    fn write_trustzone_cert(&self, cert_file: &str, oid: &str) -> Result<()> {
         trace!(task = "write_trustzone_cert", "init");
 
-        let command_args = [
-            "/MECHA_TEST/optiga_trust_m/trustm_cert",
-            "-w",
-            oid,
-            "-i",
-            cert_file,
-        ];
+        let command_args = ["trustm_cert", "-w", oid, "-i", cert_file];
 
-        let command_output = Command::new(&command_args[0])
-            .args(&command_args[1..])
-            .output()
-            .map_err(|e| {
-                TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToWriteTrustZoneCert,
-                    format!("Error executing trustm_cert: {}", e),
-                )
-            });
+        let command_output = self.run_tool(&command_args).map_err(|e| {
+            let message = format!("Error executing trustm_cert: {}", e);
+            anyhow!(e).context(TrustZoneCtrlError::new(
+                TrustZoneCtrlErrorCodes::UnableToWriteTrustZoneCert,
+                message,
+            ))
+        });
 
         match command_output {
             Ok(output) => {
@@ -143,29 +303,28 @@ impl TrustZoneCtrl {
             }
             Err(e) => {
                 trace_error!(task = "write_trustzone_cert", "unable to write cert: {}", e);
-                bail!(TrustZoneCtrlError::new(
+                let message = format!("unable to write cert: {}", e);
+                Err(e).context(TrustZoneCtrlError::new(
                     TrustZoneCtrlErrorCodes::UnableToWriteTrustZoneCert,
-                    format!("unable to write cert: {}", e),
+                    message,
                 ))
             }
         }
     }
 
     //remove_trustzone_cert form the trustzone ic and return ok or error using match and anyhow error
-    pub fn remove_trustzone_cert(&self, oid: &str) -> Result<()> {
+    fn remove_trustzone_cert(&self, oid: &str) -> Result<()> {
         trace!(task = "remove_trustzone_cert", "init");
 
-        let command_args = ["/MECHA_TEST/optiga_trust_m/trustm_cert", "-c", oid];
+        let command_args = ["trustm_cert", "-c", oid];
 
-        let command_output = Command::new(&command_args[0])
-            .args(&command_args[1..])
-            .output()
-            .map_err(|e| {
-                TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToRemoveTrustZoneCert,
-                    format!("Error executing trustm_cert: {}", e),
-                )
-            });
+        let command_output = self.run_tool(&command_args).map_err(|e| {
+            let message = format!("Error executing trustm_cert: {}", e);
+            anyhow!(e).context(TrustZoneCtrlError::new(
+                TrustZoneCtrlErrorCodes::UnableToRemoveTrustZoneCert,
+                message,
+            ))
+        });
 
         match command_output {
             Ok(output) => {
@@ -192,16 +351,17 @@ impl TrustZoneCtrl {
                     "unable to remove cert: {}",
                     e
                 );
-                bail!(TrustZoneCtrlError::new(
+                let message = format!("unable to remove cert: {}", e);
+                Err(e).context(TrustZoneCtrlError::new(
                     TrustZoneCtrlErrorCodes::UnableToRemoveTrustZoneCert,
-                    format!("unable to remove cert: {}", e),
+                    message,
                 ))
             }
         }
     }
 
     //generate_trustzone_key we need to generate a key uisng trust ic and return it that will be type of string or error using match and anyhow error
-    pub fn generate_trustzone_key(
+    fn generate_trustzone_key(
         &self,
         oid: &str,
         key_type: KeyType,
@@ -229,7 +389,7 @@ impl TrustZoneCtrl {
         };
 
         let command_args = [
-            "/MECHA_TEST/optiga_trust_m/trustm_ecc_keygen",
+            "trustm_ecc_keygen",
             "-g",
             oid,
             "-t",
@@ -240,15 +400,13 @@ impl TrustZoneCtrl {
             output_file,
         ];
 
-        let command_output = Command::new(&command_args[0])
-            .args(&command_args[1..])
-            .output()
-            .map_err(|e| {
-                TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneKey,
-                    format!("Error executing trustm_ecc_keygen: {}", e),
-                )
-            });
+        let command_output = self.run_tool(&command_args).map_err(|e| {
+            let message = format!("Error executing trustm_ecc_keygen: {}", e);
+            anyhow!(e).context(TrustZoneCtrlError::new(
+                TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneKey,
+                message,
+            ))
+        });
 
         match command_output {
             Ok(output) => {
@@ -271,9 +429,10 @@ impl TrustZoneCtrl {
                                 "unable to read public key: {}",
                                 e
                             );
-                            bail!(TrustZoneCtrlError::new(
+                            let message = format!("unable to read public key: {}", e);
+                            Err(e).context(TrustZoneCtrlError::new(
                                 TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneKey,
-                                format!("unable to read public key: {}", e),
+                                message,
                             ))
                         }
                     }
@@ -295,16 +454,17 @@ impl TrustZoneCtrl {
                     "unable to generate key: {}",
                     e
                 );
-                bail!(TrustZoneCtrlError::new(
+                let message = format!("unable to generate key: {}", e);
+                Err(e).context(TrustZoneCtrlError::new(
                     TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneKey,
-                    format!("unable to generate key: {}", e),
+                    message,
                 ))
             }
         }
     }
 
     //sign_trustzone_data uisng trust ic and return it that will be type of string or error using match and anyhow error
-    pub fn sign_trustzone_data(
+    fn sign_trustzone_data(
         &self,
         key_oid: &str,
         input_file: &str,
@@ -314,7 +474,7 @@ impl TrustZoneCtrl {
         trace!(task = "sign_trustzone_data", "init");
 
         let mut command_args = vec![
-            "/MECHA_TEST/optiga_trust_m/trustm_ecc_sign",
+            "trustm_ecc_sign",
             "-k",
             key_oid,
             "-o",
@@ -327,15 +487,13 @@ impl TrustZoneCtrl {
             command_args.push("-H");
         }
 
-        let command_output = Command::new(&command_args[0])
-            .args(&command_args[1..])
-            .output()
-            .map_err(|e| {
-                TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToSignTrustZoneData,
-                    format!("Error executing trustm_ecc_sign: {}", e),
-                )
-            });
+        let command_output = self.run_tool(&command_args).map_err(|e| {
+            let message = format!("Error executing trustm_ecc_sign: {}", e);
+            anyhow!(e).context(TrustZoneCtrlError::new(
+                TrustZoneCtrlErrorCodes::UnableToSignTrustZoneData,
+                message,
+            ))
+        });
 
         match command_output {
             Ok(output) => {
@@ -355,9 +513,10 @@ impl TrustZoneCtrl {
                                 "unable to read signed data: {}",
                                 e
                             );
-                            bail!(TrustZoneCtrlError::new(
+                            let message = format!("unable to read signed data: {}", e);
+                            Err(e).context(TrustZoneCtrlError::new(
                                 TrustZoneCtrlErrorCodes::UnableToSignTrustZoneData,
-                                format!("unable to read signed data: {}", e),
+                                message,
                             ))
                         }
                     }
@@ -375,16 +534,17 @@ impl TrustZoneCtrl {
             }
             Err(e) => {
                 trace_error!(task = "sign_trustzone_data", "unable to sign data: {}", e);
-                bail!(TrustZoneCtrlError::new(
+                let message = format!("unable to sign data: {}", e);
+                Err(e).context(TrustZoneCtrlError::new(
                     TrustZoneCtrlErrorCodes::UnableToSignTrustZoneData,
-                    format!("unable to sign data: {}", e),
+                    message,
                 ))
             }
         }
     }
 
     //verify_trustzone_data that accept path of file using trust ic and return it that will be type of string or error using match and anyhow error
-    pub fn verify_trustzone_data(
+    fn verify_trustzone_data(
         &self,
         pubkey_file: &str,
         input_file: &str,
@@ -394,7 +554,7 @@ impl TrustZoneCtrl {
         trace!(task = "verify_trustzone_data", "init");
 
         let mut command_args = vec![
-            "/MECHA_TEST/optiga_trust_m/trustm_ecc_verify",
+            "trustm_ecc_verify",
             "-p",
             pubkey_file,
             "-i",
@@ -407,15 +567,13 @@ impl TrustZoneCtrl {
             command_args.push("-H");
         }
 
-        let command_output = Command::new(&command_args[0])
-            .args(&command_args[1..])
-            .output()
-            .map_err(|e| {
-                TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToVerifyTrustZoneData,
-                    format!("Error executing trustm_ecc_verify: {}", e),
-                )
-            });
+        let command_output = self.run_tool(&command_args).map_err(|e| {
+            let message = format!("Error executing trustm_ecc_verify: {}", e);
+            anyhow!(e).context(TrustZoneCtrlError::new(
+                TrustZoneCtrlErrorCodes::UnableToVerifyTrustZoneData,
+                message,
+            ))
+        });
 
         match command_output {
             Ok(output) => {
@@ -442,89 +600,16 @@ impl TrustZoneCtrl {
                     "unable to verify data: {}",
                     e
                 );
-                bail!(TrustZoneCtrlError::new(
+                let message = format!("unable to verify data: {}", e);
+                Err(e).context(TrustZoneCtrlError::new(
                     TrustZoneCtrlErrorCodes::UnableToVerifyTrustZoneData,
-                    format!("unable to verify data: {}", e),
+                    message,
                 ))
             }
         }
     }
-    //encrypt_trustzone_data that accept path of file using trust ic and return it that will be type of string or error using match and anyhow error
-    pub fn encrypt_trustzone_data(&self, _data: &str) -> Result<String> {
-        trace!(task = "encrypt_trustzone_data", "init");
-        //read x,y,z values from the motion sensor or error using match and anyhow error
-        let encrypted_data = match self.read_value_from_file("/dev/trustzone_encrypt") {
-            Ok(x) => {
-                info!(task = "encrypt_trustzone_data", "encrypted_data: {}", x);
-                x
-            }
-            Err(e) => {
-                trace_error!(
-                    task = "encrypt_trustzone_data",
-                    "unable to read encrypted_data: {}",
-                    e
-                );
-                bail!(TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
-                    format!("unable to read encrypted_data: {}", e),
-                ))
-            }
-        };
-        Ok(encrypted_data)
-    }
-
-    //decrypt_trustzone_data that accept path of file using trust ic and return it that will be type of string or error using match and anyhow error
-    pub fn decrypt_trustzone_data(&self, _data: &str) -> Result<String> {
-        trace!(task = "decrypt_trustzone_data", "init");
-        //read x,y,z values from the motion sensor or error using match and anyhow error
-        let decrypted_data = match self.read_value_from_file("/dev/trustzone_decrypt") {
-            Ok(x) => {
-                info!(task = "decrypt_trustzone_data", "decrypted_data: {}", x);
-                x
-            }
-            Err(e) => {
-                trace_error!(
-                    task = "decrypt_trustzone_data",
-                    "unable to read decrypted_data: {}",
-                    e
-                );
-                bail!(TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
-                    format!("unable to read decrypted_data: {}", e),
-                ))
-            }
-        };
-        Ok(decrypted_data)
-    }
 
-    //encrypt_trustzone_data that accept path of file using trust ic and return it that will be type of string or error using match and anyhow error
-    pub fn encrypt_trustzone_data_with_key(&self, _data: &str, _key: &str) -> Result<String> {
-        trace!(task = "encrypt_trustzone_data_with_key", "init");
-        //read x,y,z values from the motion sensor or error using match and anyhow error
-        let encrypted_data = match self.read_value_from_file("/dev/trustzone_encrypt") {
-            Ok(x) => {
-                info!(
-                    task = "encrypt_trustzone_data_with_key",
-                    "encrypted_data: {}", x
-                );
-                x
-            }
-            Err(e) => {
-                trace_error!(
-                    task = "encrypt_trustzone_data_with_key",
-                    "unable to read encrypted_data: {}",
-                    e
-                );
-                bail!(TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToReadTrustZoneCert,
-                    format!("unable to read encrypted_data: {}", e),
-                ))
-            }
-        };
-        Ok(encrypted_data)
-    }
-
-    pub fn derive_trustzone_key(
+    fn derive_trustzone_key(
         &self,
         secret_oid: &str,
         hkdf_type: u16,
@@ -535,7 +620,7 @@ impl TrustZoneCtrl {
         trace!(task = "derive_trustzone_key", "init");
 
         let command_args = [
-            "/MECHA_TEST/optiga_trust_m/trustm_hkdf",
+            "trustm_hkdf",
             "-i",
             secret_oid,
             "-H",
@@ -548,15 +633,13 @@ impl TrustZoneCtrl {
             output_file,
         ];
 
-        let command_output = Command::new(&command_args[0])
-            .args(&command_args[1..])
-            .output()
-            .map_err(|e| {
-                TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToDeriveTrustZoneKey,
-                    format!("Error executing trustm_hkdf: {}", e),
-                )
-            });
+        let command_output = self.run_tool(&command_args).map_err(|e| {
+            let message = format!("Error executing trustm_hkdf: {}", e);
+            anyhow!(e).context(TrustZoneCtrlError::new(
+                TrustZoneCtrlErrorCodes::UnableToDeriveTrustZoneKey,
+                message,
+            ))
+        });
 
         match command_output {
             Ok(output) => {
@@ -579,9 +662,10 @@ impl TrustZoneCtrl {
                                 "unable to read derived key: {}",
                                 e
                             );
-                            bail!(TrustZoneCtrlError::new(
+                            let message = format!("unable to read derived key: {}", e);
+                            Err(e).context(TrustZoneCtrlError::new(
                                 TrustZoneCtrlErrorCodes::UnableToDeriveTrustZoneKey,
-                                format!("unable to read derived key: {}", e),
+                                message,
                             ))
                         }
                     }
@@ -599,16 +683,17 @@ impl TrustZoneCtrl {
             }
             Err(e) => {
                 trace_error!(task = "derive_trustzone_key", "unable to derive key: {}", e);
-                bail!(TrustZoneCtrlError::new(
+                let message = format!("unable to derive key: {}", e);
+                Err(e).context(TrustZoneCtrlError::new(
                     TrustZoneCtrlErrorCodes::UnableToDeriveTrustZoneKey,
-                    format!("unable to derive key: {}", e),
+                    message,
                 ))
             }
         }
     }
 
     //generate_trustzone_hmac we need to generate a hmac uisng trust ic and return it that will be type of string or error using match and anyhow error
-    pub fn generate_trustzone_hmac(
+    fn generate_trustzone_hmac(
         &self,
         secret_oid: &str,
         hmac_type: u16,
@@ -618,7 +703,7 @@ impl TrustZoneCtrl {
         trace!(task = "generate_trustzone_hmac", "init");
 
         let command_args = [
-            "/MECHA_TEST/optiga_trust_m/trustm_hmac",
+            "trustm_hmac",
             "-I",
             secret_oid,
             "-H",
@@ -629,15 +714,13 @@ impl TrustZoneCtrl {
             output_file,
         ];
 
-        let command_output = Command::new(&command_args[0])
-            .args(&command_args[1..])
-            .output()
-            .map_err(|e| {
-                TrustZoneCtrlError::new(
-                    TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneHMAC,
-                    format!("Error executing trustm_hmac: {}", e),
-                )
-            });
+        let command_output = self.run_tool(&command_args).map_err(|e| {
+            let message = format!("Error executing trustm_hmac: {}", e);
+            anyhow!(e).context(TrustZoneCtrlError::new(
+                TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneHMAC,
+                message,
+            ))
+        });
 
         match command_output {
             Ok(output) => {
@@ -660,9 +743,10 @@ impl TrustZoneCtrl {
                                 "unable to read generated HMAC: {}",
                                 e
                             );
-                            bail!(TrustZoneCtrlError::new(
+                            let message = format!("unable to read generated HMAC: {}", e);
+                            Err(e).context(TrustZoneCtrlError::new(
                                 TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneHMAC,
-                                format!("unable to read generated HMAC: {}", e),
+                                message,
                             ))
                         }
                     }
@@ -684,11 +768,22 @@ impl TrustZoneCtrl {
                     "unable to generate HMAC: {}",
                     e
                 );
-                bail!(TrustZoneCtrlError::new(
+                let message = format!("unable to generate HMAC: {}", e);
+                Err(e).context(TrustZoneCtrlError::new(
                     TrustZoneCtrlErrorCodes::UnableToGenerateTrustZoneHMAC,
-                    format!("unable to generate HMAC: {}", e),
+                    message,
                 ))
             }
         }
     }
 }
+
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut data = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut data);
+        }
+        data
+    })
+}
@@ -0,0 +1,41 @@
+use mecha_trustzone_ctrl::{TrustZoneControl, TrustZoneCtrl};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// a directory of trustm tools that all run `script`
+fn tools(name: &str, script: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mecha-trustm-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let tool = dir.join("trustm_cert");
+    std::fs::write(&tool, format!("#!/bin/sh\n{}\n", script)).unwrap();
+    std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+    dir
+}
+
+#[test]
+fn a_hung_tool_is_killed_after_the_timeout() {
+    let dir = tools("hung", "sleep 30");
+    let trustzone = TrustZoneCtrl {
+        path: dir.to_string_lossy().to_string(),
+        timeout: Some(Duration::from_millis(200)),
+    };
+
+    let started = Instant::now();
+    let e = trustzone.remove_trustzone_cert("0xE0E1").unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(format!("{:#}", e).contains("did not exit within"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn tools_finishing_in_time_are_not_killed() {
+    let dir = tools("quick", "echo removed");
+    let trustzone = TrustZoneCtrl {
+        path: dir.to_string_lossy().to_string(),
+        timeout: Some(Duration::from_secs(5)),
+    };
+
+    trustzone.remove_trustzone_cert("0xE0E1").unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}